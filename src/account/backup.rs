@@ -2,11 +2,11 @@ use futures::StreamExt;
 use matrix_sdk::encryption::recovery::EnableProgress;
 use tracing::{info, warn};
 
-use crate::init::singletons::CLIENT;
+use crate::init::singletons::get_client;
 
 /// Checks whether this account has secret backup setup
 pub async fn has_backup_setup() -> crate::Result<bool> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     client
         .encryption()
         .backups()
@@ -19,7 +19,7 @@ pub async fn has_backup_setup() -> crate::Result<bool> {
 pub async fn restore_backup_with_passphrase(passphrase: String) -> crate::Result<()> {
     info!("Restoring backup with passphrase");
 
-    let client = CLIENT.wait();
+    let client = get_client()?;
     client
         .encryption()
         .recovery()
@@ -44,7 +44,7 @@ pub async fn restore_backup_with_passphrase(passphrase: String) -> crate::Result
 
 /// Setup a new backup for secret keys
 pub async fn setup_new_backup() -> crate::Result<String> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     let recovery = client.encryption().recovery();
     let enable = recovery.enable().wait_for_backups_to_upload();
     let mut progress_stream = enable.subscribe_to_progress();
@@ -0,0 +1,219 @@
+//! TypeScript bindings of the serializable models, generated with [ts-rs](https://docs.rs/ts-rs).
+
+use std::{any::TypeId, collections::BTreeMap, collections::HashSet, path::Path};
+
+use ts_rs::{TS, TypeVisitor};
+
+use crate::{
+    Error, FrontendSearchResults, FrontendSyncServiceState, FrontendTimelineItem,
+    FrontendVerificationState, LoginState, MatrixRequest, RoomScreen, RoomScreenDiff, RoomsList,
+    RoomsListDiff, UserProfile,
+    events::timeline::PaginationDirection,
+    init::FrontendAuthTypeResponse,
+    models::{
+        events::{
+            FrontendDevice, FrontendRegistrationFlow, MatrixLoginPayload, MatrixRegistrationEvent,
+            MatrixRequestResponse, MatrixRoomStoreCreateRequest, MatrixUpdateCurrentActiveRoom,
+            MatrixVerificationEmojis, MatrixVerificationResponse, MediaStreamEvent,
+            OsNotificationRequest, QrLoginProgress, RegistrationStageRequest,
+            ToastNotificationRequest, VerifyDeviceEvent,
+        },
+        misc::{EditRoomInformationPayload, EditUserInformationPayload, FrontendComposerDraft},
+        profile::ProfileModel,
+    },
+};
+
+/// Write the TypeScript declarations of all the models exchanged with the frontend
+/// (states, events, command payloads and responses) to a single file.
+///
+/// Adapters can call it from a build script or a test, to regenerate the frontend types
+/// whenever this crate is updated.
+pub fn export_typescript_bindings(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let mut collector = DeclCollector::default();
+    // States
+    collector.visit::<RoomsList>();
+    collector.visit::<RoomsListDiff<'static>>();
+    collector.visit::<RoomScreen>();
+    collector.visit::<RoomScreenDiff<'static>>();
+    collector.visit::<LoginState>();
+    collector.visit::<FrontendVerificationState>();
+    collector.visit::<FrontendSyncServiceState>();
+    // Outgoing events
+    collector.visit::<MatrixVerificationEmojis>();
+    collector.visit::<ToastNotificationRequest>();
+    collector.visit::<OsNotificationRequest>();
+    collector.visit::<RegistrationStageRequest>();
+    collector.visit::<QrLoginProgress>();
+    collector.visit::<MatrixRoomStoreCreateRequest>();
+    collector.visit::<MediaStreamEvent>();
+    collector.visit::<VerifyDeviceEvent>();
+    collector.visit::<MatrixRequestResponse>();
+    // Incoming events
+    collector.visit::<MatrixVerificationResponse>();
+    collector.visit::<MatrixUpdateCurrentActiveRoom>();
+    collector.visit::<MatrixLoginPayload>();
+    collector.visit::<MatrixRegistrationEvent>();
+    collector.visit::<PaginationDirection>();
+    collector.visit::<MatrixRequest>();
+    // Commands
+    collector.visit::<Error>();
+    collector.visit::<FrontendAuthTypeResponse>();
+    collector.visit::<FrontendRegistrationFlow>();
+    collector.visit::<FrontendDevice>();
+    collector.visit::<FrontendTimelineItem>();
+    collector.visit::<UserProfile>();
+    collector.visit::<ProfileModel>();
+    collector.visit::<EditUserInformationPayload>();
+    collector.visit::<EditRoomInformationPayload>();
+    collector.visit::<FrontendComposerDraft>();
+    collector.visit::<FrontendSearchResults>();
+
+    let mut bindings = format!(
+        "// Generated by matrix-ui-serializable {}. Do not edit this file manually.\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for decl in collector.decls.values() {
+        bindings.push('\n');
+        bindings.push_str(decl);
+        bindings.push('\n');
+    }
+    std::fs::write(path, bindings)
+}
+
+/// Collects the declarations of the visited types and of their dependencies, by name.
+#[derive(Default)]
+struct DeclCollector {
+    seen: HashSet<TypeId>,
+    decls: BTreeMap<String, String>,
+}
+
+impl TypeVisitor for DeclCollector {
+    fn visit<T: TS + 'static + ?Sized>(&mut self) {
+        // Primitives and wrappers such as `Option` or `Vec` cannot be declared.
+        if T::output_path().is_none() || !self.seen.insert(TypeId::of::<T>()) {
+            return;
+        }
+        self.decls
+            .entry(T::ident())
+            .or_insert_with(|| format!("{}export {}", T::docs().unwrap_or_default(), T::decl()));
+        T::visit_dependencies(self);
+        T::visit_generics(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    /// The names that TypeScript itself declares.
+    const BUILTINS: &[&str] = &[
+        "Array",
+        "Record",
+        "string",
+        "number",
+        "boolean",
+        "bigint",
+        "null",
+        "undefined",
+        "unknown",
+        "never",
+        "any",
+        "object",
+        "true",
+        "false",
+        "key",
+        "in",
+        "export",
+        "type",
+    ];
+
+    /// Blanks out the comments and string literals, which may contain any word.
+    fn strip_comments_and_strings(bindings: &str) -> String {
+        let mut stripped = String::with_capacity(bindings.len());
+        let mut chars = bindings.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('/', Some('*')) => {
+                    let mut prev = ' ';
+                    for c in chars.by_ref() {
+                        if prev == '*' && c == '/' {
+                            break;
+                        }
+                        prev = c;
+                    }
+                    stripped.push(' ');
+                }
+                ('/', Some('/')) => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                    stripped.push('\n');
+                }
+                ('"', _) => {
+                    let mut escaped = false;
+                    for c in chars.by_ref() {
+                        match c {
+                            '"' if !escaped => break,
+                            '\\' => escaped = !escaped,
+                            _ => escaped = false,
+                        }
+                    }
+                    stripped.push_str("\"\"");
+                }
+                _ => stripped.push(c),
+            }
+        }
+        stripped
+    }
+
+    /// Splits the bindings into identifiers, along with the character following each of them.
+    fn identifiers(bindings: &str) -> Vec<(&str, Option<char>)> {
+        let is_ident_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+        let mut identifiers = Vec::new();
+        let mut rest = bindings;
+        while let Some(start) = rest.find(|c: char| is_ident_char(c)) {
+            rest = &rest[start..];
+            let end = rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+            let (ident, after) = rest.split_at(end);
+            if !ident.starts_with(|c: char| c.is_ascii_digit()) {
+                identifiers.push((ident, after.trim_start().chars().next()));
+            }
+            rest = after;
+        }
+        identifiers
+    }
+
+    #[test]
+    fn bindings_declare_every_referenced_type() {
+        let path = std::env::temp_dir().join(format!("bindings-{}.ts", std::process::id()));
+        super::export_typescript_bindings(&path).unwrap();
+        let bindings = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let bindings = strip_comments_and_strings(&bindings);
+
+        let mut declared: HashSet<&str> = BUILTINS.iter().copied().collect();
+        let mut referenced = Vec::new();
+        for decl in bindings.split("export type ").skip(1) {
+            let (header, body) = decl.split_once('=').expect("a type declaration");
+            // The name of the type, then its generic parameters.
+            declared.extend(identifiers(header).into_iter().map(|(ident, _)| ident));
+            referenced.extend(
+                identifiers(body)
+                    .into_iter()
+                    // Skip the field names.
+                    .filter(|(_, next)| !matches!(next, Some(':' | '?')))
+                    .map(|(ident, _)| ident),
+            );
+        }
+
+        let mut unresolved: Vec<_> = referenced
+            .into_iter()
+            .filter(|ident| !declared.contains(ident))
+            .collect();
+        unresolved.sort_unstable();
+        unresolved.dedup();
+        assert!(unresolved.is_empty(), "undeclared types: {unresolved:?}");
+    }
+}
@@ -8,40 +8,51 @@ use crate::{
         login::build_client,
         singletons::{
             CLIENT, CURRENT_USER_ID, HAS_SESSION_STORED, TEMP_CLIENT, TEMP_CLIENT_SESSION,
-            get_event_bridge,
+            get_client, get_event_bridge,
         },
     },
     models::{
         async_requests::MatrixRequest,
-        events::{EmitEvent, FrontendDevice},
-        misc::{EditRoomInformationPayload, EditUserInformationPayload},
+        events::{EmitEvent, FrontendDevice, FrontendRegistrationFlow},
+        misc::{
+            EditRoomInformationPayload, EditUserInformationPayload, FrontendComposerDraft,
+            FrontendComposerDraftType,
+        },
+        profile::ProfileModel,
         state_updater::StateUpdater,
     },
     room::{
         frontend_events::events_dto::{FrontendTimelineItem, map_event_timeline_item},
-        joined_room::get_timeline,
+        joined_room::{draft_preview, get_timeline},
+        link_preview::url_previews_enabled,
         rooms_list::{RoomsListUpdate, enqueue_rooms_list_update},
+        search::{FrontendSearchResults, search_on_server, search_room_locally},
     },
     user::{user_power_level::UserPowerLevels, user_profile::UserProfile},
-    utils::guess_device_type,
+    utils::{guess_device_type, voice_waveform},
 };
 use anyhow::anyhow;
 use matrix_sdk_ui::timeline::{AttachmentConfig, AttachmentSource};
 use mime::Mime;
 use rand::{RngExt, distr::Alphanumeric, rng};
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tracing::info;
 use url::Url;
 
-pub use crate::{init::FrontendAuthTypeResponse, models::events::VerifyDeviceEvent};
+pub use crate::{
+    init::{FrontendAuthTypeResponse, FrontendIdentityProvider},
+    models::events::VerifyDeviceEvent,
+};
 pub use matrix_sdk::ruma::{
     MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedEventId, OwnedRoomId, OwnedUserId, UInt, UserId,
 };
 use matrix_sdk::{
-    attachment::{AttachmentInfo, Thumbnail},
+    RoomMemberships,
+    attachment::{AttachmentInfo, BaseAudioInfo, Thumbnail},
     encryption::CrossSigningResetAuthType,
+    room::RoomMember,
     ruma::{
-        DeviceId, OwnedMxcUri,
+        DeviceId, OwnedMxcUri, RoomId,
         api::client::uiaa::{self, MatrixUserIdentifier, UserIdentifier},
         events::room::message::TextMessageEventContent,
     },
@@ -65,11 +76,49 @@ pub async fn build_temp_client_from_homeserver_url(homeserver: String) -> crate:
     Ok(())
 }
 
+/// Build the client from a QR code scanned on this new device, displayed by an already logged in
+/// device. Like `build_temp_client_from_homeserver_url`, the init process can then proceed:
+/// the OAuth login step will use the QR code instead of the browser flow.
+pub async fn build_temp_client_from_qr_code(qr_code: Vec<u8>) -> crate::Result<()> {
+    let homeserver = crate::init::qr_login::set_scanned_qr_code(&qr_code)?;
+    build_temp_client_from_homeserver_url(homeserver).await
+}
+
+/// Generate a QR code to let a new device log in to the current account.
+/// The QR code and the progress are emitted as `EmitEvent::QrLoginProgress`, this resolves
+/// once the new device is logged in.
+pub async fn grant_login_with_qr_code() -> crate::Result<()> {
+    Ok(crate::init::qr_login::grant_login_with_generated_qr_code().await?)
+}
+
+/// Submit the check code displayed by the new device during a QR code login grant.
+pub async fn submit_qr_login_check_code(check_code: u8) -> crate::Result<()> {
+    Ok(crate::init::qr_login::submit_qr_login_check_code(check_code).await?)
+}
+
 pub async fn check_homeserver_auth_type() -> crate::Result<FrontendAuthTypeResponse> {
     let (auth_type, _) = crate::init::check_homeserver_auth_type().await?;
     Ok(auth_type)
 }
 
+/// Build the URL to log in with the legacy SSO flow through the given identity provider,
+/// as listed by `check_homeserver_auth_type`. The `redirect_url` must be the OAuth redirect URI
+/// passed to the `LibConfig`, since the callback is received on the OAuth deeplink receiver.
+pub async fn get_sso_login_url(redirect_url: Url, idp_id: Option<String>) -> crate::Result<String> {
+    let (_, client) = crate::init::check_homeserver_auth_type().await?;
+    Ok(client
+        .matrix_auth()
+        .get_sso_login_url(redirect_url.as_str(), idp_id.as_deref())
+        .await?)
+}
+
+/// Query the registration flows of the homeserver set with `build_temp_client_from_homeserver_url`.
+/// The registration itself is driven through the `matrix_registration_receiver` events.
+pub async fn get_registration_flows() -> crate::Result<Vec<FrontendRegistrationFlow>> {
+    let (_, client) = crate::init::check_homeserver_auth_type().await?;
+    Ok(crate::init::registration::query_registration_flows(&client).await?)
+}
+
 /// Submit a request to the Matrix Client that will be executed asynchronously.
 pub fn submit_async_request(request: MatrixRequest) {
     crate::models::async_requests::submit_async_request(request);
@@ -89,7 +138,7 @@ pub async fn fetch_user_profile(
 
 /// Get the list of this user's account registered devices.
 pub async fn get_devices(user_id: &UserId) -> crate::Result<Vec<FrontendDevice>> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     let devices_list = client.devices().await.map_err(anyhow::Error::from)?;
     let devices: Vec<FrontendDevice> = client
         .encryption()
@@ -141,10 +190,58 @@ pub async fn setup_new_backup() -> crate::Result<String> {
 }
 
 pub fn get_dm_room_from_user_id(user_id: &UserId) -> crate::Result<Option<OwnedRoomId>> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     Ok(client.get_dm_room(user_id).map(|r| r.room_id().to_owned()))
 }
 
+/// Autocomplete the mentions typed in the given room.
+///
+/// Returns the joined members of the room known locally (as in `RoomScreen.members`) whose
+/// display name or user ID matches the `query`, the ones starting with it first.
+/// The current user is never suggested.
+pub async fn search_room_members(
+    room_id: &RoomId,
+    query: &str,
+    limit: usize,
+) -> crate::Result<Vec<ProfileModel>> {
+    let client = get_client()?;
+    let room = client
+        .get_room(room_id)
+        .ok_or(crate::Error::NotFound(format!("Room {room_id} not found")))?;
+    let own_user_id = CURRENT_USER_ID.get();
+    let query = query.trim_start_matches('@').to_lowercase();
+
+    let mut matches: Vec<(bool, RoomMember)> = room
+        .members_no_sync(RoomMemberships::JOIN)
+        .await?
+        .into_iter()
+        .filter(|member| own_user_id.as_deref() != Some(member.user_id()))
+        .filter_map(|member| {
+            let name = member.name().to_lowercase();
+            let user_id = member.user_id().as_str().to_lowercase();
+            let starts_with_query = name.split_whitespace().any(|word| word.starts_with(&query))
+                || user_id[1..].starts_with(&query);
+            (starts_with_query || name.contains(&query) || user_id.contains(&query))
+                .then_some((starts_with_query, member))
+        })
+        .collect();
+    matches.sort_by(|(a_starts, a), (b_starts, b)| {
+        b_starts
+            .cmp(a_starts)
+            .then_with(|| a.name().to_lowercase().cmp(&b.name().to_lowercase()))
+    });
+
+    Ok(matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, member)| ProfileModel {
+            user_id: member.user_id().to_owned(),
+            display_name: member.display_name().map(ToOwned::to_owned),
+            avatar_url: member.avatar_url().map(ToOwned::to_owned),
+        })
+        .collect())
+}
+
 /// Start the SAS V1 Emoji verification process with another user's device.
 pub async fn verify_device(
     user_id: OwnedUserId,
@@ -159,14 +256,26 @@ pub async fn verify_device(
 
 /// Disconnect the connected user
 pub async fn disconnect_user() -> crate::Result<()> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     // Logout the session
     client.logout().await.map_err(|e| e.into())
 }
 
+/// Log out the connected user and tear down its whole session.
+///
+/// The access token is revoked, the sync service stopped and the local database deleted.
+/// The adapter is then asked to forget the persisted session through
+/// `StateUpdater::clear_login_session`, and the login state goes back to
+/// `AwaitingForHomeserver`, so another account can log in without restarting the app.
+pub async fn logout() -> crate::Result<()> {
+    crate::init::logout::logout_and_teardown()
+        .await
+        .map_err(Into::into)
+}
+
 /// Disconnect the connected user
 pub async fn check_if_last_device() -> crate::Result<bool> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     client
         .encryption()
         .recovery()
@@ -181,12 +290,16 @@ pub fn is_logged_in() -> bool {
     CLIENT.get().is_some()
 }
 
+/// Whether the adapter had a session stored when the current session started, or `false`
+/// after a logout.
+///
+/// This blocks until the session has been restored or not, at startup.
 pub fn has_session_stored() -> bool {
-    *HAS_SESSION_STORED.wait()
+    HAS_SESSION_STORED.wait()
 }
 
 pub async fn reset_cross_signing(password: Option<String>) -> crate::Result<()> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     let encryption = client.encryption();
     if let Some(handle) = encryption
         .recovery()
@@ -232,7 +345,7 @@ pub async fn edit_user_information(
     payload: EditUserInformationPayload,
     updater: Arc<Box<dyn StateUpdater>>,
 ) -> crate::Result<()> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     let account_manager = client.account();
     if let Some(ref display_name) = payload.new_display_name {
         account_manager.set_display_name(Some(display_name)).await?;
@@ -253,7 +366,7 @@ pub async fn edit_user_information(
 }
 
 pub async fn rename_device(device_id: &DeviceId, display_name: &str) -> crate::Result<()> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     match client.rename_device(device_id, display_name).await {
         Ok(_) => Ok(()),
         Err(err) => Err(crate::Error::Anyhow(anyhow!(err))),
@@ -261,7 +374,7 @@ pub async fn rename_device(device_id: &DeviceId, display_name: &str) -> crate::R
 }
 
 pub async fn upload_media(content_type: Mime, data: Vec<u8>) -> crate::Result<OwnedMxcUri> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     let res = client.media().upload(&content_type, data, None).await?;
     Ok(res.content_uri)
 }
@@ -271,10 +384,12 @@ pub fn filter_room_list(keywords: String) {
 }
 
 pub async fn define_room_informations(payload: EditRoomInformationPayload) -> crate::Result<()> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     let room = client
         .get_room(&payload.room_id)
-        .ok_or(anyhow!("Couldn't get room for given id"))?;
+        .ok_or(crate::Error::NotFound(
+            "Couldn't get room for given id".to_owned(),
+        ))?;
     if let Some(uri) = payload.new_avatar_uri {
         room.set_avatar_url(&uri, None).await?;
     }
@@ -287,8 +402,102 @@ pub async fn define_room_informations(payload: EditRoomInformationPayload) -> cr
     Ok(())
 }
 
+/// Saves the composer draft of the given room, or of the given thread if `thread_root_event_id`
+/// is set. An empty new message draft clears the stored one.
+pub async fn save_composer_draft(
+    room_id: OwnedRoomId,
+    thread_root_event_id: Option<OwnedEventId>,
+    draft: FrontendComposerDraft,
+) -> crate::Result<()> {
+    let client = get_client()?;
+    let room = client.get_room(&room_id).ok_or(crate::Error::NotFound(
+        "Couldn't get room for given id".to_owned(),
+    ))?;
+    let preview = draft_preview(&draft.text);
+    if preview.is_none() && matches!(draft.draft_type, FrontendComposerDraftType::NewMessage) {
+        return clear_composer_draft(room_id, thread_root_event_id).await;
+    }
+    room.save_composer_draft(draft.into(), thread_root_event_id.as_deref())
+        .await?;
+    if thread_root_event_id.is_none() {
+        enqueue_rooms_list_update(RoomsListUpdate::UpdateDraft {
+            room_id,
+            draft: preview,
+        });
+    }
+    Ok(())
+}
+
+/// Loads the composer draft of the given room, or of the given thread if `thread_root_event_id` is set.
+pub async fn load_composer_draft(
+    room_id: OwnedRoomId,
+    thread_root_event_id: Option<OwnedEventId>,
+) -> crate::Result<Option<FrontendComposerDraft>> {
+    let client = get_client()?;
+    let room = client.get_room(&room_id).ok_or(crate::Error::NotFound(
+        "Couldn't get room for given id".to_owned(),
+    ))?;
+    let draft = room
+        .load_composer_draft(thread_root_event_id.as_deref())
+        .await?;
+    Ok(draft.map(FrontendComposerDraft::from))
+}
+
+/// Clears the composer draft of the given room, or of the given thread if `thread_root_event_id` is set.
+/// It should be called once the message is sent.
+pub async fn clear_composer_draft(
+    room_id: OwnedRoomId,
+    thread_root_event_id: Option<OwnedEventId>,
+) -> crate::Result<()> {
+    let client = get_client()?;
+    let room = client.get_room(&room_id).ok_or(crate::Error::NotFound(
+        "Couldn't get room for given id".to_owned(),
+    ))?;
+    room.clear_composer_draft(thread_root_event_id.as_deref())
+        .await?;
+    if thread_root_event_id.is_none() {
+        enqueue_rooms_list_update(RoomsListUpdate::UpdateDraft {
+            room_id,
+            draft: None,
+        });
+    }
+    Ok(())
+}
+
+/// Searches the message history, in the given room or in all the joined rooms.
+///
+/// Unencrypted rooms are searched by the homeserver. Encrypted rooms are searched locally,
+/// among the events already stored in the event cache, so they must be searched one by one.
+/// Pass the `next_batch` token of the previous results to get the next page.
+/// Use the `JumpToEvent` request to show a recent result in the room's timeline,
+/// or open the room on a timeline focused on an older one.
+pub async fn search_messages(
+    room_id: Option<OwnedRoomId>,
+    search_term: String,
+    next_batch: Option<String>,
+) -> crate::Result<FrontendSearchResults> {
+    let client = get_client()?;
+    let Some(room_id) = room_id else {
+        return search_on_server(&client, None, search_term, next_batch).await;
+    };
+    let room = client.get_room(&room_id).ok_or(crate::Error::NotFound(
+        "Couldn't get room for given id".to_owned(),
+    ))?;
+    if room.latest_encryption_state().await?.is_encrypted() {
+        search_room_locally(&client, &room, search_term, next_batch).await
+    } else {
+        search_on_server(&client, Some(vec![room_id]), search_term, next_batch).await
+    }
+}
+
+/// Returns whether URL previews are shown in the given room.
+/// Use the `SetUrlPreviewsEnabled` request to change it.
+pub fn get_url_previews_enabled(room_id: OwnedRoomId) -> bool {
+    url_previews_enabled(&room_id)
+}
+
 pub fn get_dm_room_id_or_create_it(user_id: OwnedUserId) -> Option<OwnedRoomId> {
-    let client = CLIENT.wait();
+    let client = get_client().ok()?;
     let res = client
         .get_dm_room(&user_id)
         .map(|room| room.room_id().to_owned());
@@ -307,14 +516,16 @@ pub async fn get_event_from_main_timeline(
     event_id: OwnedEventId,
 ) -> crate::Result<FrontendTimelineItem> {
     let kind = TimelineKind::MainRoom { room_id };
-    let timeline = get_timeline(&kind).ok_or(anyhow!("Cannot get timeline"))?;
+    let timeline =
+        get_timeline(&kind).ok_or(crate::Error::NotFound("Cannot get timeline".to_owned()))?;
 
+    let own_user_id = CURRENT_USER_ID.get().ok_or(crate::Error::NotLoggedIn)?;
     let pl = timeline.room().power_levels_or_default().await;
 
     let event = timeline
         .item_by_event_id(&event_id)
         .await
-        .ok_or(anyhow!("Event not found"))?;
+        .ok_or(crate::Error::NotFound("Event not found".to_owned()))?;
 
     let unique_id: String = rng()
         .sample_iter(Alphanumeric)
@@ -326,7 +537,7 @@ pub async fn get_event_from_main_timeline(
         unique_id,
         &event,
         &kind,
-        &UserPowerLevels::from(&pl, CURRENT_USER_ID.get().unwrap()),
+        &UserPowerLevels::from(&pl, &own_user_id),
     )
     .ok_or(anyhow!("This item cannot be mapped to a frontend struct"))?)
 }
@@ -343,8 +554,8 @@ pub async fn send_media_message(
     info: AttachmentInfo,
     thumbnail: Option<Thumbnail>,
 ) -> crate::Result<()> {
-    let timeline = get_timeline(&get_timeline_kind(room_id, thread_root))
-        .ok_or(anyhow!("Cannot get timeline"))?;
+    let timeline = get_timeline(&get_timeline_kind(room_id, thread_root, None))
+        .ok_or(crate::Error::NotFound("Cannot get timeline".to_owned()))?;
 
     let source = AttachmentSource::Data {
         bytes: buffer,
@@ -366,6 +577,41 @@ pub async fn send_media_message(
         .map_err(Into::into)
 }
 
+/// Sends a recorded voice message, flagged as such (MSC3245) so that clients display it
+/// with its waveform instead of as an audio file.
+///
+/// The `amplitudes` are the input levels sampled while recording, in any unit.
+/// They are downsampled and normalized into the waveform of the message.
+#[allow(clippy::too_many_arguments)]
+pub async fn send_voice_message(
+    room_id: OwnedRoomId,
+    thread_root: Option<OwnedEventId>,
+    buffer: Vec<u8>,
+    filename: String,
+    mime_type: Mime,
+    in_reply_to: Option<OwnedEventId>,
+    duration_ms: u64,
+    amplitudes: Vec<f32>,
+) -> crate::Result<()> {
+    let info = AttachmentInfo::Voice(BaseAudioInfo {
+        duration: Some(Duration::from_millis(duration_ms)),
+        size: UInt::new(buffer.len() as u64),
+        waveform: Some(voice_waveform(&amplitudes)),
+    });
+    send_media_message(
+        room_id,
+        thread_root,
+        buffer,
+        filename,
+        mime_type,
+        None,
+        in_reply_to,
+        info,
+        None,
+    )
+    .await
+}
+
 pub async fn register_notifications(
     _token: String,
     _user_language: String,
@@ -373,7 +619,7 @@ pub async fn register_notifications(
     _ios_sygnal_url: Url,
     _app_id: String,
 ) -> anyhow::Result<()> {
-    let client = CLIENT.wait();
+    let client = get_client()?;
     #[cfg(any(target_os = "android", target_os = "ios"))]
     crate::room::notifications::register_mobile_push_notifications(
         &client,
@@ -385,7 +631,7 @@ pub async fn register_notifications(
     )
     .await?;
     #[cfg(not(any(target_os = "android", target_os = "ios")))]
-    crate::room::notifications::register_os_desktop_notifications(client).await;
+    crate::room::notifications::register_os_desktop_notifications(&client).await;
 
     Ok(())
 }
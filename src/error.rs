@@ -0,0 +1,270 @@
+use matrix_sdk::{
+    HttpError,
+    encryption::{recovery::RecoveryError, secret_storage::SecretStorageError},
+    ruma::{IdParseError, api::error::Error as ClientApiError},
+};
+use serde::{Serialize, ser::Serializer};
+
+/// matrix-ui-serializable Error enum
+///
+/// It is serialized as an object with a stable machine-readable `code`, the Matrix `errcode`
+/// returned by the homeserver when present, and a human readable `message`.
+#[derive(Debug, thiserror::Error)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS), ts(as = "SerializedError"))]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Anyhow(#[from] anyhow::Error),
+    #[error(transparent)]
+    MatrixSdk(#[from] matrix_sdk::Error),
+    /// The requested room, event or timeline doesn't exist locally.
+    #[error("{0}")]
+    NotFound(String),
+    /// The input given by the frontend is invalid.
+    #[error("{0}")]
+    InvalidInput(String),
+    /// No user is logged in, e.g. after a logout.
+    #[error("No user is logged in")]
+    NotLoggedIn,
+}
+
+/// The category of an [`Error`], for the frontend to react accordingly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The homeserver couldn't be reached.
+    Network,
+    /// The access token is missing, invalid or expired.
+    Unauthorized,
+    /// The user is not allowed to perform this action.
+    Forbidden,
+    NotFound,
+    RateLimited,
+    InvalidInput,
+    /// An encryption error, such as a wrong recovery passphrase.
+    Crypto,
+    /// An error with the local database or the file system.
+    Storage,
+    Unknown,
+}
+
+impl Error {
+    /// The machine-readable category of this error.
+    pub fn code(&self) -> ErrorCode {
+        self.details().0
+    }
+
+    /// The Matrix `errcode` returned by the homeserver (such as `M_FORBIDDEN`), if any.
+    pub fn errcode(&self) -> Option<String> {
+        self.details().1
+    }
+
+    fn details(&self) -> (ErrorCode, Option<String>) {
+        match self {
+            Error::Io(_) => (ErrorCode::Storage, None),
+            Error::MatrixSdk(e) => sdk_error_details(e),
+            Error::NotFound(_) => (ErrorCode::NotFound, None),
+            Error::InvalidInput(_) => (ErrorCode::InvalidInput, None),
+            Error::NotLoggedIn => (ErrorCode::Unauthorized, None),
+            // Most errors are wrapped in an `anyhow::Error`, look for a known error in the chain.
+            Error::Anyhow(e) => e
+                .chain()
+                .find_map(std_error_details)
+                .unwrap_or((ErrorCode::Unknown, None)),
+        }
+    }
+}
+
+fn std_error_details(e: &(dyn std::error::Error + 'static)) -> Option<(ErrorCode, Option<String>)> {
+    if let Some(e) = e.downcast_ref::<Error>() {
+        Some(e.details())
+    } else if let Some(e) = e.downcast_ref::<matrix_sdk::Error>() {
+        Some(sdk_error_details(e))
+    } else if let Some(e) = e.downcast_ref::<HttpError>() {
+        Some(http_error_details(e))
+    } else if let Some(e) = e.downcast_ref::<ClientApiError>() {
+        Some(client_api_error_details(e))
+    } else if let Some(e) = e.downcast_ref::<RecoveryError>() {
+        Some(match e {
+            RecoveryError::Sdk(e) => sdk_error_details(e),
+            RecoveryError::SecretStorage(SecretStorageError::Sdk(e)) => sdk_error_details(e),
+            _ => (ErrorCode::Crypto, None),
+        })
+    } else if let Some(e) = e.downcast_ref::<SecretStorageError>() {
+        Some(match e {
+            SecretStorageError::Sdk(e) => sdk_error_details(e),
+            _ => (ErrorCode::Crypto, None),
+        })
+    } else if e.is::<std::io::Error>() {
+        Some((ErrorCode::Storage, None))
+    } else if e.is::<IdParseError>() || e.is::<url::ParseError>() {
+        Some((ErrorCode::InvalidInput, None))
+    } else {
+        None
+    }
+}
+
+fn sdk_error_details(e: &matrix_sdk::Error) -> (ErrorCode, Option<String>) {
+    use matrix_sdk::Error as SdkError;
+
+    match e {
+        SdkError::Http(e) => http_error_details(e),
+        SdkError::AuthenticationRequired => (ErrorCode::Unauthorized, None),
+        SdkError::BadCryptoStoreState
+        | SdkError::NoOlmMachine
+        | SdkError::CryptoStoreError(_)
+        | SdkError::OlmError(_)
+        | SdkError::MegolmError(_)
+        | SdkError::DecryptorError(_)
+        | SdkError::BackupNotEnabled => (ErrorCode::Crypto, None),
+        SdkError::Io(_)
+        | SdkError::StateStore(_)
+        | SdkError::EventCacheStore(_)
+        | SdkError::MediaStore(_)
+        | SdkError::CrossProcessLockError(_) => (ErrorCode::Storage, None),
+        SdkError::Identifier(_)
+        | SdkError::Url(_)
+        | SdkError::UserTagName(_)
+        | SdkError::WrongRoomState(_) => (ErrorCode::InvalidInput, None),
+        SdkError::PowerLevels(_) => (ErrorCode::Forbidden, None),
+        SdkError::Timeout => (ErrorCode::Network, None),
+        _ => (ErrorCode::Unknown, None),
+    }
+}
+
+fn http_error_details(e: &HttpError) -> (ErrorCode, Option<String>) {
+    match e {
+        HttpError::Reqwest(_) => (ErrorCode::Network, None),
+        HttpError::RefreshToken(_) => (ErrorCode::Unauthorized, None),
+        HttpError::Cached(e) => http_error_details(e),
+        _ => e
+            .as_client_api_error()
+            .map(client_api_error_details)
+            .unwrap_or((ErrorCode::Unknown, None)),
+    }
+}
+
+fn client_api_error_details(e: &ClientApiError) -> (ErrorCode, Option<String>) {
+    let code = match e.status_code.as_u16() {
+        400 | 413 => ErrorCode::InvalidInput,
+        401 => ErrorCode::Unauthorized,
+        403 => ErrorCode::Forbidden,
+        404 => ErrorCode::NotFound,
+        429 => ErrorCode::RateLimited,
+        502..=504 => ErrorCode::Network,
+        _ => ErrorCode::Unknown,
+    };
+    let errcode = e.error_kind().map(|kind| kind.errcode().to_string());
+    (code, errcode)
+}
+
+/// The serialized shape of an [`Error`], which can be cloned to be sent in events.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+pub(crate) struct SerializedError {
+    code: ErrorCode,
+    errcode: Option<String>,
+    message: String,
+}
+
+impl From<&Error> for SerializedError {
+    fn from(error: &Error) -> Self {
+        let (code, errcode) = error.details();
+        Self {
+            code,
+            errcode,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SerializedError::from(self).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use matrix_sdk::ruma::{
+        api::error::{
+            ErrorBody, ErrorKind, FromHttpResponseError, LimitExceededErrorData, StandardErrorBody,
+        },
+        exports::http::StatusCode,
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn serialize(error: Error) -> Value {
+        serde_json::to_value(error).unwrap()
+    }
+
+    fn api_error(status_code: StatusCode, kind: ErrorKind) -> matrix_sdk::Error {
+        let body = ErrorBody::Standard(StandardErrorBody::new(kind, "Denied".to_owned()));
+        HttpError::from(FromHttpResponseError::Server(ClientApiError::new(
+            status_code,
+            body,
+        )))
+        .into()
+    }
+
+    #[test]
+    fn not_found() {
+        assert_eq!(
+            serialize(Error::NotFound("Room not found".to_owned())),
+            json!({ "code": "not_found", "errcode": null, "message": "Room not found" })
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            serialize(Error::InvalidInput("Invalid event ID".to_owned())),
+            json!({ "code": "invalid_input", "errcode": null, "message": "Invalid event ID" })
+        );
+    }
+
+    #[test]
+    fn not_logged_in() {
+        assert_eq!(
+            serialize(Error::NotLoggedIn),
+            json!({ "code": "unauthorized", "errcode": null, "message": "No user is logged in" })
+        );
+    }
+
+    #[test]
+    fn sdk_error_keeps_the_homeserver_errcode() {
+        let error = Error::from(api_error(StatusCode::FORBIDDEN, ErrorKind::Forbidden));
+        let serialized = serialize(error);
+        assert_eq!(serialized["code"], "forbidden");
+        assert_eq!(serialized["errcode"], "M_FORBIDDEN");
+    }
+
+    #[test]
+    fn sdk_error_wrapped_in_anyhow() {
+        let error = anyhow::Error::from(api_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::LimitExceeded(LimitExceededErrorData::new()),
+        ))
+        .context("Failed to send the message");
+        let serialized = serialize(Error::from(error));
+        assert_eq!(serialized["code"], "rate_limited");
+        assert_eq!(serialized["errcode"], "M_LIMIT_EXCEEDED");
+        assert_eq!(serialized["message"], "Failed to send the message");
+    }
+
+    #[test]
+    fn unknown_anyhow_error() {
+        let serialized = serialize(Error::from(anyhow::anyhow!("Something went wrong")));
+        assert_eq!(
+            serialized,
+            json!({ "code": "unknown", "errcode": null, "message": "Something went wrong" })
+        );
+    }
+}
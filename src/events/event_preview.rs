@@ -226,7 +226,12 @@ pub fn text_preview_of_message(
 ) -> TextPreview {
     let text = match message.msgtype() {
         MessageType::Audio(audio) => format!(
-            "[Audio]: <i>{}</i>",
+            "{}: <i>{}</i>",
+            if audio.voice.is_some() {
+                "[Voice message]"
+            } else {
+                "[Audio]"
+            },
             if let Some(formatted_body) = audio.formatted.as_ref() {
                 Cow::Borrowed(formatted_body.body.as_str())
             } else {
@@ -5,19 +5,26 @@ use matrix_sdk::{
     ruma::{
         MilliSecondsSinceUnixEpoch,
         events::{
+            AnyRoomAccountDataEvent,
             key::verification::request::ToDeviceKeyVerificationRequestEvent,
             room::{
                 member::OriginalSyncRoomMemberEvent,
                 message::{MessageType, OriginalSyncRoomMessageEvent},
             },
         },
+        serde::Raw,
     },
 };
 use matrix_sdk_ui::timeline::{LatestEventValue, Profile, TimelineDetails};
 use tokio::runtime::Handle;
 use tracing::warn;
 
-use crate::init::singletons::MEMBERSHIP_UPDATES_EXPIRY_MAP;
+use crate::{
+    init::singletons::MEMBERSHIP_UPDATES_EXPIRY_MAP,
+    room::link_preview::{
+        ROOM_SETTINGS_EVENT_TYPE, URL_PREVIEWS_EVENT_TYPE, reload_url_previews_setting,
+    },
+};
 
 use super::{
     emoji_verification::request_verification_handler, event_preview::text_preview_of_timeline_item,
@@ -78,6 +85,17 @@ pub fn add_event_handlers(client: &Client) {
         // from too large rooms we may haven't seen yet.
         MEMBERSHIP_UPDATES_EXPIRY_MAP.insert(room.room_id().to_owned(), Duration::from_millis(200));
     });
+
+    // URL previews can be shown or hidden from another client of the user.
+    client.add_event_handler(|ev: Raw<AnyRoomAccountDataEvent>, room: Room| async move {
+        let event_type = ev.get_field::<String>("type").ok().flatten();
+        if matches!(
+            event_type.as_deref(),
+            Some(URL_PREVIEWS_EVENT_TYPE | ROOM_SETTINGS_EVENT_TYPE)
+        ) {
+            reload_url_previews_setting(&room).await;
+        }
+    });
 }
 
 /// Returns the timestamp and text preview of the given `latest_event` timeline item.
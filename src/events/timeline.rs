@@ -8,13 +8,14 @@ use futures::StreamExt;
 use matrix_sdk::{
     Room, SuccessorRoom,
     room::RoomMember,
-    ruma::{OwnedEventId, OwnedRoomId, events::receipt::Receipt},
+    ruma::{OwnedEventId, OwnedRoomId, OwnedUserId, events::receipt::Receipt},
 };
 use matrix_sdk_ui::{
     Timeline,
     eyeball_im::{Vector, VectorDiff},
     timeline::{
-        self, LatestEventValue, RoomExt, TimelineDetails, TimelineEventItemId, TimelineItem,
+        self, LatestEventValue, RoomExt, TimelineDetails, TimelineEventItemId, TimelineFocus,
+        TimelineItem,
     },
 };
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
@@ -25,12 +26,19 @@ use crate::{
     events::{
         event_preview::text_preview_of_timeline_item, handlers::get_sender_username_from_profile,
     },
-    init::singletons::{CLIENT, UIUpdateMessage, broadcast_event},
-    models::async_requests::{MatrixRequest, submit_async_request},
+    init::singletons::{UIUpdateMessage, broadcast_event},
+    models::{
+        async_requests::{MatrixRequest, submit_async_request},
+        diff::DiffOp,
+    },
     room::{
-        frontend_events::events_dto::{FrontendTimelineItem, to_frontend_timeline_item},
+        frontend_events::{
+            events_dto::{FrontendTimelineItem, to_frontend_timeline_item},
+            shield::FrontendShieldState,
+        },
         joined_room::UnreadMessageCount,
         rooms_list::{RoomsListUpdate, enqueue_rooms_list_update},
+        threads_list::{FrontendThreadRoot, FrontendThreadsListFilter},
     },
     user::user_power_level::UserPowerLevels,
 };
@@ -42,6 +50,7 @@ use crate::{
 /// * `Backwards`: the more typical choice, in which earlier events are retrieved
 ///   (towards the start of the timeline), which works in  both live mode and focused mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum PaginationDirection {
     Forwards,
@@ -99,6 +108,11 @@ pub enum TimelineUpdate {
         target_event_id: OwnedEventId,
         index: usize,
     },
+    /// A request to jump to the given event, e.g., a search result.
+    ///
+    /// The RoomScreen then asks the background `timeline_subscriber_handler` async task
+    /// to search backwards for this event, which replies with `TargetEventFound`.
+    JumpToEvent(OwnedEventId),
     /// A notice that the background task doing pagination for this room is currently running
     /// a pagination request in the given direction, and is waiting for that request to complete.
     PaginationRunning(PaginationDirection),
@@ -135,6 +149,9 @@ pub enum TimelineUpdate {
     /// This is different from RoomMembersSynced which only indicates members were fetched
     /// but doesn't provide the actual data.
     RoomMembersListFetched { members: Vec<RoomMember> },
+    /// The shield of the room, computed from its encryption state
+    /// and the identities of its joined members.
+    RoomShield(FrontendShieldState),
     /// A notice that one or more requested media items (images, videos, etc.)
     /// that should be displayed in this timeline have now been fetched and are available.
     _MediaFetched,
@@ -147,6 +164,33 @@ pub enum TimelineUpdate {
     UserPowerLevels(UserPowerLevels),
     /// An update to the currently logged-in user's own read receipt for this room.
     OwnUserReadReceipt(Receipt),
+    /// The pinned events of this room, and their timeline items loaded so far.
+    PinnedEvents {
+        event_ids: Vec<OwnedEventId>,
+        items: Vector<Arc<TimelineItem>>,
+    },
+    /// A page of the threads of this room, requested with a `PaginateThreadsList` request.
+    ThreadsList {
+        filter: FrontendThreadsListFilter,
+        threads: Vec<FrontendThreadRoot>,
+        next_batch: Option<String>,
+        /// Whether this is the first page, which replaces the threads listed so far.
+        reset: bool,
+    },
+    /// The root of a thread that wasn't listed yet, requested with a `FetchThreadRoot` request.
+    NewThread {
+        filter: FrontendThreadsListFilter,
+        thread: Box<FrontendThreadRoot>,
+    },
+    /// A new reply was received in a thread of this room.
+    ThreadReply {
+        thread_root_event_id: OwnedEventId,
+        /// The formatted text preview of the reply.
+        event_formatted_summary: String,
+        sender_id: OwnedUserId,
+    },
+    /// A link preview was fetched, or URL previews were enabled or disabled in this room.
+    LinkPreviewsChanged,
 }
 
 /// The global set of all timeline states, one entry per room.
@@ -160,6 +204,11 @@ pub static TIMELINE_STATES: LazyLock<Mutex<HashMap<TimelineKind, TimelineUiState
 /// If a state is more temporary and shouldn't be persisted when the timeline is hidden,
 /// then it should be stored in the RoomScreen widget itself, not in this struct.
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(as = "TimelineUiStateBinding<Vec<FrontendTimelineItem>>")
+)]
 pub struct TimelineUiState {
     /// Info determining whether this is a main room timeline is a thread-focused timeline.
     pub(crate) kind: TimelineKind,
@@ -175,6 +224,11 @@ pub struct TimelineUiState {
     /// This must be reset to `false` whenever the timeline is fully cleared.
     pub(crate) fully_paginated: bool,
 
+    /// Whether the latest event of this room is in the timeline, which means
+    /// that further forwards pagination requests are useless.
+    /// This is always `true` for main room and thread timelines, which receive live events.
+    pub(crate) fully_paginated_forwards: bool,
+
     /// The list of items (events) in this room's timeline that our client currently knows about.
     pub(crate) items: Vector<Arc<TimelineItem>>,
 
@@ -209,6 +263,19 @@ impl Serialize for TimelineUiState {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
+    {
+        self.serialize_with_items(
+            serializer,
+            &serialize_timeline_items(&self.items, &self.kind, &self.user_power),
+        )
+    }
+}
+
+impl TimelineUiState {
+    fn serialize_with_items<S, I>(&self, serializer: S, items: &I) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        I: Serialize,
     {
         match self.kind {
             TimelineKind::MainRoom { ref room_id } => {
@@ -218,10 +285,7 @@ impl Serialize for TimelineUiState {
                 state.serialize_field("roomId", room_id)?;
                 state.serialize_field("userPower", &self.user_power)?;
                 state.serialize_field("fullyPaginated", &self.fully_paginated)?;
-                state.serialize_field(
-                    "items",
-                    &serialize_timeline_items(&self.items, &self.kind, &self.user_power),
-                )?;
+                state.serialize_field("items", items)?;
                 state.serialize_field("scrolledPastReadMarker", &self.scrolled_past_read_marker)?;
                 state.serialize_field("latestOwnUserReceipt", &self.latest_own_user_receipt)?;
 
@@ -238,10 +302,25 @@ impl Serialize for TimelineUiState {
                 state.serialize_field("threadRootEventId", thread_root_event_id)?;
                 state.serialize_field("userPower", &self.user_power)?;
                 state.serialize_field("fullyPaginated", &self.fully_paginated)?;
-                state.serialize_field(
-                    "items",
-                    &serialize_timeline_items(&self.items, &self.kind, &self.user_power),
-                )?;
+                state.serialize_field("items", items)?;
+                state.serialize_field("scrolledPastReadMarker", &self.scrolled_past_read_marker)?;
+                state.serialize_field("latestOwnUserReceipt", &self.latest_own_user_receipt)?;
+
+                state.end()
+            }
+            TimelineKind::FocusedEvent {
+                ref room_id,
+                ref event_id,
+            } => {
+                let mut state = serializer.serialize_struct("TimelineUiState", 9)?;
+
+                state.serialize_field("timelineKind", "focusedEvent")?;
+                state.serialize_field("roomId", room_id)?;
+                state.serialize_field("eventId", event_id)?;
+                state.serialize_field("userPower", &self.user_power)?;
+                state.serialize_field("fullyPaginated", &self.fully_paginated)?;
+                state.serialize_field("fullyPaginatedForwards", &self.fully_paginated_forwards)?;
+                state.serialize_field("items", items)?;
                 state.serialize_field("scrolledPastReadMarker", &self.scrolled_past_read_marker)?;
                 state.serialize_field("latestOwnUserReceipt", &self.latest_own_user_receipt)?;
 
@@ -250,6 +329,78 @@ impl Serialize for TimelineUiState {
         }
     }
 }
+
+/// A [`TimelineUiState`] whose `items` are the changes since the previous update, in diff mode.
+/// Items are patched by their `unique_id`.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(as = "TimelineUiStateBinding<Vec<DiffOp<String, FrontendTimelineItem>>>")
+)]
+pub struct TimelineUiStateDiff<'a> {
+    pub(crate) state: &'a TimelineUiState,
+    pub(crate) items: Vec<DiffOp<String, FrontendTimelineItem>>,
+}
+
+impl Serialize for TimelineUiStateDiff<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.state.serialize_with_items(serializer, &self.items)
+    }
+}
+
+/// The shape of a serialized [`TimelineUiState`] whose items are `I`.
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[ts(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "timelineKind"
+)]
+#[allow(dead_code)]
+enum TimelineUiStateBinding<I> {
+    MainRoom {
+        room_id: String,
+        user_power: UserPowerLevels,
+        fully_paginated: bool,
+        items: I,
+        scrolled_past_read_marker: bool,
+        latest_own_user_receipt: Option<ReceiptBinding>,
+    },
+    Thread {
+        room_id: String,
+        thread_root_event_id: String,
+        user_power: UserPowerLevels,
+        fully_paginated: bool,
+        items: I,
+        scrolled_past_read_marker: bool,
+        latest_own_user_receipt: Option<ReceiptBinding>,
+    },
+    FocusedEvent {
+        room_id: String,
+        event_id: String,
+        user_power: UserPowerLevels,
+        fully_paginated: bool,
+        fully_paginated_forwards: bool,
+        items: I,
+        scrolled_past_read_marker: bool,
+        latest_own_user_receipt: Option<ReceiptBinding>,
+    },
+}
+
+/// The shape of a receipt serialized by ruma.
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[ts(rename = "Receipt", optional_fields)]
+#[allow(dead_code)]
+struct ReceiptBinding {
+    ts: Option<f64>,
+    thread_id: Option<String>,
+}
+
 fn serialize_timeline_items(
     items: &Vector<Arc<TimelineItem>>,
     timeline_kind: &TimelineKind,
@@ -275,10 +426,7 @@ pub fn take_timeline_endpoints(kind: &TimelineKind) -> Option<TimelineEndpoints>
 
         let details = match kind {
             TimelineKind::MainRoom { .. } => &mut lock.main_timeline,
-            TimelineKind::Thread {
-                thread_root_event_id,
-                ..
-            } => lock.thread_timelines.get_mut(thread_root_event_id)?,
+            _ => lock.focused_timelines.get_mut(kind)?,
         };
         let (update_receiver, request_sender) = details.timeline_singleton_endpoints.take()?;
 
@@ -299,7 +447,7 @@ pub async fn timeline_subscriber_handler(
     timeline: Arc<Timeline>,
     timeline_update_sender: crossbeam_channel::Sender<TimelineUpdate>,
     mut request_receiver: watch::Receiver<Vec<BackwardsPaginateUntilEventRequest>>,
-    thread_root_event_id: Option<OwnedEventId>,
+    timeline_kind: TimelineKind,
 ) {
     /// An inner function that searches the given new timeline items for a target event.
     ///
@@ -325,10 +473,10 @@ pub async fn timeline_subscriber_handler(
     }
 
     let room_id = room.room_id().to_owned();
-    trace!("Starting timeline subscriber for room {room_id}, thread {thread_root_event_id:?}...");
+    trace!("Starting timeline subscriber for {timeline_kind}...");
     let (mut timeline_items, mut subscriber) = timeline.subscribe().await;
     trace!(
-        "Received initial timeline update of {} items for room {room_id}, thread {thread_root_event_id:?}.",
+        "Received initial timeline update of {} items for {timeline_kind}.",
         timeline_items.len()
     );
 
@@ -403,22 +551,13 @@ pub async fn timeline_subscriber_handler(
                         }
                         else {
                             trace!("Target event not in timeline. Starting backwards pagination \
-                                in room {room_id}, thread {thread_root_event_id:?} to find target event \
+                                in {timeline_kind} to find target event \
                                 {new_target_event_id} starting from index {starting_index}.",
                             );
                             // If we didn't find the target event in the current timeline items,
                             // we need to start loading previous items into the timeline.
                             submit_async_request(MatrixRequest::PaginateTimeline {
-                                timeline_kind: if let Some(thread_root_event_id) = thread_root_event_id.clone() {
-                                    TimelineKind::Thread {
-                                        room_id: room_id.clone(),
-                                        thread_root_event_id,
-                                    }
-                                } else {
-                                    TimelineKind::MainRoom {
-                                        room_id: room_id.clone(),
-                                    }
-                                },
+                                timeline_kind: timeline_kind.clone(),
                                 num_events: 50,
                                 direction: PaginationDirection::Backwards,
                             });
@@ -596,12 +735,56 @@ pub async fn timeline_subscriber_handler(
     error!("Error: unexpectedly ended timeline subscriber for room {room_id}.");
 }
 
+/// A task that keeps the pinned events of the given room up to date,
+/// by listening to a timeline focused on them.
+///
+/// The pinned events are sent to the room's main timeline as `TimelineUpdate::PinnedEvents`.
+pub async fn pinned_events_subscriber_handler(
+    room: Room,
+    timeline_update_sender: crossbeam_channel::Sender<TimelineUpdate>,
+) {
+    let room_id = room.room_id().to_owned();
+    let timeline = match room
+        .timeline_builder()
+        .with_focus(TimelineFocus::PinnedEvents)
+        .build()
+        .await
+    {
+        Ok(timeline) => timeline,
+        Err(e) => {
+            error!("Failed to build the pinned events timeline for room {room_id}: {e}");
+            return;
+        }
+    };
+
+    let (mut items, mut subscriber) = timeline.subscribe().await;
+    loop {
+        // The pinned events timeline is reloaded whenever the pinned events state changes.
+        let update = TimelineUpdate::PinnedEvents {
+            event_ids: room.pinned_event_ids().unwrap_or_default(),
+            items: items.clone(),
+        };
+        if timeline_update_sender.send(update).is_err() {
+            break;
+        }
+        broadcast_event(UIUpdateMessage::RefreshUI);
+
+        let Some(batch) = subscriber.next().await else {
+            break;
+        };
+        for diff in batch {
+            diff.apply(&mut items);
+        }
+    }
+    debug!("Ended pinned events subscriber for room {room_id}.");
+}
+
 /// Handles the given updated latest event for the given room.
 ///
 /// This function sends a `RoomsListUpdate::UpdateLatestEvent`
 /// to update the latest event in the RoomsListEntry for the given room.
 pub async fn update_latest_event(room: &Room) {
-    let client = CLIENT.wait();
+    let client = room.client();
     let (sender_username, _sender_id, timestamp, content) = match room.latest_event().await {
         LatestEventValue::Remote {
             timestamp,
@@ -672,8 +855,10 @@ pub async fn update_latest_event(room: &Room) {
     });
 }
 
-/// Either a main room timeline or a thread-focused timeline.
+/// Either a main room timeline, a thread-focused timeline,
+/// or a timeline focused on a given event (e.g., when opening a permalink).
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(
     rename_all = "camelCase",
     rename_all_fields = "camelCase",
@@ -681,24 +866,37 @@ pub async fn update_latest_event(room: &Room) {
 )]
 pub enum TimelineKind {
     MainRoom {
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
         room_id: OwnedRoomId,
     },
     Thread {
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
         room_id: OwnedRoomId,
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
         thread_root_event_id: OwnedEventId,
     },
+    /// A timeline loaded around the given event, which can be paginated in both directions.
+    /// Once it has been paginated forwards up to the latest event,
+    /// the frontend can switch to the main room timeline to follow new messages.
+    FocusedEvent {
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+        room_id: OwnedRoomId,
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+        event_id: OwnedEventId,
+    },
 }
 impl TimelineKind {
     pub fn room_id(&self) -> &OwnedRoomId {
         match self {
             TimelineKind::MainRoom { room_id } => room_id,
             TimelineKind::Thread { room_id, .. } => room_id,
+            TimelineKind::FocusedEvent { room_id, .. } => room_id,
         }
     }
 
     pub fn thread_root_event_id(&self) -> Option<&OwnedEventId> {
         match self {
-            TimelineKind::MainRoom { .. } => None,
+            TimelineKind::MainRoom { .. } | TimelineKind::FocusedEvent { .. } => None,
             TimelineKind::Thread {
                 thread_root_event_id,
                 ..
@@ -716,6 +914,9 @@ impl std::fmt::Display for TimelineKind {
             } => {
                 write!(f, "Thread({}, {})", room_id, thread_root_event_id)
             }
+            TimelineKind::FocusedEvent { room_id, event_id } => {
+                write!(f, "FocusedEvent({}, {})", room_id, event_id)
+            }
         }
     }
 }
@@ -732,7 +933,7 @@ pub struct TimelineEndpoints {
     pub _successor_room: Option<SuccessorRoom>,
 }
 
-/// Info about a timeline for a joined room or a thread in a joined room.
+/// Info about a timeline for a joined room, a thread or an event in a joined room.
 pub(crate) struct PerTimelineDetails {
     /// A shared reference to a room's main timeline or thread's timeline of events.
     pub timeline: Arc<Timeline>,
@@ -1,19 +1,37 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use matrix_sdk::{
-    Client, ThreadingSupport, config::RequestConfig, encryption::EncryptionSettings,
+    AuthSession, Client, ThreadingSupport, config::RequestConfig, encryption::EncryptionSettings,
     sliding_sync::VersionBuilder,
 };
 
 use rand::{RngExt, distr::Alphanumeric, rng};
+use tracing::warn;
 
 use crate::{
     CLIENT,
     events::handlers::add_event_handlers,
-    init::singletons::{APP_DATA_DIR, TEMP_CLIENT, TEMP_CLIENT_SESSION},
+    init::singletons::{APP_DATA_DIR, CLIENT_SESSION, TEMP_CLIENT, TEMP_CLIENT_SESSION},
 };
 
 use super::session::ClientSession;
 
+/// Drops the temporary client of a failed login and deletes its database,
+/// so the login can be retried with another homeserver.
+pub(crate) async fn discard_temp_client() {
+    *TEMP_CLIENT.lock.lock().unwrap() = None;
+    super::qr_login::take_scanned_qr_code();
+    let client_session = TEMP_CLIENT_SESSION.lock().unwrap().take();
+    if let Some(client_session) = client_session {
+        let db_path = APP_DATA_DIR
+            .wait()
+            .join("matrix-db")
+            .join(&client_session.db_identifier);
+        if let Err(e) = tokio::fs::remove_dir_all(&db_path).await {
+            warn!("Failed to delete the Matrix DB at {db_path:?}: {e}");
+        }
+    }
+}
+
 pub(crate) async fn login_and_persist_matrix_session(
     client: &Client,
     username: String,
@@ -29,16 +47,22 @@ pub(crate) async fn login_and_persist_matrix_session(
 
     let user_session = matrix_auth
         .session()
-        .expect("Should have session after login");
+        .ok_or(anyhow!("Should have session after login"))?;
 
-    let full = super::session::FullMatrixSession::new(
-        TEMP_CLIENT_SESSION
-            .lock()
-            .unwrap()
-            .clone()
-            .ok_or(anyhow!("No temporary session has been set !"))?,
-        matrix_sdk::AuthSession::Matrix(user_session),
-    );
+    set_client_and_serialize_session(matrix_sdk::AuthSession::Matrix(user_session))
+}
+
+/// Promotes the temporary client used during login to the logged-in `CLIENT`,
+/// then returns the serialized `FullMatrixSession` that must be persisted by the adapter.
+pub(crate) fn set_client_and_serialize_session(
+    user_session: AuthSession,
+) -> anyhow::Result<String> {
+    let client_session = TEMP_CLIENT_SESSION
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or(anyhow!("No temporary session has been set !"))?;
+    let full = super::session::FullMatrixSession::new(client_session.clone(), user_session)?;
 
     CLIENT
         .set(
@@ -49,7 +73,10 @@ pub(crate) async fn login_and_persist_matrix_session(
                 .clone()
                 .ok_or(anyhow!("No temporary client was set !"))?,
         )
-        .expect("Client already set !");
+        .map_err(|_| anyhow!("BUG: CLIENT already set!"))?;
+    CLIENT_SESSION
+        .set(client_session)
+        .map_err(|_| anyhow!("BUG: CLIENT_SESSION already set!"))?;
     let serialized = serde_json::to_string(&full)?;
 
     Ok(serialized)
@@ -90,7 +117,7 @@ pub async fn build_client(
                     .collect();
                 (homeserver, db_path, passphrase, db_identifier)
             } else {
-                panic!("Cannot recover session from storage or create it without homeserver")
+                bail!("Cannot recover session from storage or create it without homeserver")
             }
         }
     };
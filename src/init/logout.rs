@@ -0,0 +1,93 @@
+use anyhow::anyhow;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::{
+    events::timeline::TIMELINE_STATES,
+    init::{
+        session::ClientSession,
+        singletons::{
+            APP_DATA_DIR, CLIENT, CLIENT_SESSION, CURRENT_USER_ID, HAS_SESSION_STORED,
+            SESSION_END_SENDER, SYNC_SERVICE, TEMP_CLIENT, TEMP_CLIENT_SESSION,
+        },
+    },
+    room::{
+        joined_room::clear_all_rooms,
+        link_preview::clear_link_previews,
+        rooms_list::{RoomsListUpdate, enqueue_rooms_list_update},
+    },
+};
+
+/// Logs the current user out and tears down everything related to its session.
+///
+/// The access token is revoked server side, then the session tasks are stopped, the session
+/// torn down and the local database deleted. The init task asks the adapter to forget the
+/// persisted session, and goes back to the login step.
+pub(crate) async fn logout_and_teardown() -> anyhow::Result<()> {
+    // The client must not be held while the session is torn down.
+    {
+        let client = CLIENT.get().ok_or(anyhow!("No client is logged in"))?;
+        // Revoke the access token first. If this fails, we keep the session untouched
+        // so the user can retry.
+        client.logout().await?;
+    }
+    info!("Access token revoked, tearing down the session...");
+
+    // The database can only be deleted once every task holding the client
+    // (and thus its open stores) has been stopped.
+    let client_session = end_session().await?;
+
+    if let Some(client_session) = client_session {
+        let db_path = APP_DATA_DIR
+            .wait()
+            .join("matrix-db")
+            .join(&client_session.db_identifier);
+        if let Err(e) = tokio::fs::remove_dir_all(&db_path).await {
+            warn!("Failed to delete the Matrix DB at {db_path:?}: {e}");
+        }
+    } else {
+        warn!("No client session was found, the Matrix DB cannot be deleted");
+    }
+
+    Ok(())
+}
+
+/// Stops the sync service and clears the session singletons and the rooms states.
+/// The server side session and the local database are left untouched.
+///
+/// This must only be called by the init task, once the session tasks and workers are stopped,
+/// so that nothing puts back per-session state afterwards.
+/// Returns the data needed to locate the database of the torn down client.
+pub(crate) async fn teardown_session() -> Option<ClientSession> {
+    if let Some(sync_service) = SYNC_SERVICE.take() {
+        sync_service.stop().await;
+    }
+
+    CLIENT.take();
+    CURRENT_USER_ID.take();
+    HAS_SESSION_STORED.replace(false);
+    let client_session = CLIENT_SESSION.take();
+    *TEMP_CLIENT.lock.lock().unwrap() = None;
+    *TEMP_CLIENT_SESSION.lock().unwrap() = None;
+
+    clear_all_rooms();
+    clear_link_previews();
+    TIMELINE_STATES.lock().unwrap().clear();
+    enqueue_rooms_list_update(RoomsListUpdate::ResetAll);
+
+    client_session
+}
+
+/// Asks the init task to end the active session, and waits until all its tasks are stopped
+/// and the session is torn down.
+async fn end_session() -> anyhow::Result<Option<ClientSession>> {
+    let (done_sender, done_receiver) = oneshot::channel();
+    SESSION_END_SENDER
+        .get()
+        .ok_or(anyhow!("The session end sender is not yet set"))?
+        .send(done_sender)
+        .map_err(|_| anyhow!("The init task has stopped"))?;
+    done_receiver
+        .await
+        .map_err(|_| anyhow!("The init task has stopped"))
+}
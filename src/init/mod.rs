@@ -1,24 +1,67 @@
 use anyhow::anyhow;
-use matrix_sdk::{Client, authentication::oauth::error::OAuthDiscoveryError};
+use matrix_sdk::{
+    Client,
+    authentication::oauth::error::OAuthDiscoveryError,
+    ruma::{
+        OwnedMxcUri,
+        api::client::session::get_login_types::v3::{IdentityProvider, LoginType},
+    },
+};
 use serde::Serialize;
 
 use crate::init::singletons::TEMP_CLIENT;
 
 pub(crate) mod login;
+pub(crate) mod logout;
 pub(crate) mod oauth;
+pub(crate) mod qr_login;
+pub(crate) mod registration;
 pub(crate) mod session;
 pub mod singletons;
+pub(crate) mod sso;
 mod sync;
 pub(crate) mod workers;
 
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum FrontendAuthTypeResponse {
-    Matrix,
+    /// Password login (`m.login.password`) or registration. If the homeserver also supports
+    /// legacy SSO login, its identity providers are listed, and the frontend can open the URL
+    /// returned by the `get_sso_login_url` command instead of sending a login payload.
+    Matrix {
+        identity_providers: Option<Vec<FrontendIdentityProvider>>,
+    },
     Oauth,
+    /// Legacy SSO login (`m.login.sso`), for homeservers without OAuth 2.0 nor password login.
+    Sso {
+        identity_providers: Vec<FrontendIdentityProvider>,
+    },
     WrongUrl,
 }
 
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendIdentityProvider {
+    pub id: String,
+    pub name: String,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
+    pub icon: Option<OwnedMxcUri>,
+    pub brand: Option<String>,
+}
+
+impl From<IdentityProvider> for FrontendIdentityProvider {
+    fn from(idp: IdentityProvider) -> Self {
+        Self {
+            id: idp.id,
+            name: idp.name,
+            icon: idp.icon,
+            brand: idp.brand.map(|b| b.as_str().to_owned()),
+        }
+    }
+}
+
 pub async fn check_homeserver_auth_type() -> anyhow::Result<(FrontendAuthTypeResponse, Client)> {
     let client = {
         let mut guard = TEMP_CLIENT.lock.lock().unwrap();
@@ -30,9 +73,38 @@ pub async fn check_homeserver_auth_type() -> anyhow::Result<(FrontendAuthTypeRes
     match client.oauth().server_metadata().await {
         Ok(_) => Ok((FrontendAuthTypeResponse::Oauth, client)),
         Err(e) => match e {
-            OAuthDiscoveryError::NotSupported => Ok((FrontendAuthTypeResponse::Matrix, client)),
+            OAuthDiscoveryError::NotSupported => Ok((matrix_auth_type(&client).await?, client)),
             OAuthDiscoveryError::Url(_) => Ok((FrontendAuthTypeResponse::WrongUrl, client)),
             _ => Err(anyhow!("Unknown error when checking available auth types")),
         },
     }
 }
+
+/// Checks which login types of the native Matrix auth API the homeserver supports.
+async fn matrix_auth_type(client: &Client) -> anyhow::Result<FrontendAuthTypeResponse> {
+    let login_types = client.matrix_auth().get_login_types().await?;
+    let mut supports_password = false;
+    let mut identity_providers = None;
+    for login_type in login_types.flows {
+        match login_type {
+            LoginType::Password(_) => supports_password = true,
+            LoginType::Sso(sso) => {
+                identity_providers = Some(
+                    sso.identity_providers
+                        .into_iter()
+                        .map(FrontendIdentityProvider::from)
+                        .collect(),
+                )
+            }
+            _ => {}
+        }
+    }
+    if supports_password {
+        return Ok(FrontendAuthTypeResponse::Matrix { identity_providers });
+    }
+    identity_providers
+        .map(|identity_providers| FrontendAuthTypeResponse::Sso { identity_providers })
+        .ok_or(anyhow!(
+            "The homeserver supports neither password nor SSO login"
+        ))
+}
@@ -13,16 +13,18 @@ use tracing::{debug, error, info};
 use url::Url;
 
 use crate::{
-    CLIENT,
-    init::singletons::{TEMP_CLIENT, TEMP_CLIENT_SESSION, get_event_bridge},
+    init::{login::set_client_and_serialize_session, singletons::get_event_bridge},
     models::events::EmitEvent,
 };
 
-/// Generate the OAuth 2.0 client metadata.
-fn client_metadata(client_uri: Url, callback_url: Url) -> Raw<ClientMetadata> {
+/// Generate the OAuth 2.0 client metadata, allowing the given grant types.
+pub(crate) fn client_metadata(
+    client_uri: Url,
+    grant_types: Vec<OAuthGrantType>,
+) -> Raw<ClientMetadata> {
     let client_uri = Localized::new(client_uri, None);
 
-    debug!("Using client URI {client_uri:?} and callback_url {callback_url:?}");
+    debug!("Using client URI {client_uri:?} and grant types {grant_types:?}");
 
     let metadata = ClientMetadata {
         // The following fields should be displayed in the OAuth 2.0 authorization server's
@@ -35,10 +37,7 @@ fn client_metadata(client_uri: Url, callback_url: Url) -> Raw<ClientMetadata> {
             // This is a native application (in contrast to a web application, that runs in a
             // browser).
             ApplicationType::Native,
-            // We are going to use the Authorization Code flow.
-            vec![OAuthGrantType::AuthorizationCode {
-                redirect_uris: vec![callback_url],
-            }],
+            grant_types,
             client_uri,
         )
     };
@@ -50,7 +49,7 @@ fn client_metadata(client_uri: Url, callback_url: Url) -> Raw<ClientMetadata> {
 /// Code flow.
 pub(crate) async fn register_and_login_oauth(
     client: &Client,
-    mut oauth_deeplink_receiver: mpsc::Receiver<Url>,
+    oauth_deeplink_receiver: &mut mpsc::Receiver<Url>,
     client_uri: &Url,
     callback_url: &Url,
 ) -> anyhow::Result<String> {
@@ -62,29 +61,36 @@ pub(crate) async fn register_and_login_oauth(
             .login(
                 callback_url.to_owned(),
                 None,
-                Some(client_metadata(client_uri.to_owned(), callback_url.to_owned()).into()),
+                Some(
+                    client_metadata(
+                        client_uri.to_owned(),
+                        // We are going to use the Authorization Code flow.
+                        vec![OAuthGrantType::AuthorizationCode {
+                            redirect_uris: vec![callback_url.to_owned()],
+                        }],
+                    )
+                    .into(),
+                ),
                 None,
             )
             .build()
             .await?;
 
         // Send auth URL to frontend
-        get_event_bridge()
-            .expect("bridge should be defined at this point")
-            .emit(EmitEvent::OAuthUrl(url.to_string()));
+        get_event_bridge()?.emit(EmitEvent::OAuthUrl(url.to_string()));
 
-        let query_string = oauth_deeplink_receiver
+        let callback = oauth_deeplink_receiver
             .recv()
             .await
-            .expect("no url was sent");
+            .ok_or(anyhow!("no url was sent"))?;
+
+        let Some(query) = callback.query() else {
+            error!("Error: no query params passed to auth callback");
+            continue;
+        };
 
         match oauth
-            .finish_login(UrlOrQuery::Query(
-                query_string
-                    .query()
-                    .expect("no query params passed to auth callback")
-                    .to_owned(),
-            ))
+            .finish_login(UrlOrQuery::Query(query.to_owned()))
             .await
         {
             Ok(()) => {
@@ -100,27 +106,7 @@ pub(crate) async fn register_and_login_oauth(
 
     let user_session = oauth
         .full_session()
-        .expect("Should have session after login");
-
-    let full = super::session::FullMatrixSession::new(
-        TEMP_CLIENT_SESSION
-            .lock()
-            .unwrap()
-            .clone()
-            .ok_or(anyhow!("No temporary session has been set !"))?,
-        matrix_sdk::AuthSession::OAuth(Box::new(user_session)),
-    );
-    CLIENT
-        .set(
-            TEMP_CLIENT
-                .lock
-                .lock()
-                .unwrap()
-                .clone()
-                .ok_or(anyhow!("No temporary client was set !"))?,
-        )
-        .expect("Client already set !");
-    let serialized = serde_json::to_string(&full)?;
+        .ok_or(anyhow!("Should have session after login"))?;
 
-    Ok(serialized)
+    set_client_and_serialize_session(matrix_sdk::AuthSession::OAuth(Box::new(user_session)))
 }
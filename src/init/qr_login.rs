@@ -0,0 +1,172 @@
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use matrix_sdk::{
+    AuthSession, Client,
+    authentication::oauth::{
+        qrcode::{
+            CheckCodeSender, GeneratedQrProgress, GrantLoginProgress, LoginProgress,
+            Msc4108IntentData, QrCodeData, QrCodeIntentData,
+        },
+        registration::OAuthGrantType,
+    },
+};
+use tracing::{error, info};
+use url::Url;
+
+use crate::{
+    init::{
+        login::set_client_and_serialize_session,
+        oauth::client_metadata,
+        singletons::{CLIENT, get_event_bridge},
+    },
+    models::events::{EmitEvent, QrLoginProgress},
+};
+
+/// The QR code scanned on this device, waiting for the init task to log in with it.
+static SCANNED_QR_CODE: Mutex<Option<QrCodeData>> = Mutex::new(None);
+
+/// The sender of the check code displayed by the new device, while a login grant is ongoing.
+static CHECK_CODE_SENDER: Mutex<Option<CheckCodeSender>> = Mutex::new(None);
+
+/// Decode the scanned QR code, store it for the login step and return the homeserver of
+/// the device that generated it.
+pub(crate) fn set_scanned_qr_code(bytes: &[u8]) -> anyhow::Result<String> {
+    let qr_code_data = QrCodeData::from_bytes(bytes)?;
+    let homeserver = match qr_code_data.intent_data() {
+        QrCodeIntentData::Msc4108 {
+            data: Msc4108IntentData::Reciprocate { server_name },
+            ..
+        } => server_name.clone(),
+        QrCodeIntentData::Msc4388 { base_url, .. } => base_url.to_string(),
+        QrCodeIntentData::Msc4108 {
+            data: Msc4108IntentData::Login,
+            ..
+        } => {
+            return Err(anyhow!(
+                "This QR code must be scanned by a device that is already logged in"
+            ));
+        }
+    };
+    *SCANNED_QR_CODE.lock().unwrap() = Some(qr_code_data);
+    Ok(homeserver)
+}
+
+/// Take the QR code scanned with `build_temp_client_from_qr_code`, if any.
+pub(crate) fn take_scanned_qr_code() -> Option<QrCodeData> {
+    SCANNED_QR_CODE.lock().unwrap().take()
+}
+
+/// Log in this new device with a QR code displayed by an already logged in device (MSC4108).
+///
+/// The check code to enter on the other device and the login steps are sent
+/// to the frontend with [`EmitEvent::QrLoginProgress`].
+pub(crate) async fn login_with_scanned_qr_code(
+    client: &Client,
+    qr_code_data: QrCodeData,
+    client_uri: &Url,
+) -> anyhow::Result<String> {
+    let oauth = client.oauth();
+    let registration_data =
+        client_metadata(client_uri.to_owned(), vec![OAuthGrantType::DeviceCode]).into();
+
+    let login = oauth
+        .login_with_qr_code(Some(&registration_data))
+        .scan(&qr_code_data);
+    let mut progress = login.subscribe_to_progress();
+    let progress_task = tokio::spawn(async move {
+        while let Some(state) = progress.next().await {
+            let progress = match state {
+                LoginProgress::Starting => QrLoginProgress::Starting,
+                LoginProgress::EstablishingSecureChannel(qr_progress) => {
+                    QrLoginProgress::CheckCode {
+                        check_code: qr_progress.check_code.to_digit(),
+                    }
+                }
+                LoginProgress::WaitingForToken { user_code } => {
+                    QrLoginProgress::WaitingForToken { user_code }
+                }
+                LoginProgress::SyncingSecrets => QrLoginProgress::SyncingSecrets,
+                LoginProgress::Done => QrLoginProgress::Done,
+            };
+            emit_progress(progress);
+        }
+    });
+
+    let result = login.await;
+    progress_task.abort();
+    result?;
+    info!("Logged in with QR code");
+
+    let user_session = oauth
+        .full_session()
+        .ok_or(anyhow!("Should have session after login"))?;
+
+    set_client_and_serialize_session(AuthSession::OAuth(Box::new(user_session)))
+}
+
+/// Let a new device log in to the current account, by generating a QR code it has to scan (MSC4108).
+///
+/// The QR code and the login steps are sent to the frontend with [`EmitEvent::QrLoginProgress`].
+/// Once the new device has scanned the QR code, the check code it displays must be submitted
+/// with [`submit_qr_login_check_code`].
+pub(crate) async fn grant_login_with_generated_qr_code() -> anyhow::Result<()> {
+    let client = CLIENT.get().ok_or(anyhow!("No client is logged in"))?;
+    let oauth = client.oauth();
+
+    let grant = oauth.grant_login_with_qr_code().generate();
+    let mut progress = grant.subscribe_to_progress();
+    let progress_task = tokio::spawn(async move {
+        while let Some(state) = progress.next().await {
+            let progress = match state {
+                GrantLoginProgress::Starting => QrLoginProgress::Starting,
+                GrantLoginProgress::EstablishingSecureChannel(GeneratedQrProgress::QrReady(
+                    qr_code_data,
+                )) => QrLoginProgress::QrReady {
+                    qr_code: qr_code_data.to_bytes(),
+                },
+                GrantLoginProgress::EstablishingSecureChannel(GeneratedQrProgress::QrScanned(
+                    check_code_sender,
+                )) => {
+                    *CHECK_CODE_SENDER.lock().unwrap() = Some(check_code_sender);
+                    QrLoginProgress::CheckCodeRequired
+                }
+                GrantLoginProgress::WaitingForAuth { verification_uri } => {
+                    QrLoginProgress::WaitingForAuth {
+                        verification_uri: verification_uri.to_string(),
+                    }
+                }
+                GrantLoginProgress::SyncingSecrets => QrLoginProgress::SyncingSecrets,
+                GrantLoginProgress::Done => QrLoginProgress::Done,
+            };
+            emit_progress(progress);
+        }
+    });
+
+    let result = grant.await;
+    progress_task.abort();
+    CHECK_CODE_SENDER.lock().unwrap().take();
+    result?;
+    info!("Granted login to a new device with QR code");
+    Ok(())
+}
+
+/// Submit the check code displayed by the new device, to confirm the secure channel
+/// of the ongoing login grant.
+pub(crate) async fn submit_qr_login_check_code(check_code: u8) -> anyhow::Result<()> {
+    let check_code_sender = CHECK_CODE_SENDER
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or(anyhow!("No QR code login is waiting for a check code"))?;
+    check_code_sender.send(check_code).await?;
+    Ok(())
+}
+
+fn emit_progress(progress: QrLoginProgress) {
+    match get_event_bridge() {
+        Ok(bridge) => bridge.emit(EmitEvent::QrLoginProgress(progress)),
+        Err(e) => error!("Cannot emit QR login progress: {e}"),
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::{anyhow, bail};
+use matrix_sdk::{
+    Client,
+    ruma::{
+        ClientSecret, UInt,
+        api::client::{
+            account::{register, request_registration_token_via_email},
+            uiaa::{self, AuthData, AuthFlow, AuthType, LoginTermsParams, UiaaInfo},
+        },
+    },
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+use crate::{
+    init::{login::set_client_and_serialize_session, singletons::get_event_bridge},
+    models::events::{
+        EmitEvent, FrontendRegistrationFlow, MatrixRegistrationEvent, MatrixRegistrationPayload,
+        RegistrationPolicy, RegistrationStage, RegistrationStageRequest,
+    },
+};
+
+/// The UIAA stages this lib can drive during registration.
+const SUPPORTED_STAGES: [AuthType; 4] = [
+    AuthType::Dummy,
+    AuthType::Terms,
+    AuthType::RegistrationToken,
+    AuthType::EmailIdentity,
+];
+
+fn is_flow_supported(flow: &AuthFlow) -> bool {
+    flow.stages.iter().all(|s| SUPPORTED_STAGES.contains(s))
+}
+
+/// Query the registration flows of the homeserver, by sending an empty registration request.
+pub(crate) async fn query_registration_flows(
+    client: &Client,
+) -> anyhow::Result<Vec<FrontendRegistrationFlow>> {
+    let err = match client.send(register::v3::Request::new()).await {
+        Ok(_) => bail!("The homeserver registered an account without any stage"),
+        Err(err) => err,
+    };
+    let info = err.as_uiaa_response().ok_or(anyhow!(
+        "Registration is not available on this homeserver: {err}"
+    ))?;
+    Ok(info
+        .flows
+        .iter()
+        .map(|flow| FrontendRegistrationFlow {
+            stages: flow.stages.iter().map(|s| s.to_string()).collect(),
+            is_supported: is_flow_supported(flow),
+        })
+        .collect())
+}
+
+/// Waits for the frontend to start a registration, ignoring stale stage answers.
+pub(crate) async fn wait_for_registration_request(
+    registration_receiver: &mut mpsc::Receiver<MatrixRegistrationEvent>,
+) -> Option<MatrixRegistrationPayload> {
+    while let Some(event) = registration_receiver.recv().await {
+        match event {
+            MatrixRegistrationEvent::Register(payload) => return Some(payload),
+            other => debug!("Ignoring registration event {other:?}, no registration started"),
+        }
+    }
+    None
+}
+
+/// Register a new account with the given credentials, driving the UIAA stages with the frontend,
+/// then return the serialized session like [`super::login::login_and_persist_matrix_session`].
+pub(crate) async fn register_and_persist_matrix_session(
+    client: &Client,
+    payload: MatrixRegistrationPayload,
+    registration_receiver: &mut mpsc::Receiver<MatrixRegistrationEvent>,
+) -> anyhow::Result<String> {
+    let matrix_auth = client.matrix_auth();
+    let mut auth: Option<AuthData> = None;
+    // Email validation is kept between attempts, so a failed stage doesn't send a new email.
+    let mut email_credentials: Option<(uiaa::ThirdpartyIdCredentials, String)> = None;
+
+    loop {
+        let mut request = register::v3::Request::new();
+        request.username = Some(payload.username.clone());
+        request.password = Some(payload.password.clone());
+        request.initial_device_display_name = Some(payload.client_name.clone());
+        request.auth = auth.take();
+
+        let err = match matrix_auth.register(request).await {
+            Ok(response) => {
+                info!("Registered new account {}", response.user_id);
+                break;
+            }
+            Err(err) => err,
+        };
+        let info = err.as_uiaa_response().ok_or(anyhow!(err.to_string()))?;
+        let error = info.auth_error.as_ref().map(|e| e.message.clone());
+
+        let flow = info
+            .flows
+            .iter()
+            .find(|f| is_flow_supported(f))
+            .ok_or(anyhow!("The homeserver has no supported registration flow"))?;
+        let stage = flow
+            .stages
+            .iter()
+            .find(|s| !info.completed.contains(s))
+            .ok_or(anyhow!("All registration stages are completed"))?;
+        debug!("Next registration stage: {stage}");
+
+        auth = Some(match stage {
+            AuthType::Dummy => {
+                let mut dummy = uiaa::Dummy::new();
+                dummy.session = info.session.clone();
+                AuthData::Dummy(dummy)
+            }
+            AuthType::Terms => {
+                emit_stage(
+                    RegistrationStage::Terms {
+                        policies: terms_policies(info),
+                    },
+                    error,
+                )?;
+                loop {
+                    if let MatrixRegistrationEvent::AcceptTerms =
+                        next_registration_event(registration_receiver).await?
+                    {
+                        break;
+                    }
+                }
+                let mut terms = uiaa::Terms::new();
+                terms.session = info.session.clone();
+                AuthData::Terms(terms)
+            }
+            AuthType::RegistrationToken => {
+                emit_stage(RegistrationStage::RegistrationToken, error)?;
+                let token = loop {
+                    if let MatrixRegistrationEvent::RegistrationToken { token } =
+                        next_registration_event(registration_receiver).await?
+                    {
+                        break token;
+                    }
+                };
+                let mut registration_token = uiaa::RegistrationToken::new(token);
+                registration_token.session = info.session.clone();
+                AuthData::RegistrationToken(registration_token)
+            }
+            AuthType::EmailIdentity => {
+                let (credentials, email) = match email_credentials.take() {
+                    // The validation email was already sent, the previous attempt
+                    // was probably submitted before the user clicked the link.
+                    Some((credentials, email)) => {
+                        emit_stage(
+                            RegistrationStage::AwaitingEmailValidation {
+                                email: email.clone(),
+                            },
+                            error,
+                        )?;
+                        (credentials, email)
+                    }
+                    None => {
+                        emit_stage(RegistrationStage::EmailIdentity, error)?;
+                        let email = loop {
+                            if let MatrixRegistrationEvent::RequestEmailValidation { email } =
+                                next_registration_event(registration_receiver).await?
+                            {
+                                break email;
+                            }
+                        };
+                        let client_secret = ClientSecret::new();
+                        let response = client
+                            .send(request_registration_token_via_email::v3::Request::new(
+                                client_secret.clone(),
+                                email.clone(),
+                                UInt::from(1u32),
+                            ))
+                            .await?;
+                        emit_stage(
+                            RegistrationStage::AwaitingEmailValidation {
+                                email: email.clone(),
+                            },
+                            None,
+                        )?;
+                        (
+                            uiaa::ThirdpartyIdCredentials::new(response.sid, client_secret),
+                            email,
+                        )
+                    }
+                };
+                loop {
+                    if let MatrixRegistrationEvent::EmailValidated =
+                        next_registration_event(registration_receiver).await?
+                    {
+                        break;
+                    }
+                }
+                email_credentials = Some((credentials.clone(), email));
+                AuthData::new(
+                    AuthType::EmailIdentity.as_str(),
+                    info.session.clone(),
+                    json!({ "threepid_creds": credentials })
+                        .as_object()
+                        .cloned()
+                        .unwrap_or_default(),
+                )?
+            }
+            _ => bail!("Unsupported registration stage {stage}"),
+        });
+    }
+
+    let user_session = matrix_auth.session().ok_or(anyhow!(
+        "The homeserver didn't log in the registered account"
+    ))?;
+
+    set_client_and_serialize_session(matrix_sdk::AuthSession::Matrix(user_session))
+}
+
+/// Returns the next registration event, or an error if the registration is cancelled.
+async fn next_registration_event(
+    registration_receiver: &mut mpsc::Receiver<MatrixRegistrationEvent>,
+) -> anyhow::Result<MatrixRegistrationEvent> {
+    match registration_receiver.recv().await {
+        Some(MatrixRegistrationEvent::Cancel) => bail!("Registration cancelled"),
+        Some(event) => Ok(event),
+        None => bail!("no registration sender to listen to"),
+    }
+}
+
+fn emit_stage(stage: RegistrationStage, error: Option<String>) -> anyhow::Result<()> {
+    get_event_bridge()?.emit(EmitEvent::RegistrationStage(RegistrationStageRequest {
+        stage,
+        error,
+    }));
+    Ok(())
+}
+
+/// Extract the policies to accept from the `m.login.terms` params, in English when available.
+fn terms_policies(info: &UiaaInfo) -> Vec<RegistrationPolicy> {
+    let Ok(Some(params)) = info.params::<LoginTermsParams>(&AuthType::Terms) else {
+        return Vec::new();
+    };
+    params
+        .policies
+        .into_iter()
+        .filter_map(|(id, policy)| {
+            let translation = policy
+                .translations
+                .get("en")
+                .or_else(|| policy.translations.values().next())?;
+            Some(RegistrationPolicy {
+                id,
+                version: policy.version.clone(),
+                name: translation.name.clone(),
+                url: translation.url.clone(),
+            })
+        })
+        .collect()
+}
@@ -11,11 +11,12 @@ use matrix_sdk::{
 };
 
 use std::sync::Arc;
+use tokio::task::JoinHandle;
 
 use crate::{
     init::{
         login::build_client,
-        singletons::{CLIENT, HAS_SESSION_STORED},
+        singletons::{CLIENT, CLIENT_SESSION, HAS_SESSION_STORED},
     },
     models::{
         events::{ToastNotificationRequest, ToastNotificationVariant},
@@ -64,12 +65,14 @@ pub enum SerializableAuthSession {
     OAuth(SerializableOAuthSession),
 }
 
-impl From<AuthSession> for SerializableAuthSession {
-    fn from(value: AuthSession) -> Self {
+impl TryFrom<AuthSession> for SerializableAuthSession {
+    type Error = anyhow::Error;
+
+    fn try_from(value: AuthSession) -> anyhow::Result<Self> {
         match value {
-            AuthSession::Matrix(m) => Self::Matrix(m),
-            AuthSession::OAuth(o) => Self::OAuth(o.into()),
-            _ => panic!("This type of auth is not yet supported"),
+            AuthSession::Matrix(m) => Ok(Self::Matrix(m)),
+            AuthSession::OAuth(o) => Ok(Self::OAuth(o.into())),
+            _ => Err(anyhow!("This type of auth is not yet supported")),
         }
     }
 }
@@ -112,11 +115,11 @@ impl From<SerializableOAuthSession> for OAuthSession {
 }
 
 impl FullMatrixSession {
-    pub fn new(client_session: ClientSession, user_session: AuthSession) -> Self {
-        FullMatrixSession {
+    pub fn new(client_session: ClientSession, user_session: AuthSession) -> anyhow::Result<Self> {
+        Ok(FullMatrixSession {
             client_session,
-            user_session: user_session.into(),
-        }
+            user_session: user_session.try_into()?,
+        })
     }
 }
 
@@ -126,13 +129,16 @@ pub async fn restore_client_from_session(session: FullMatrixSession) -> anyhow::
         user_session,
     } = session;
 
-    let (client, _) = build_client(None, Some(client_session)).await?;
+    let (client, client_session) = build_client(None, Some(client_session)).await?;
 
     client.restore_session(user_session).await?;
 
     CLIENT
         .set(client.clone())
-        .expect("BUG: CLIENT already set!");
+        .map_err(|_| anyhow!("BUG: CLIENT already set!"))?;
+    CLIENT_SESSION
+        .set(client_session)
+        .map_err(|_| anyhow!("BUG: CLIENT_SESSION already set!"))?;
 
     Ok(client)
 }
@@ -142,15 +148,11 @@ pub async fn try_restore_session_to_state(
 ) -> crate::Result<Option<Client>> {
     match session_option {
         None => {
-            HAS_SESSION_STORED
-                .set(false)
-                .map_err(|b| anyhow!("HAS_SESSION_STORED was already defined. {b}"))?;
+            HAS_SESSION_STORED.replace(false);
             Ok(None)
         }
         Some(session_string) => {
-            HAS_SESSION_STORED
-                .set(true)
-                .map_err(|b| anyhow!("HAS_SESSION_STORED was already defined. {b}"))?;
+            HAS_SESSION_STORED.replace(true);
             let session: FullMatrixSession =
                 serde_json::from_str(&session_string).map_err(|e| anyhow!(e))?;
             let initial_client = restore_client_from_session(session).await?;
@@ -163,9 +165,10 @@ pub async fn try_restore_session_to_state(
 /// whenever there are new tokens that have been received.
 ///
 /// This should always be set up whenever automatic refresh is happening.
-pub(crate) fn setup_token_background_save(updater: Arc<Box<dyn StateUpdater>>) {
+/// The returned task handle must be aborted when the session is torn down.
+pub(crate) fn setup_token_background_save(updater: Arc<Box<dyn StateUpdater>>) -> JoinHandle<()> {
     tokio::spawn(async move {
-        let client = CLIENT.wait();
+        let client = CLIENT.wait_async().await;
         while let Ok(update) = client.subscribe_to_session_changes().recv().await {
             match update {
                 matrix_sdk::SessionChange::UnknownToken(s) => {
@@ -184,7 +187,7 @@ pub(crate) fn setup_token_background_save(updater: Arc<Box<dyn StateUpdater>>) {
                 }
                 matrix_sdk::SessionChange::TokensRefreshed => {
                     // The tokens have been refreshed, persist them to disk.
-                    if let Err(err) = update_stored_session(client, updater.clone()).await {
+                    if let Err(err) = update_stored_session(&client, updater.clone()).await {
                         enqueue_toast_notification(ToastNotificationRequest::new(
                             format!("Failed to persist refreshed session. Error: {err}"),
                             None,
@@ -195,7 +198,7 @@ pub(crate) fn setup_token_background_save(updater: Arc<Box<dyn StateUpdater>>) {
                 }
             }
         }
-    });
+    })
 }
 
 /// Update the session stored in the keychain.
@@ -13,8 +13,9 @@ use matrix_sdk::{
 use matrix_sdk_ui::sync_service::SyncService;
 use tokio::{
     sync::{
-        broadcast,
+        Notify, broadcast,
         mpsc::{Receiver, UnboundedSender},
+        oneshot,
     },
     time::{Instant, interval},
 };
@@ -33,8 +34,8 @@ use crate::{
 /// Currently there is only one, but it can be cloned if we need more concurrent senders.
 pub static REQUEST_SENDER: OnceLock<UnboundedSender<MatrixRequest>> = OnceLock::new();
 
-/// The singleton sync service.
-pub static SYNC_SERVICE: OnceLock<SyncService> = OnceLock::new();
+/// The singleton sync service. It is cleared when the user logs out.
+pub static SYNC_SERVICE: ResettableSlot<Arc<SyncService>> = ResettableSlot::new();
 
 /// Flag set by `handle_rooms_loading_state` when all rooms are loaded.
 /// if rooms have been synced or not.
@@ -56,16 +57,112 @@ pub static TEMP_CLIENT: LazyLock<TempClientSlot> = LazyLock::new(|| TempClientSl
 pub static TEMP_CLIENT_SESSION: LazyLock<Mutex<Option<ClientSession>>> =
     LazyLock::new(|| Mutex::new(None));
 
+/// A slot that behaves like a `OnceLock`, except that its value can be taken out.
+/// This is used for the session related singletons, that must be cleared on logout
+/// so another session can be set up without restarting the app.
+pub struct ResettableSlot<T> {
+    lock: Mutex<Option<T>>,
+    cvar: Condvar,
+    notify: Notify,
+}
+
+impl<T: Clone> ResettableSlot<T> {
+    pub const fn new() -> Self {
+        Self {
+            lock: Mutex::new(None),
+            cvar: Condvar::new(),
+            notify: Notify::const_new(),
+        }
+    }
+
+    /// Returns a clone of the value, if any.
+    pub fn get(&self) -> Option<T> {
+        self.lock.lock().unwrap().clone()
+    }
+
+    /// Blocks the current thread until the slot is filled, then returns a clone of its value.
+    pub fn wait(&self) -> T {
+        let guard = self
+            .cvar
+            .wait_while(self.lock.lock().unwrap(), |value| value.is_none())
+            .unwrap();
+        guard.clone().expect("slot should be filled at this point")
+    }
+
+    /// Waits until the slot is filled, then returns a clone of its value.
+    /// Unlike [`Self::wait`], this doesn't block the thread, so it must be used in async tasks.
+    pub async fn wait_async(&self) -> T {
+        loop {
+            // Registered before checking the value, so a value set in between isn't missed.
+            let notified = self.notify.notified();
+            if let Some(value) = self.get() {
+                return value;
+            }
+            notified.await;
+        }
+    }
+
+    /// Fills the slot. Returns the given value back if the slot was already filled.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut guard = self.lock.lock().unwrap();
+        if guard.is_some() {
+            return Err(value);
+        }
+        *guard = Some(value);
+        self.cvar.notify_all();
+        self.notify.notify_waiters();
+        Ok(())
+    }
+
+    /// Empties the slot, returning its previous value.
+    pub fn take(&self) -> Option<T> {
+        self.lock.lock().unwrap().take()
+    }
+
+    /// Fills the slot, replacing its previous value if any.
+    pub fn replace(&self, value: T) -> Option<T> {
+        let previous = self.lock.lock().unwrap().replace(value);
+        self.cvar.notify_all();
+        self.notify.notify_waiters();
+        previous
+    }
+}
+
+impl<T: Clone> Default for ResettableSlot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// The logged-in Matrix client, which can be freely and cheaply cloned.
-pub static CLIENT: OnceLock<Client> = OnceLock::new();
+pub static CLIENT: ResettableSlot<Client> = ResettableSlot::new();
+
+/// Returns the logged-in Matrix client, or an error if no user is logged in.
+pub(crate) fn get_client() -> crate::Result<Client> {
+    CLIENT.get().ok_or(crate::Error::NotLoggedIn)
+}
+
+/// The data needed to rebuild the logged-in client, used to locate its database on logout.
+pub(crate) static CLIENT_SESSION: ResettableSlot<ClientSession> = ResettableSlot::new();
 
 /// The current User's ID
-pub static CURRENT_USER_ID: OnceLock<OwnedUserId> = OnceLock::new();
+pub static CURRENT_USER_ID: ResettableSlot<OwnedUserId> = ResettableSlot::new();
 
 /// Flag to be set once the frontend Login Store is up and ready
-pub static LOGIN_STORE_READY: OnceLock<bool> = OnceLock::new();
+pub static LOGIN_STORE_READY: ResettableSlot<bool> = ResettableSlot::new();
+
+/// Whether the adapter had a session stored when the current session started.
+/// It is set once the session is restored or not, and set to `false` on logout.
+pub static HAS_SESSION_STORED: ResettableSlot<bool> = ResettableSlot::new();
+
+/// A request to end the active session after a logout: the sender is used by the init task
+/// to return the torn down session once all its tasks are stopped.
+pub(crate) type SessionEndRequest = oneshot::Sender<Option<ClientSession>>;
 
-pub static HAS_SESSION_STORED: OnceLock<bool> = OnceLock::new();
+/// Used to ask the init task to stop the session tasks and workers,
+/// tear down the active session, and go back to the login step.
+pub(crate) static SESSION_END_SENDER: OnceLock<UnboundedSender<SessionEndRequest>> =
+    OnceLock::new();
 
 #[derive(Debug, Clone)]
 pub enum UIUpdateMessage {
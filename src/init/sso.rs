@@ -0,0 +1,59 @@
+use anyhow::anyhow;
+use matrix_sdk::Client;
+use tokio::sync::mpsc;
+use tracing::info;
+use url::Url;
+
+use crate::{
+    init::{login::set_client_and_serialize_session, singletons::get_event_bridge},
+    models::events::EmitEvent,
+};
+
+/// Log in the user with the legacy SSO flow (`m.login.sso`), for homeservers
+/// that do not support the OAuth 2.0 API.
+///
+/// The SSO URL is sent to the frontend, then the `loginToken` received on the callback URL
+/// is used to complete a `m.login.token` login. On failure, the error is returned
+/// so the login can be retried from the homeserver selection.
+pub(crate) async fn login_with_sso(
+    client: &Client,
+    sso_deeplink_receiver: &mut mpsc::Receiver<Url>,
+    callback_url: &Url,
+) -> anyhow::Result<String> {
+    // Without identity provider, the homeserver lets the user choose one.
+    // Frontends can also build a provider specific URL with the `get_sso_login_url` command.
+    let url = client
+        .matrix_auth()
+        .get_sso_login_url(callback_url.as_str(), None)
+        .await?;
+
+    // Send SSO URL to frontend
+    get_event_bridge()?.emit(EmitEvent::SsoUrl(url));
+
+    let callback = sso_deeplink_receiver
+        .recv()
+        .await
+        .ok_or(anyhow!("no url was sent"))?;
+
+    finish_sso_login(client, callback).await
+}
+
+/// Completes a legacy SSO login with the `loginToken` of the given callback URL.
+/// A callback without `loginToken`, e.g. to cancel the login, fails it so the user can retry.
+pub(crate) async fn finish_sso_login(client: &Client, callback: Url) -> anyhow::Result<String> {
+    let login_token = callback
+        .query_pairs()
+        .find(|(key, _)| key == "loginToken")
+        .map(|(_, value)| value.into_owned())
+        .ok_or(anyhow!("No loginToken in the SSO callback URL"))?;
+
+    let matrix_auth = client.matrix_auth();
+    matrix_auth.login_token(&login_token).await?;
+    info!("Logged in with SSO");
+
+    let user_session = matrix_auth
+        .session()
+        .ok_or(anyhow!("Should have session after login"))?;
+
+    set_client_and_serialize_session(matrix_sdk::AuthSession::Matrix(user_session))
+}
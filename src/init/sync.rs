@@ -28,10 +28,12 @@ pub async fn sync(
     client: Client,
     state_updaters: Arc<Box<dyn StateUpdater>>,
 ) -> anyhow::Result<()> {
-    let sync_service = SyncService::builder(client)
-        .with_offline_mode()
-        .build()
-        .await?;
+    let sync_service = Arc::new(
+        SyncService::builder(client.clone())
+            .with_offline_mode()
+            .build()
+            .await?,
+    );
 
     // Start the sync service
     sync_service.start().await;
@@ -43,7 +45,7 @@ pub async fn sync(
 
     let all_rooms_list = room_list_service.all_rooms().await?;
     handle_rooms_loading_state(all_rooms_list.loading_state());
-    handle_sync_service_state(sync_service_state, state_updaters);
+    handle_sync_service_state(client, sync_service_state, state_updaters);
 
     // TODO: paginate the rooms instead of getting them all
     let (room_diff_stream, room_list_dynamic_entries_controller) =
@@ -52,7 +54,7 @@ pub async fn sync(
     room_list_dynamic_entries_controller.set_filter(Box::new(|_room| true));
 
     let mut all_known_rooms: Vector<RoomListServiceRoomInfo> = Vector::new();
-    let current_user_id = CURRENT_USER_ID.get().cloned();
+    let current_user_id = CURRENT_USER_ID.get();
 
     pin_mut!(room_diff_stream);
     while let Some(batch) = room_diff_stream.next().await {
@@ -137,7 +139,7 @@ pub async fn sync(
                     trace!("room_list: diff PushFront");
                     let new_room = RoomListServiceRoomInfo::from_room(
                         new_room.into_inner(),
-                        &CURRENT_USER_ID.get().cloned(),
+                        &CURRENT_USER_ID.get(),
                     )
                     .await;
                     let room_id = new_room.room_id.clone();
@@ -151,7 +153,7 @@ pub async fn sync(
                     trace!("room_list: diff PushBack");
                     let new_room = RoomListServiceRoomInfo::from_room(
                         new_room.into_inner(),
-                        &CURRENT_USER_ID.get().cloned(),
+                        &CURRENT_USER_ID.get(),
                     )
                     .await;
                     let room_id = new_room.room_id.clone();
@@ -173,7 +175,7 @@ pub async fn sync(
                             &mut peekable_diffs,
                             &mut all_known_rooms,
                             &room_list_service,
-                            &CURRENT_USER_ID.get().cloned(),
+                            &CURRENT_USER_ID.get(),
                         )
                         .await?;
                     }
@@ -190,7 +192,7 @@ pub async fn sync(
                             &mut peekable_diffs,
                             &mut all_known_rooms,
                             &room_list_service,
-                            &CURRENT_USER_ID.get().cloned(),
+                            &CURRENT_USER_ID.get(),
                         )
                         .await?;
                     }
@@ -202,7 +204,7 @@ pub async fn sync(
                     trace!("room_list: diff Insert at {index}");
                     let new_room = RoomListServiceRoomInfo::from_room(
                         new_room.into_inner(),
-                        &CURRENT_USER_ID.get().cloned(),
+                        &CURRENT_USER_ID.get(),
                     )
                     .await;
                     let room_id = new_room.room_id.clone();
@@ -357,6 +359,7 @@ async fn optimize_remove_then_add_into_update(
 }
 
 pub fn handle_sync_service_state(
+    client: Client,
     mut sync_service_state: Subscriber<sync_service::State>,
     state_updaters: Arc<Box<dyn StateUpdater>>,
 ) {
@@ -364,6 +367,12 @@ pub fn handle_sync_service_state(
         while let Some(state) = sync_service_state.next().await {
             debug!("Sync service changed state: {state:?}");
 
+            // Any sending error pauses the room's send queue, so resume the sending of the
+            // messages written while offline once the connection is back.
+            if matches!(state, sync_service::State::Running) {
+                client.send_queue().set_enabled(true).await;
+            }
+
             let _ = state_updaters.update_sync_service(FrontendSyncServiceState::new(state));
         }
     });
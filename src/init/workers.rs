@@ -1,42 +1,64 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::bail;
 use futures::{StreamExt, pin_mut};
 use matrix_sdk::{
     Client, RoomMemberships,
     ruma::{
-        RoomOrAliasId,
-        api::client::{
-            profile::{AvatarUrl, DisplayName},
-            receipt::create_receipt::v3::ReceiptType,
-            room::create_room,
+        OwnedEventId, OwnedRoomId, RoomOrAliasId,
+        api::{
+            client::{
+                profile::{AvatarUrl, DisplayName},
+                receipt::create_receipt::v3::ReceiptType,
+                reporting::report_user,
+                room::create_room,
+            },
+            error::ErrorKind,
+        },
+        events::{
+            AnyMessageLikeEventContent, Mentions,
+            poll::{
+                unstable_end::UnstablePollEndEventContent,
+                unstable_response::UnstablePollResponseEventContent,
+            },
+            relation::Thread,
+            room::message::{
+                LocationMessageEventContent, MessageType, Relation, RoomMessageEventContent,
+            },
+            sticker::StickerEventContent,
         },
-        events::room::message::RoomMessageEventContent,
         matrix_uri::MatrixId,
     },
 };
-use matrix_sdk_ui::timeline::{RoomExt, TimelineFocus, TimelineReadReceiptTracking};
+use matrix_sdk_ui::timeline::{
+    EncryptedMessage, EventSendState, RoomExt, TimelineEventFocusThreadMode, TimelineEventItemId,
+    TimelineFocus, TimelineReadReceiptTracking,
+};
 use tokio::{
     runtime::Handle,
     sync::{
         Mutex,
         mpsc::{self, UnboundedReceiver},
-        watch,
+        oneshot, watch,
     },
-    task::JoinHandle,
+    task::{AbortHandle, JoinSet},
 };
 use tracing::{debug, error, info, trace, warn};
 
 use crate::{
     events::timeline::{
         PaginationDirection, PerTimelineDetails, TimelineKind, TimelineUpdate,
-        timeline_subscriber_handler,
+        pinned_events_subscriber_handler, timeline_subscriber_handler,
     },
     init::singletons::{
         CLIENT, CURRENT_USER_ID, UIUpdateMessage, broadcast_event, get_event_bridge,
     },
     models::{
-        async_requests::{MatrixRequest, submit_async_request},
+        async_requests::{MatrixRequest, RequestResponder, TimelineCreated, submit_async_request},
         events::{
             EmitEvent, MatrixUpdateCurrentActiveRoom, ToastNotificationRequest,
             ToastNotificationVariant,
@@ -45,11 +67,16 @@ use crate::{
         state_updater::StateUpdater,
     },
     room::{
+        frontend_events::shield::room_shield,
         joined_room::{UnreadMessageCount, get_timeline, get_timeline_and_sender},
+        link_preview::{
+            fetch_link_preview, notify_link_previews_changed, set_url_previews_enabled,
+        },
         notifications::{enqueue_toast_notification, process_toast_notifications},
         rooms_list::{
             RoomsCollectionStatus, RoomsList, RoomsListUpdate, enqueue_rooms_list_update,
         },
+        threads_list::{fetch_thread_root, paginate_threads_list, subscribe_to_thread_replies},
     },
     user::{
         user_power_level::UserPowerLevels,
@@ -58,14 +85,13 @@ use crate::{
             process_user_profile_updates,
         },
     },
-    utils::debounce_broadcast,
+    utils::{debounce_broadcast, emote_message_content, poll_start_content, text_message_content},
 };
 
 /// The main loop that actually uses a Matrix client
 pub async fn async_main_loop(
     client: Client,
     state_updaters: Arc<Box<dyn StateUpdater>>,
-    room_update_receiver: mpsc::Receiver<MatrixUpdateCurrentActiveRoom>,
 ) -> anyhow::Result<()> {
     let logged_in_user_id = client
         .user_id()
@@ -84,8 +110,6 @@ pub async fn async_main_loop(
     // Listen for updates to the ignored user list.
     // handle_ignore_user_list_subscriber(client.clone());
 
-    Handle::current().spawn(ui_worker(state_updaters.clone(), room_update_receiver));
-
     crate::init::sync::sync(client, state_updaters).await?;
 
     bail!("room list service sync loop ended unexpectedly")
@@ -95,15 +119,36 @@ pub async fn async_main_loop(
 ///
 /// All this thread does is wait for [`MatrixRequests`] from the main UI-driven non-async thread(s)
 /// and then executes them within an async runtime context.
+///
+/// A new worker is started for each session: the request receiver is locked for as long as
+/// the worker runs. Once `stop_receiver` fires, the tasks it spawned to handle the requests
+/// are aborted, and the worker returns after they have all stopped.
 pub async fn async_worker(
-    mut request_receiver: UnboundedReceiver<MatrixRequest>,
+    request_receiver: Arc<Mutex<UnboundedReceiver<MatrixRequest>>>,
+    mut stop_receiver: oneshot::Receiver<()>,
 ) -> anyhow::Result<()> {
     debug!("Started async_worker task.");
+    let mut request_receiver = request_receiver.lock().await;
+    // The async tasks that are spawned to handle the requests, as they hold the client.
+    let mut tasks: JoinSet<()> = JoinSet::new();
     // The async tasks that are spawned to subscribe to changes in our own user's read receipts for each timeline.
-    let mut subscribers_own_user_read_receipts: HashMap<TimelineKind, JoinHandle<()>> =
-        HashMap::new();
+    // They are also in `tasks`, this is used to abort them once unsubscribed.
+    let mut subscribers_own_user_read_receipts: HashMap<TimelineKind, AbortHandle> = HashMap::new();
 
-    while let Some(request) = request_receiver.recv().await {
+    loop {
+        let request = tokio::select! {
+            request = request_receiver.recv() => match request {
+                Some(request) => request,
+                None => break,
+            },
+            // Remove the finished tasks, so the set doesn't grow for the whole session.
+            Some(_) = tasks.join_next() => continue,
+            _ = &mut stop_receiver => {
+                debug!("Stopping async_worker task...");
+                tasks.shutdown().await;
+                return Ok(());
+            }
+        };
         match request {
             MatrixRequest::PaginateTimeline {
                 timeline_kind,
@@ -116,7 +161,7 @@ pub async fn async_worker(
                 };
 
                 // Spawn a new async task that will make the actual pagination request.
-                let _paginate_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Starting {direction} pagination request for room {timeline_kind}...");
                     sender.send(TimelineUpdate::PaginationRunning(direction)).unwrap();
                     broadcast_event(UIUpdateMessage::RefreshUI);
@@ -162,7 +207,7 @@ pub async fn async_worker(
                 };
 
                 // Spawn a new async task that will make the actual edit request.
-                let _edit_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending request to edit message {timeline_event_item_id:?} in {timeline_kind}...");
                     let result = timeline.edit(&timeline_event_item_id, edited_content).await;
                     match result {
@@ -187,97 +232,24 @@ pub async fn async_worker(
                 room_id,
                 thread_root_event_id,
                 sender,
-            } => {
-                let main_room_timeline = {
-                    let Some(arc) = crate::room::joined_room::try_get_room_details(&room_id) else {
-                        error!("BUG: room info not found for get room members request {room_id}");
-                        continue;
-                    };
-                    let mut room_info = arc.lock().unwrap();
-
-                    if room_info
-                        .thread_timelines
-                        .contains_key(&thread_root_event_id)
-                    {
-                        continue;
-                    }
-                    let newly_pending = room_info
-                        .pending_thread_timelines
-                        .insert(thread_root_event_id.clone());
-                    if !newly_pending {
-                        continue;
-                    }
-                    room_info.main_timeline.timeline.clone()
-                };
+            } => create_focused_timeline(
+                &mut tasks,
+                TimelineKind::Thread {
+                    room_id,
+                    thread_root_event_id,
+                },
+                sender,
+            ),
 
-                let _create_thread_timeline_task = Handle::current().spawn(async move {
-                    debug!("Creating thread-focused timeline for room {room_id}, thread {thread_root_event_id}...");
-                    let build_result = main_room_timeline.room()
-                        .timeline_builder()
-                        .with_focus(TimelineFocus::Thread {
-                            root_event_id: thread_root_event_id.clone(),
-                        })
-                        .track_read_marker_and_receipts(TimelineReadReceiptTracking::AllEvents)
-                        .build()
-                        .await;
-
-                    match build_result {
-                        Ok(thread_timeline) => {
-                            let Some(arc) = crate::room::joined_room::try_get_room_details(&room_id)
-                            else {
-                                return;
-                            };
-                            let mut room_info = arc.lock().unwrap();
-                            debug!("Successfully created thread-focused timeline for room {room_id}, thread {thread_root_event_id}.");
-                            let thread_timeline = Arc::new(thread_timeline);
-                            let (timeline_update_sender, timeline_update_receiver) = crossbeam_channel::unbounded();
-                            let (request_sender, request_receiver) = watch::channel(Vec::new());
-                            let timeline_subscriber_handler_task = Handle::current().spawn(
-                                timeline_subscriber_handler(
-                                    main_room_timeline.room().clone(),
-                                    thread_timeline.clone(),
-                                    timeline_update_sender.clone(),
-                                    request_receiver,
-                                    Some(thread_root_event_id.clone()),
-                                )
-                            );
-                            room_info
-                                .pending_thread_timelines
-                                .remove(&thread_root_event_id);
-                            room_info.thread_timelines.insert(
-                                thread_root_event_id.clone(),
-                                PerTimelineDetails {
-                                    timeline: thread_timeline,
-                                    timeline_update_sender,
-                                    timeline_singleton_endpoints: Some((
-                                        timeline_update_receiver,
-                                        request_sender,
-                                    )),
-                                    timeline_subscriber_handler_task,
-                                },
-                            );
-                            let _ = sender.send(());
-                            broadcast_event(UIUpdateMessage::RefreshUI);
-                        }
-                        Err(error) => {
-                            error!("Failed to create thread-focused timeline for room {room_id}, thread {thread_root_event_id}: {error}");
-                            if let Some(arc) = crate::room::joined_room::try_get_room_details(&room_id)
-                            {
-                            let mut room_info = arc.lock().unwrap();
-                            room_info
-                                .pending_thread_timelines
-                                .remove(&thread_root_event_id);
-                            }
-                            enqueue_toast_notification(
-                                ToastNotificationRequest::new(
-                                format!("Failed to create thread-focused timeline. Please retry opening the thread again later.\n\nError: {error}"),
-                                None,
-                                ToastNotificationVariant::Error,
-                            ));
-                        }
-                    }
-                });
-            }
+            MatrixRequest::CreateFocusedEventTimeline {
+                room_id,
+                event_id,
+                sender,
+            } => create_focused_timeline(
+                &mut tasks,
+                TimelineKind::FocusedEvent { room_id, event_id },
+                sender,
+            ),
 
             MatrixRequest::FetchDetailsForEvent {
                 timeline_kind,
@@ -289,7 +261,7 @@ pub async fn async_worker(
                 };
 
                 // Spawn a new async task that will make the actual fetch request.
-                let _fetch_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending request to fetch details for event {event_id} in room {timeline_kind}...");
                     let result = timeline.fetch_details_for_event(&event_id).await;
                     match result {
@@ -307,6 +279,22 @@ pub async fn async_worker(
                 });
             }
 
+            MatrixRequest::JumpToEvent {
+                timeline_kind,
+                event_id,
+            } => {
+                let Some((_timeline, sender)) = get_timeline_and_sender(&timeline_kind) else {
+                    error!("BUG: {timeline_kind} not found when jumping to event {event_id}");
+                    continue;
+                };
+                // The RoomScreen owns the requests to search for an event in the timeline,
+                // so we let it start the backwards search.
+                if sender.send(TimelineUpdate::JumpToEvent(event_id)).is_err() {
+                    warn!("Failed to send the jump request to {timeline_kind}");
+                }
+                broadcast_event(UIUpdateMessage::RefreshUI);
+            }
+
             MatrixRequest::SyncRoomMemberList { timeline_kind } => {
                 let Some((timeline, sender)) = get_timeline_and_sender(&timeline_kind) else {
                     trace!("Skipping pagination request for unknown {timeline_kind}");
@@ -314,7 +302,7 @@ pub async fn async_worker(
                 };
 
                 // Spawn a new async task that will make the actual fetch request.
-                let _fetch_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending sync room members request for room {timeline_kind}...");
                     timeline.fetch_members().await;
                     debug!("Completed sync room members request for room {timeline_kind}.");
@@ -331,7 +319,7 @@ pub async fn async_worker(
             }
             MatrixRequest::JoinRoom { room_id } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _join_room_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending request to join room {room_id}...");
                     if let Some(room) = client.get_room(&room_id) {
                         match room.join().await {
@@ -364,7 +352,7 @@ pub async fn async_worker(
             }
             MatrixRequest::LeaveRoom { room_id } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _leave_room_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending request to leave room {room_id}...");
                     if let Some(room) = client.get_room(&room_id) {
                         match room.leave().await {
@@ -405,7 +393,7 @@ pub async fn async_worker(
                     continue;
                 };
 
-                let _get_members_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     let room = timeline.room();
 
                     if local_only {
@@ -429,6 +417,9 @@ pub async fn async_worker(
                             .send(TimelineUpdate::RoomMembersListFetched { members })
                             .unwrap();
                     }
+                    sender
+                        .send(TimelineUpdate::RoomShield(room_shield(room).await))
+                        .unwrap();
 
                     broadcast_event(UIUpdateMessage::RefreshUI);
                 });
@@ -441,7 +432,7 @@ pub async fn async_worker(
                 sender,
             } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _fetch_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
 
                     let mut update = None;
 
@@ -499,12 +490,15 @@ pub async fn async_worker(
 
                     if let Some(upd) = update {
                         if let Some(sender) = sender {
-                         let _ = sender.send(upd.get_user_profile_from_update().cloned());
+                            sender.respond(upd.get_user_profile_from_update().cloned());
                         }
                         debug!("Successfully completed get user profile request: user: {user_id}, room: {room_id:?}, local_only: {local_only}.");
                         enqueue_user_profile_update(upd);
                     } else {
                         error!("Failed to get user profile: user: {user_id}, room: {room_id:?}, local_only: {local_only}.");
+                        if let Some(sender) = sender {
+                            sender.respond_error(crate::Error::NotFound(format!("Profile of user {user_id} not found")));
+                        }
                     }
                 });
             }
@@ -513,7 +507,7 @@ pub async fn async_worker(
                     trace!("Skipping pagination request for unknown {timeline_kind}");
                     continue;
                 };
-                let _get_unreads_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     match sender.send(TimelineUpdate::NewUnreadMessagesCount(
                         UnreadMessageCount::Known(timeline.room().num_unread_messages())
                     )) {
@@ -534,11 +528,22 @@ pub async fn async_worker(
             }
             MatrixRequest::IgnoreUser {
                 ignore,
-                room_member,
+                user_id,
                 room_id,
             } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _ignore_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
+                    let Some(room) = client.get_room(&room_id) else {
+                        error!("BUG: client could not get room with ID {room_id}");
+                        return;
+                    };
+                    let room_member = match room.get_member(&user_id).await {
+                        Ok(Some(room_member)) => room_member,
+                        _ => {
+                            warn!("Ignore user request: user {user_id} was not a member of room {room_id}");
+                            return;
+                        }
+                    };
                     let user_id = room_member.user_id();
                     debug!("Sending request to {}ignore user: {user_id}...", if ignore { "" } else { "un" });
                     let ignore_result = if ignore {
@@ -560,7 +565,7 @@ pub async fn async_worker(
                     // has changed, i.e., the user has been (un)ignored.
                     // We then need to send an update to replace the cached `RoomMember`
                     // with the now-stale ignored state.
-                    if let Some(room) = client.get_room(&room_id) && let Ok(Some(new_room_member)) = room.get_member(user_id).await {
+                    if let Ok(Some(new_room_member)) = room.get_member(user_id).await {
                             debug!("Enqueueing user profile update for user {user_id}, who went from {}ignored to {}ignored.",
                                 if room_member.is_ignored() { "" } else { "un" },
                                 if new_room_member.is_ignored() { "" } else { "un" },
@@ -592,7 +597,7 @@ pub async fn async_worker(
                     error!("BUG: client/room not found for typing notice request {room_id}");
                     continue;
                 };
-                let _typing_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     if let Err(e) = room.typing_notice(typing).await {
                         warn!("Failed to send typing notice to room {room_id}: {e:?}");
                     }
@@ -643,7 +648,7 @@ pub async fn async_worker(
                     )
                 };
 
-                let _typing_notices_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     while let Ok(user_ids) = typing_notice_receiver.recv().await {
                         debug!("Received typing notifications for room {room_id}: {user_ids:?}");
                         let mut users = Vec::with_capacity(user_ids.len());
@@ -670,10 +675,10 @@ pub async fn async_worker(
                 subscribe,
             } => {
                 if !subscribe {
-                    if let Some(task_handler) =
+                    if let Some(subscriber) =
                         subscribers_own_user_read_receipts.remove(&timeline_kind)
                     {
-                        task_handler.abort();
+                        subscriber.abort();
                     }
                     continue;
                 }
@@ -683,11 +688,11 @@ pub async fn async_worker(
                 };
 
                 let timeline_kind_clone = timeline_kind.clone();
-                let subscribe_own_read_receipt_task = Handle::current().spawn(async move {
+                let subscribe_own_read_receipt_task = tasks.spawn(async move {
                     let update_receiver = timeline.subscribe_own_user_read_receipts_changed().await;
                     pin_mut!(update_receiver);
                     if let Some(client_user_id) = CURRENT_USER_ID.get() {
-                        if let Some((event_id, receipt)) = timeline.latest_user_read_receipt(client_user_id).await {
+                        if let Some((event_id, receipt)) = timeline.latest_user_read_receipt(&client_user_id).await {
                             trace!("Received own user read receipt for {timeline_kind}: {receipt:?}, event ID: {event_id:?}");
                             if sender.send(TimelineUpdate::OwnUserReadReceipt(receipt)).is_err() {
                                 error!("Failed to send own user read receipt to UI.");
@@ -695,7 +700,7 @@ pub async fn async_worker(
                         }
 
                         while update_receiver.next().await.is_some() {
-                            if let Some((_, receipt)) = timeline.latest_user_read_receipt(client_user_id).await {
+                            if let Some((_, receipt)) = timeline.latest_user_read_receipt(&client_user_id).await {
                                 if sender.send(TimelineUpdate::OwnUserReadReceipt(receipt)).is_err() {
                                     error!("Failed to send own user read receipt to UI.");
                                 }
@@ -720,12 +725,15 @@ pub async fn async_worker(
                         }
                     }
                 });
-                subscribers_own_user_read_receipts
-                    .insert(timeline_kind_clone, subscribe_own_read_receipt_task);
+                if let Some(previous_subscriber) = subscribers_own_user_read_receipts
+                    .insert(timeline_kind_clone, subscribe_own_read_receipt_task)
+                {
+                    previous_subscriber.abort();
+                }
             }
             MatrixRequest::ResolveRoomAlias(room_alias) => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _resolve_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending resolve room alias request for {room_alias}...");
                     let res = client.resolve_room_alias(&room_alias).await;
                     debug!("Resolved room alias {room_alias} to: {res:?}");
@@ -739,30 +747,50 @@ pub async fn async_worker(
                 let Some(client) = CLIENT.get() else { continue };
                 let media = client.media();
 
-                let _fetch_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending fetch media request for {media_request:?}...");
                     let res = media.get_media_content(&media_request, true).await;
-                    if let Err(e) = content_sender.send(res) {
-                        error!("Cannot send media content. {e:?}");
-                    };
+                    content_sender.respond(res);
                 });
             }
 
             MatrixRequest::SendTextMessage {
                 timeline_kind,
                 message,
+                format,
+                mentioned_user_ids,
+                mention_room,
                 replied_to_id,
             } => {
-                // TODO: use this timeline `_sender` once we support sending-message status/operations in the UI.
-                let Some((timeline, _sender)) = get_timeline_and_sender(&timeline_kind) else {
+                // The send state of the message is exposed by its local echo in the timeline.
+                let Some(timeline) = get_timeline(&timeline_kind) else {
                     trace!("BUG: {timeline_kind} not found for send message request");
                     continue;
                 };
 
                 // Spawn a new async task that will send the actual message.
-                let _send_message_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Sending message to room {timeline_kind}: {message:?}...");
-                    let message_content = RoomMessageEventContent::text_plain(message);
+                    let mut mentioned_users = Vec::with_capacity(mentioned_user_ids.len());
+                    for user_id in mentioned_user_ids {
+                        let name = timeline
+                            .room()
+                            .get_member_no_sync(&user_id)
+                            .await
+                            .ok()
+                            .flatten()
+                            .and_then(|m| m.display_name().map(|d| d.to_owned()))
+                            .unwrap_or_else(|| user_id.to_string());
+                        mentioned_users.push((user_id, name));
+                    }
+                    // Always set the intentional mentions, so that receiving clients
+                    // don't look for mentions in the body.
+                    let mut mentions = Mentions::with_user_ids(
+                        mentioned_users.iter().map(|(user_id, _)| user_id.clone()),
+                    );
+                    mentions.room = mention_room;
+                    let message_content = text_message_content(message, format, &mentioned_users)
+                        .add_mentions(mentions);
                     if let Some(replied_event_id) = replied_to_id {
                         match timeline
                             .send_reply(message_content.into(), replied_event_id)
@@ -799,6 +827,134 @@ pub async fn async_worker(
                 });
             }
 
+            MatrixRequest::SendEmote {
+                timeline_kind,
+                message,
+                format,
+            } => {
+                let content = emote_message_content(message, format);
+                send_content_if_allowed(
+                    &mut tasks,
+                    timeline_kind,
+                    content.into(),
+                    UserPowerLevels::_can_send_message,
+                    "emote",
+                );
+            }
+            MatrixRequest::SendLocation {
+                timeline_kind,
+                geo_uri,
+                description,
+            } => {
+                if !geo_uri.starts_with("geo:") {
+                    warn!("Refusing to share location {geo_uri:?}, it is not a geo URI.");
+                    enqueue_toast_notification(ToastNotificationRequest::new(
+                        "Failed to share the location: invalid geo URI.".to_owned(),
+                        None,
+                        ToastNotificationVariant::Error,
+                    ));
+                    continue;
+                }
+                let body = description.unwrap_or_else(|| format!("Location: {geo_uri}"));
+                let content = RoomMessageEventContent::new(MessageType::Location(
+                    LocationMessageEventContent::new(body, geo_uri),
+                ));
+                send_content_if_allowed(
+                    &mut tasks,
+                    timeline_kind,
+                    content.into(),
+                    UserPowerLevels::_can_send_location,
+                    "location",
+                );
+            }
+            MatrixRequest::SendSticker {
+                timeline_kind,
+                body,
+                url,
+                info,
+            } => {
+                let content = StickerEventContent::new(body, info, url);
+                send_content_if_allowed(
+                    &mut tasks,
+                    timeline_kind,
+                    content.into(),
+                    UserPowerLevels::_can_send_sticker,
+                    "sticker",
+                );
+            }
+
+            MatrixRequest::FetchLinkPreview { timeline_kind, url } => {
+                let Some(client) = CLIENT.get() else { continue };
+                let Some((_timeline, sender)) = get_timeline_and_sender(&timeline_kind) else {
+                    trace!("Skipping link preview request for unknown {timeline_kind}");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    match fetch_link_preview(&client, url.clone()).await {
+                        Ok(Some(_preview)) => {
+                            if sender.send(TimelineUpdate::LinkPreviewsChanged).is_err() {
+                                warn!("Failed to send the link preview update to {timeline_kind}");
+                                return;
+                            }
+                            broadcast_event(UIUpdateMessage::RefreshUI);
+                        }
+                        Ok(None) => debug!("No preview available for link {url}"),
+                        Err(e) => warn!("Failed to fetch the preview of link {url}: {e:?}"),
+                    }
+                });
+            }
+            MatrixRequest::SetUrlPreviewsEnabled { room_id, enabled } => {
+                let Some(room) = CLIENT.get().and_then(|c| c.get_room(&room_id)) else {
+                    warn!("BUG: client/room not found when setting URL previews, room: {room_id}");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    if let Err(e) = set_url_previews_enabled(&room, enabled).await {
+                        warn!("Failed to set URL previews in room {room_id}: {e:?}");
+                        enqueue_toast_notification(ToastNotificationRequest::new(
+                            format!("Failed to change the URL previews setting. Error: {e}"),
+                            None,
+                            ToastNotificationVariant::Error,
+                        ));
+                        return;
+                    }
+                    notify_link_previews_changed(room_id);
+                });
+            }
+            MatrixRequest::RetryDecryption { timeline_kind } => {
+                let Some(timeline) = get_timeline(&timeline_kind) else {
+                    error!("BUG: {timeline_kind} not found when retrying decryption");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let session_ids: BTreeSet<String> = timeline
+                        .items()
+                        .await
+                        .iter()
+                        .filter_map(|item| {
+                            match item.as_event()?.content().as_unable_to_decrypt()? {
+                                EncryptedMessage::MegolmV1AesSha2 { session_id, .. } => {
+                                    Some(session_id.clone())
+                                }
+                                _ => None,
+                            }
+                        })
+                        .collect();
+                    if session_ids.is_empty() {
+                        return;
+                    }
+                    debug!(
+                        "Retrying decryption of {} sessions in {timeline_kind}",
+                        session_ids.len()
+                    );
+                    // The timeline sends the decrypted items as usual.
+                    timeline.retry_decryption(session_ids).await;
+                });
+            }
+
             MatrixRequest::ReadReceipt {
                 timeline_kind,
                 event_id,
@@ -809,7 +965,7 @@ pub async fn async_worker(
                     continue;
                 };
 
-                let _send_rr_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     match timeline.send_single_receipt(receipt_type, event_id.clone()).await {
                         Ok(sent) => debug!("{} read receipt to room {timeline_kind} for event {event_id}", if sent { "Sent" } else { "Already sent" }),
                         Err(_e) => warn!("Failed to send read receipt to room {timeline_kind} for event {event_id}; error: {_e:?}"),
@@ -831,7 +987,7 @@ pub async fn async_worker(
                     error!("BUG: {timeline_kind} not found when marking room as read");
                     continue;
                 };
-                let _send_frr_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     match timeline.mark_as_read(ReceiptType::FullyRead).await {
                         Ok(sent) => debug!(
                             "{} fully read receipt to room {timeline_kind}",
@@ -863,12 +1019,12 @@ pub async fn async_worker(
                     continue;
                 };
 
-                let _power_levels_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     match timeline.room().power_levels().await {
                         Ok(power_levels) => {
                             debug!("Successfully fetched power levels for room {timeline_kind}.");
                             if let Err(e) = sender.send(TimelineUpdate::UserPowerLevels(
-                                UserPowerLevels::from(&power_levels, user_id),
+                                UserPowerLevels::from(&power_levels, &user_id),
                             )) {
                                 warn!("Failed to send the result of if user can send message: {e}")
                             }
@@ -892,7 +1048,7 @@ pub async fn async_worker(
                     continue;
                 };
 
-                let _toggle_reaction_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     debug!("Toggle Reaction to room {timeline_kind}: ...");
                     match timeline.toggle_reaction(&timeline_event_id, &reaction).await {
                         Ok(_send_handle) => {
@@ -915,7 +1071,7 @@ pub async fn async_worker(
                     continue;
                 };
 
-                let _redact_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     match timeline.redact(&timeline_event_id, reason.as_deref()).await {
                         Ok(()) => {
                             debug!("Successfully redacted message in room {timeline_kind}.");
@@ -936,9 +1092,372 @@ pub async fn async_worker(
                     }
                 });
             }
+            MatrixRequest::SubscribeToPinnedEvents { room_id, subscribe } => {
+                let Some(room_info) = crate::room::joined_room::try_get_room_details(&room_id)
+                else {
+                    error!(
+                        "BUG: room info not found for subscribe to pinned events request, room {room_id}"
+                    );
+                    continue;
+                };
+                let mut lock = room_info.lock().unwrap();
+                if !subscribe {
+                    if let Some(task) = lock.pinned_events_subscriber.take() {
+                        task.abort();
+                    }
+                    continue;
+                }
+                if lock.pinned_events_subscriber.is_some() {
+                    debug!("Note: room {room_id} is already subscribed to pinned events.");
+                    continue;
+                }
+                let Some(room) = CLIENT.get().and_then(|c| c.get_room(&room_id)) else {
+                    warn!(
+                        "BUG: client/room not found when subscribing to pinned events request, room: {room_id}"
+                    );
+                    continue;
+                };
+                let timeline_update_sender = lock.main_timeline.timeline_update_sender.clone();
+                lock.pinned_events_subscriber = Some(Handle::current().spawn(
+                    pinned_events_subscriber_handler(room, timeline_update_sender),
+                ));
+            }
+            MatrixRequest::PaginateThreadsList {
+                room_id,
+                filter,
+                from,
+            } => {
+                let Some(room_info) = crate::room::joined_room::try_get_room_details(&room_id)
+                else {
+                    error!(
+                        "BUG: room info not found for paginate threads list request, room {room_id}"
+                    );
+                    continue;
+                };
+                let Some(room) = CLIENT.get().and_then(|c| c.get_room(&room_id)) else {
+                    warn!(
+                        "BUG: client/room not found when paginating threads list, room: {room_id}"
+                    );
+                    continue;
+                };
+                let timeline_update_sender = room_info
+                    .lock()
+                    .unwrap()
+                    .main_timeline
+                    .timeline_update_sender
+                    .clone();
+
+                tasks.spawn(async move {
+                    let reset = from.is_none();
+                    match paginate_threads_list(&room, filter, from).await {
+                        Ok((threads, next_batch)) => {
+                            if let Err(e) =
+                                timeline_update_sender.send(TimelineUpdate::ThreadsList {
+                                    filter,
+                                    threads,
+                                    next_batch,
+                                    reset,
+                                })
+                            {
+                                error!(
+                                    "Failed to send threads list update for room {room_id}: {e}"
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Failed to fetch the threads of room {room_id}: {e:?}");
+                            enqueue_toast_notification(ToastNotificationRequest::new(
+                                format!("Failed to load the threads of this room. Error: {e}"),
+                                None,
+                                ToastNotificationVariant::Error,
+                            ));
+                        }
+                    }
+                    broadcast_event(UIUpdateMessage::RefreshUI);
+                });
+            }
+            MatrixRequest::FetchThreadRoot {
+                room_id,
+                filter,
+                thread_root_event_id,
+            } => {
+                let Some(room_info) = crate::room::joined_room::try_get_room_details(&room_id)
+                else {
+                    error!(
+                        "BUG: room info not found for fetch thread root request, room {room_id}"
+                    );
+                    continue;
+                };
+                let Some(room) = CLIENT.get().and_then(|c| c.get_room(&room_id)) else {
+                    warn!("BUG: client/room not found when fetching thread root, room: {room_id}");
+                    continue;
+                };
+                let timeline_update_sender = room_info
+                    .lock()
+                    .unwrap()
+                    .main_timeline
+                    .timeline_update_sender
+                    .clone();
+
+                tasks.spawn(async move {
+                    match fetch_thread_root(&room, &thread_root_event_id).await {
+                        Ok(Some(thread)) => {
+                            if let Err(e) = timeline_update_sender
+                                .send(TimelineUpdate::NewThread {
+                                filter,
+                                thread: Box::new(thread),
+                            })
+                            {
+                                error!("Failed to send new thread update for room {room_id}: {e}");
+                            }
+                            broadcast_event(UIUpdateMessage::RefreshUI);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            warn!(
+                                "Failed to fetch the root of thread {thread_root_event_id} in room {room_id}: {e:?}"
+                            );
+                        }
+                    }
+                });
+            }
+            MatrixRequest::SubscribeToThreadReplies { room_id, subscribe } => {
+                let Some(room_info) = crate::room::joined_room::try_get_room_details(&room_id)
+                else {
+                    error!(
+                        "BUG: room info not found for subscribe to thread replies request, room {room_id}"
+                    );
+                    continue;
+                };
+                let mut lock = room_info.lock().unwrap();
+                if !subscribe {
+                    lock.thread_replies_subscriber.take();
+                    continue;
+                }
+                if lock.thread_replies_subscriber.is_some() {
+                    debug!("Note: room {room_id} is already subscribed to thread replies.");
+                    continue;
+                }
+                let Some(room) = CLIENT.get().and_then(|c| c.get_room(&room_id)) else {
+                    warn!(
+                        "BUG: client/room not found when subscribing to thread replies request, room: {room_id}"
+                    );
+                    continue;
+                };
+                let timeline_update_sender = lock.main_timeline.timeline_update_sender.clone();
+                lock.thread_replies_subscriber =
+                    Some(subscribe_to_thread_replies(&room, timeline_update_sender));
+            }
+            MatrixRequest::PinEvent { room_id, event_id } => {
+                pin_or_unpin_event(&mut tasks, room_id, event_id, true);
+            }
+            MatrixRequest::UnpinEvent { room_id, event_id } => {
+                pin_or_unpin_event(&mut tasks, room_id, event_id, false);
+            }
+            MatrixRequest::RetrySend {
+                timeline_kind,
+                transaction_id,
+            } => {
+                let Some(timeline) = get_timeline(&timeline_kind) else {
+                    error!("BUG: {timeline_kind} not found when retrying to send {transaction_id}");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let items = timeline.items().await;
+                    let Some(event) = items.iter().rev().find_map(|item| {
+                        item.as_event()
+                            .filter(|event| event.transaction_id() == Some(&*transaction_id))
+                    }) else {
+                        warn!("Local echo {transaction_id} not found in {timeline_kind}");
+                        return;
+                    };
+                    match (event.send_state(), event.local_echo_send_handle()) {
+                        // Unrecoverable errors are parked until the event is unwedged.
+                        (
+                            Some(EventSendState::SendingFailed {
+                                is_recoverable: false,
+                                ..
+                            }),
+                            Some(handle),
+                        ) => {
+                            if let Err(e) = handle.unwedge().await {
+                                warn!("Failed to retry sending {transaction_id} in {timeline_kind}: {e:?}");
+                                enqueue_toast_notification(ToastNotificationRequest::new(
+                                    format!("Failed to send message. Error: {e}"),
+                                    None,
+                                    ToastNotificationVariant::Error,
+                                ));
+                            }
+                        }
+                        // Recoverable errors only paused the room's send queue.
+                        _ => timeline.room().send_queue().set_enabled(true),
+                    }
+                    broadcast_event(UIUpdateMessage::RefreshUI);
+                });
+            }
+            MatrixRequest::CancelSend {
+                timeline_kind,
+                transaction_id,
+            } => {
+                let Some(timeline) = get_timeline(&timeline_kind) else {
+                    error!("BUG: {timeline_kind} not found when cancelling send {transaction_id}");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    // Redacting a local echo aborts its sending.
+                    let item_id = TimelineEventItemId::TransactionId(transaction_id);
+                    if let Err(e) = timeline.redact(&item_id, None).await {
+                        warn!("Failed to cancel sending {item_id:?} in {timeline_kind}: {e:?}");
+                        enqueue_toast_notification(ToastNotificationRequest::new(
+                            format!("Failed to cancel sending the message. Error: {e}"),
+                            None,
+                            ToastNotificationVariant::Error,
+                        ));
+                    }
+                    broadcast_event(UIUpdateMessage::RefreshUI);
+                });
+            }
+            MatrixRequest::StartPoll {
+                timeline_kind,
+                question,
+                answers,
+                kind,
+                max_selections,
+            } => {
+                let Some(timeline) = get_timeline(&timeline_kind) else {
+                    error!("BUG: {timeline_kind} not found when starting a poll");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let result =
+                        match poll_start_content(question, answers, kind.into(), max_selections) {
+                            Ok(content) => timeline
+                                .send(content.into())
+                                .await
+                                .map(|_| ())
+                                .map_err(|e| e.to_string()),
+                            Err(e) => Err(e.to_string()),
+                        };
+                    match result {
+                        Ok(()) => debug!("Started a poll in room {timeline_kind}."),
+                        Err(e) => {
+                            warn!("Failed to start a poll in room {timeline_kind}: {e}");
+                            enqueue_toast_notification(ToastNotificationRequest::new(
+                                format!("Failed to start the poll. Error: {e}"),
+                                None,
+                                ToastNotificationVariant::Error,
+                            ));
+                        }
+                    }
+                    broadcast_event(UIUpdateMessage::RefreshUI);
+                });
+            }
+            MatrixRequest::SendPollResponse {
+                timeline_kind,
+                poll_start_event_id,
+                answer_ids,
+            } => {
+                let Some(timeline) = get_timeline(&timeline_kind) else {
+                    error!(
+                        "BUG: {timeline_kind} not found when voting for poll {poll_start_event_id}"
+                    );
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let content =
+                        UnstablePollResponseEventContent::new(answer_ids, poll_start_event_id);
+                    match timeline.send(content.into()).await {
+                        Ok(_send_handle) => debug!("Sent poll response to room {timeline_kind}."),
+                        Err(e) => {
+                            warn!("Failed to send poll response to room {timeline_kind}: {e:?}");
+                            enqueue_toast_notification(ToastNotificationRequest::new(
+                                format!("Failed to vote. Error: {e}"),
+                                None,
+                                ToastNotificationVariant::Error,
+                            ));
+                        }
+                    }
+                    broadcast_event(UIUpdateMessage::RefreshUI);
+                });
+            }
+            MatrixRequest::EndPoll {
+                timeline_kind,
+                poll_start_event_id,
+            } => {
+                let Some(timeline) = get_timeline(&timeline_kind) else {
+                    error!("BUG: {timeline_kind} not found when ending poll {poll_start_event_id}");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let content = UnstablePollEndEventContent::new(
+                        "The poll has ended.",
+                        poll_start_event_id,
+                    );
+                    match timeline.send(content.into()).await {
+                        Ok(_send_handle) => debug!("Ended a poll in room {timeline_kind}."),
+                        Err(e) => {
+                            warn!("Failed to end poll in room {timeline_kind}: {e:?}");
+                            enqueue_toast_notification(ToastNotificationRequest::new(
+                                format!("Failed to end the poll. Error: {e}"),
+                                None,
+                                ToastNotificationVariant::Error,
+                            ));
+                        }
+                    }
+                    broadcast_event(UIUpdateMessage::RefreshUI);
+                });
+            }
+            MatrixRequest::ReportEvent {
+                timeline_kind,
+                event_id,
+                reason,
+            } => {
+                let Some(timeline) = get_timeline(&timeline_kind) else {
+                    error!("BUG: {timeline_kind} not found when reporting event {event_id}");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let result = timeline.room().report_content(event_id, reason).await;
+                    enqueue_report_result_toast(result.map(|_| ()), "message");
+                });
+            }
+            MatrixRequest::ReportRoom { room_id, reason } => {
+                let Some(room) = CLIENT.get().and_then(|c| c.get_room(&room_id)) else {
+                    warn!("BUG: client/room not found when reporting room {room_id}");
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let result = room.report_room(reason).await;
+                    enqueue_report_result_toast(result.map(|_| ()), "room");
+                });
+            }
+            MatrixRequest::ReportUser { user_id, reason } => {
+                let Some(client) = CLIENT.get() else {
+                    enqueue_report_result_toast(
+                        Err(matrix_sdk::Error::AuthenticationRequired),
+                        "user",
+                    );
+                    continue;
+                };
+
+                tasks.spawn(async move {
+                    let result = client
+                        .send(report_user::v3::Request::new(user_id, reason))
+                        .await
+                        .map_err(matrix_sdk::Error::from);
+                    enqueue_report_result_toast(result.map(|_| ()), "user");
+                });
+            }
             MatrixRequest::GetMatrixRoomLinkPillInfo { matrix_id, via } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _fetch_matrix_link_pill_info_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     let room_or_alias_id: Option<&RoomOrAliasId> = match &matrix_id {
                         MatrixId::Room(room_id) => Some((&**room_id).into()),
                         MatrixId::RoomAlias(room_alias_id) => Some((&**room_alias_id).into()),
@@ -965,20 +1484,20 @@ pub async fn async_worker(
             } => {
                 let Some(client) = CLIENT.get() else { continue };
 
-                let _fetch_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     match client.search_users(&search_term, limit).await {
                         Ok(res) => {
                             let users: Vec<ProfileModel> =
                                 res.results.iter().cloned().map(|i| i.into()).collect();
-                            content_sender.send(Ok(users))
+                            content_sender.respond(Ok(users))
                         }
-                        Err(e) => content_sender.send(Err(e.into())),
+                        Err(e) => content_sender.respond(Err(e.into())),
                     }
                 });
             }
             MatrixRequest::CreateDMRoom { user_id } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _create_dm_room_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     match client.create_dm(&user_id).await {
                         Ok(room) => {
                             let event_bridge =
@@ -1010,7 +1529,7 @@ pub async fn async_worker(
                 topic,
             } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _create_room_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     let mut request = create_room::v3::Request::new();
                     request.is_direct = false;
                     request.name = Some(room_name);
@@ -1072,7 +1591,7 @@ pub async fn async_worker(
                 invited_user_ids,
             } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _invite_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     let room = client
                         .get_room(&room_id)
                         .expect("Room should be defined if we can invite users in it");
@@ -1098,7 +1617,7 @@ pub async fn async_worker(
                 reason,
             } => {
                 let Some(client) = CLIENT.get() else { continue };
-                let _kick_task = Handle::current().spawn(async move {
+                tasks.spawn(async move {
                     let room = client
                         .get_room(&room_id)
                         .expect("Room should be defined if we can ban users from it");
@@ -1127,13 +1646,282 @@ pub async fn async_worker(
     bail!("async_worker task ended unexpectedly")
 }
 
+/// Builds the thread-focused or event-focused timeline of the given kind,
+/// and stores it in the room details so that a `RoomScreen` can take its endpoints.
+fn create_focused_timeline(
+    tasks: &mut JoinSet<()>,
+    timeline_kind: TimelineKind,
+    sender: RequestResponder<TimelineCreated>,
+) {
+    let focus = match &timeline_kind {
+        TimelineKind::Thread {
+            thread_root_event_id,
+            ..
+        } => TimelineFocus::Thread {
+            root_event_id: thread_root_event_id.clone(),
+        },
+        TimelineKind::FocusedEvent { event_id, .. } => TimelineFocus::Event {
+            target: event_id.clone(),
+            num_context_events: 20,
+            // Like the main timeline, threaded events are shown in their own thread timeline.
+            thread_mode: TimelineEventFocusThreadMode::Automatic {
+                hide_threaded_events: true,
+            },
+        },
+        TimelineKind::MainRoom { .. } => {
+            sender.respond_error(crate::Error::InvalidInput(
+                "The main timeline is created along with its room".to_owned(),
+            ));
+            return;
+        }
+    };
+    let room_id = timeline_kind.room_id().clone();
+
+    let room = {
+        let Some(arc) = crate::room::joined_room::try_get_room_details(&room_id) else {
+            error!("BUG: room info not found when creating {timeline_kind}");
+            sender.respond_error(crate::Error::NotFound(format!("Room {room_id} not found")));
+            return;
+        };
+        let mut room_info = arc.lock().unwrap();
+
+        if room_info.focused_timelines.contains_key(&timeline_kind) {
+            sender.respond_error(crate::Error::InvalidInput(format!(
+                "The timeline {timeline_kind} already exists"
+            )));
+            return;
+        }
+        let newly_pending = room_info
+            .pending_focused_timelines
+            .insert(timeline_kind.clone());
+        if !newly_pending {
+            sender.respond_error(crate::Error::InvalidInput(format!(
+                "The timeline {timeline_kind} is already being created"
+            )));
+            return;
+        }
+        room_info.main_timeline.timeline.room().clone()
+    };
+
+    tasks.spawn(async move {
+        debug!("Creating focused timeline {timeline_kind}...");
+        let build_result = room
+            .timeline_builder()
+            .with_focus(focus)
+            .track_read_marker_and_receipts(TimelineReadReceiptTracking::AllEvents)
+            .build()
+            .await;
+
+        match build_result {
+            Ok(focused_timeline) => {
+                let Some(arc) = crate::room::joined_room::try_get_room_details(&room_id) else {
+                    return;
+                };
+                let mut room_info = arc.lock().unwrap();
+                // An event-focused timeline is only kept while its RoomScreen shows it,
+                // which may have been closed while the timeline was being built.
+                if matches!(timeline_kind, TimelineKind::FocusedEvent { .. }) && sender.is_closed() {
+                    debug!("Dropping focused timeline {timeline_kind}, it is no longer shown.");
+                    room_info.pending_focused_timelines.remove(&timeline_kind);
+                    return;
+                }
+                debug!("Successfully created focused timeline {timeline_kind}.");
+                let focused_timeline = Arc::new(focused_timeline);
+                let (timeline_update_sender, timeline_update_receiver) =
+                    crossbeam_channel::unbounded();
+                let (request_sender, request_receiver) = watch::channel(Vec::new());
+                let timeline_subscriber_handler_task =
+                    Handle::current().spawn(timeline_subscriber_handler(
+                        room,
+                        focused_timeline.clone(),
+                        timeline_update_sender.clone(),
+                        request_receiver,
+                        timeline_kind.clone(),
+                    ));
+                room_info.pending_focused_timelines.remove(&timeline_kind);
+                room_info.focused_timelines.insert(
+                    timeline_kind,
+                    PerTimelineDetails {
+                        timeline: focused_timeline,
+                        timeline_update_sender,
+                        timeline_singleton_endpoints: Some((
+                            timeline_update_receiver,
+                            request_sender,
+                        )),
+                        timeline_subscriber_handler_task,
+                    },
+                );
+                sender.respond(TimelineCreated);
+                broadcast_event(UIUpdateMessage::RefreshUI);
+            }
+            Err(error) => {
+                error!("Failed to create focused timeline {timeline_kind}: {error}");
+                if let Some(arc) = crate::room::joined_room::try_get_room_details(&room_id) {
+                    let mut room_info = arc.lock().unwrap();
+                    room_info.pending_focused_timelines.remove(&timeline_kind);
+                }
+                let description = if matches!(timeline_kind, TimelineKind::Thread { .. }) {
+                    "thread"
+                } else {
+                    "message"
+                };
+                sender.respond_error(anyhow::anyhow!("{error}").into());
+                broadcast_event(UIUpdateMessage::RefreshUI);
+                enqueue_toast_notification(ToastNotificationRequest::new(
+                    format!("Failed to load this {description}. Please retry opening it again later.\n\nError: {error}"),
+                    None,
+                    ToastNotificationVariant::Error,
+                ));
+            }
+        }
+    });
+}
+
+/// Pins or unpins the given event, if the current user is allowed to.
+fn pin_or_unpin_event(
+    tasks: &mut JoinSet<()>,
+    room_id: OwnedRoomId,
+    event_id: OwnedEventId,
+    pin: bool,
+) {
+    let Some(room) = CLIENT.get().and_then(|c| c.get_room(&room_id)) else {
+        warn!("BUG: client/room not found when (un)pinning event {event_id}, room: {room_id}");
+        return;
+    };
+    let Some(user_id) = CURRENT_USER_ID.get() else {
+        return;
+    };
+
+    tasks.spawn(async move {
+        let power_levels = room.power_levels_or_default().await;
+        if !UserPowerLevels::from(&power_levels, &user_id)._can_pin() {
+            enqueue_toast_notification(ToastNotificationRequest::new(
+                "You are not allowed to pin or unpin events in this room.".to_owned(),
+                None,
+                ToastNotificationVariant::Error,
+            ));
+            return;
+        }
+        let result = if pin {
+            room.pin_event(&event_id).await
+        } else {
+            room.unpin_event(&event_id).await
+        };
+        match result {
+            Ok(_changed) => debug!(
+                "Successfully {} event {event_id} in room {room_id}.",
+                if pin { "pinned" } else { "unpinned" }
+            ),
+            Err(e) => {
+                warn!("Failed to (un)pin event {event_id} in room {room_id}: {e:?}");
+                enqueue_toast_notification(ToastNotificationRequest::new(
+                    format!(
+                        "Failed to {} the message. Error: {e}",
+                        if pin { "pin" } else { "unpin" }
+                    ),
+                    None,
+                    ToastNotificationVariant::Error,
+                ));
+            }
+        }
+    });
+}
+
+/// Sends the given content to a timeline, if the power levels of the current user
+/// in its room pass the `can_send` check.
+///
+/// Stickers sent to a thread timeline are related to its thread root.
+fn send_content_if_allowed(
+    tasks: &mut JoinSet<()>,
+    timeline_kind: TimelineKind,
+    mut content: AnyMessageLikeEventContent,
+    can_send: fn(UserPowerLevels) -> bool,
+    content_name: &'static str,
+) {
+    let Some(timeline) = get_timeline(&timeline_kind) else {
+        error!("BUG: {timeline_kind} not found when sending a {content_name}");
+        return;
+    };
+    let Some(user_id) = CURRENT_USER_ID.get() else {
+        return;
+    };
+
+    tasks.spawn(async move {
+        let power_levels = timeline.room().power_levels_or_default().await;
+        if !can_send(UserPowerLevels::from(&power_levels, &user_id)) {
+            enqueue_toast_notification(ToastNotificationRequest::new(
+                format!("You are not allowed to send a {content_name} in this room."),
+                None,
+                ToastNotificationVariant::Error,
+            ));
+            return;
+        }
+        // The thread relation is set here rather than left to the inference of `Timeline::send`,
+        // so that a sticker sent from a thread never lands in the main timeline.
+        if let (
+            AnyMessageLikeEventContent::Sticker(sticker),
+            TimelineKind::Thread {
+                thread_root_event_id,
+                ..
+            },
+        ) = (&mut content, &timeline_kind)
+            && sticker.relates_to.is_none()
+        {
+            // The latest event is used for the reply fallback of the clients without threads.
+            let latest_event_id = timeline
+                .latest_event_id()
+                .await
+                .unwrap_or_else(|| thread_root_event_id.clone());
+            sticker.relates_to = Some(Relation::Thread(Thread::plain(
+                thread_root_event_id.clone(),
+                latest_event_id,
+            )));
+        }
+        match timeline.send(content).await {
+            Ok(_send_handle) => debug!("Sent {content_name} to room {timeline_kind}."),
+            Err(e) => {
+                warn!("Failed to send {content_name} to room {timeline_kind}: {e:?}");
+                enqueue_toast_notification(ToastNotificationRequest::new(
+                    format!("Failed to send the {content_name}. Error: {e}"),
+                    None,
+                    ToastNotificationVariant::Error,
+                ));
+            }
+        }
+        broadcast_event(UIUpdateMessage::RefreshUI);
+    });
+}
+
+/// Tells the user whether the given report was sent to the homeserver administrators.
+fn enqueue_report_result_toast(result: matrix_sdk::Result<()>, reported: &str) {
+    let (message, variant) = match result {
+        Ok(()) => (
+            format!("The {reported} has been reported to your homeserver administrators."),
+            ToastNotificationVariant::Success,
+        ),
+        Err(e) if e.client_api_error_kind() == Some(&ErrorKind::Unrecognized) => (
+            format!("Your homeserver does not support reporting a {reported}."),
+            ToastNotificationVariant::Error,
+        ),
+        Err(e) => {
+            warn!("Failed to report a {reported}: {e:?}");
+            (
+                format!("Failed to report the {reported}. Error: {e}"),
+                ToastNotificationVariant::Error,
+            )
+        }
+    };
+    enqueue_toast_notification(ToastNotificationRequest::new(message, None, variant));
+}
+
 /// Worker that loops to update rooms_list updates in queue
 /// currently it handles active_room updates outside the other actions,
 /// but maybe I should handle this as every other action
 pub async fn ui_worker(
     state_updaters: Arc<Box<dyn StateUpdater>>,
-    mut room_update_receiver: mpsc::Receiver<MatrixUpdateCurrentActiveRoom>,
+    room_update_receiver: Arc<Mutex<mpsc::Receiver<MatrixUpdateCurrentActiveRoom>>>,
 ) -> anyhow::Result<()> {
+    let mut room_update_receiver = room_update_receiver.lock().await;
     let rooms_list = Arc::new(Mutex::new(RoomsList::new(state_updaters.clone())));
 
     // create UI subscriber
@@ -1145,8 +1933,10 @@ pub async fn ui_worker(
     loop {
         tokio::select! {
             // Handle incoming events from listener
-            Some(MatrixUpdateCurrentActiveRoom { thread_root_event_id, room_id, room_name }) = room_update_receiver.recv() => {
-                let timeline_kind = if let Some(root) = thread_root_event_id {
+            Some(MatrixUpdateCurrentActiveRoom { thread_root_event_id, focused_event_id, room_id, room_name }) = room_update_receiver.recv() => {
+                let timeline_kind = if let Some(event_id) = focused_event_id {
+                    TimelineKind::FocusedEvent { room_id, event_id }
+                } else if let Some(root) = thread_root_event_id {
                     TimelineKind::Thread { thread_root_event_id: root, room_id }
                 } else {
                     TimelineKind::MainRoom { room_id }
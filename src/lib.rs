@@ -1,11 +1,13 @@
 #![recursion_limit = "256"]
 use std::{path::PathBuf, sync::Arc};
 
+use anyhow::{anyhow, bail};
 use futures::StreamExt;
-use serde::{Serialize, ser::Serializer};
+use matrix_sdk::Client;
 use tokio::{
     runtime::Handle,
     sync::{broadcast, mpsc::unbounded_channel},
+    task::JoinHandle,
 };
 use tracing::{error, info};
 use url::Url;
@@ -15,15 +17,15 @@ use crate::{
         FrontendAuthTypeResponse, check_homeserver_auth_type,
         session::{setup_token_background_save, try_restore_session_to_state},
         singletons::{
-            APP_DATA_DIR, CURRENT_USER_ID, EVENT_BRIDGE, REQUEST_SENDER,
-            VERIFICATION_RESPONSE_RECEIVER,
+            APP_DATA_DIR, CURRENT_USER_ID, EVENT_BRIDGE, REQUEST_SENDER, SESSION_END_SENDER,
+            SessionEndRequest, VERIFICATION_RESPONSE_RECEIVER,
         },
-        workers::{async_main_loop, async_worker},
+        workers::{async_main_loop, async_worker, ui_worker},
     },
     models::{
         event_bridge::EventBridge,
         events::{
-            EmitEvent, MatrixLoginPayload, MatrixUpdateCurrentActiveRoom,
+            EmitEvent, MatrixLoginPayload, MatrixRegistrationEvent, MatrixUpdateCurrentActiveRoom,
             MatrixVerificationResponse, ToastNotificationRequest, ToastNotificationVariant,
         },
         state_updater::StateUpdater,
@@ -35,7 +37,10 @@ use crate::{
 };
 
 pub(crate) mod account;
+#[cfg(feature = "ts-rs")]
+mod bindings;
 pub mod commands;
+pub(crate) mod error;
 pub(crate) mod events;
 pub(crate) mod init;
 pub mod models;
@@ -46,26 +51,6 @@ pub(crate) mod utils;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-/// matrix-ui-serializable Error enum
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error(transparent)]
-    Io(#[from] std::io::Error),
-    #[error(transparent)]
-    Anyhow(#[from] anyhow::Error),
-    #[error(transparent)]
-    MatrixSdk(#[from] matrix_sdk::Error),
-}
-
-impl Serialize for Error {
-    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(self.to_string().as_ref())
-    }
-}
-
 /// Required `mpsc:Receiver`s to listen to incoming events
 pub struct EventReceivers {
     // Event based
@@ -73,6 +58,8 @@ pub struct EventReceivers {
     room_update_receiver: mpsc::Receiver<MatrixUpdateCurrentActiveRoom>,
     // Command based
     matrix_login_receiver: mpsc::Receiver<MatrixLoginPayload>,
+    matrix_registration_receiver: mpsc::Receiver<MatrixRegistrationEvent>,
+    /// Receives the OAuth and SSO callback URLs
     oauth_deeplink_receiver: mpsc::Receiver<Url>,
 }
 
@@ -81,12 +68,14 @@ impl EventReceivers {
         verification_response_receiver: mpsc::Receiver<MatrixVerificationResponse>,
         room_update_receiver: mpsc::Receiver<MatrixUpdateCurrentActiveRoom>,
         matrix_login_receiver: mpsc::Receiver<MatrixLoginPayload>,
+        matrix_registration_receiver: mpsc::Receiver<MatrixRegistrationEvent>,
         oauth_deeplink_receiver: mpsc::Receiver<Url>,
     ) -> Self {
         Self {
             verification_response_receiver,
             room_update_receiver,
             matrix_login_receiver,
+            matrix_registration_receiver,
             oauth_deeplink_receiver,
         }
     }
@@ -162,265 +151,374 @@ pub fn init(mut config: LibConfig) -> broadcast::Receiver<EmitEvent> {
         matrix_request_receiver
     });
 
+    let (session_end_sender, mut session_end_receiver) = unbounded_channel::<SessionEndRequest>();
+    SESSION_END_SENDER
+        .set(session_end_sender)
+        .expect("Couldn't set the session end sender");
+
     let _monitor = Handle::current().spawn(async move {
         let updaters_arc = Arc::new(config.updaters);
         let inner_updaters = updaters_arc.clone();
 
-        // Setup the token refresher thread before first sync or client build.
-        setup_token_background_save(inner_updaters.clone());
-        let matrix_request_receiver = basic_init_handle.await.expect("couldn't do basic init");
+        let matrix_request_receiver = Arc::new(tokio::sync::Mutex::new(
+            basic_init_handle.await.expect("couldn't do basic init"),
+        ));
+        let room_update_receiver = Arc::new(tokio::sync::Mutex::new(
+            config.event_receivers.room_update_receiver,
+        ));
 
-        let client_opt = match try_restore_session_to_state(config.session_option).await {
-            Ok(opt) => opt,
-            Err(e) => {
-                enqueue_toast_notification(ToastNotificationRequest::new(
-                    format!("Failed to restore session, falling back on login. Error: {e}"),
-                    None,
-                    ToastNotificationVariant::Error,
-                ));
-                None
-            }
-        };
-
-        let (client, _has_been_restored) = match client_opt {
-            Some(restored) => {
-                if let Err(e) = &inner_updaters.update_login_state(
-                    LoginState::Restored,
-                    restored.user_id().map(|v| v.to_string()),
-                ) {
+        let mut ui_event_receiver =
+            init::singletons::subscribe_to_events().expect("Couldn't get UI event receiver"); // subscribe to events so the sender(s) never fail
+
+        let mut session_option = config.session_option;
+
+        // Each iteration of this loop handles a whole session, from login to logout.
+        'session: loop {
+            // The workers keep per-session state (subscriptions, rooms list, room screens),
+            // so they are restarted for each session.
+            let (worker_stop_sender, worker_stop_receiver) = oneshot::channel();
+            let mut worker_join_handle = Handle::current().spawn(async_worker(
+                matrix_request_receiver.clone(),
+                worker_stop_receiver,
+            ));
+            let ui_worker_join_handle = Handle::current().spawn(ui_worker(
+                updaters_arc.clone(),
+                room_update_receiver.clone(),
+            ));
+
+            // Setup the token refresher thread before first sync or client build.
+            let token_save_handle = setup_token_background_save(inner_updaters.clone());
+
+            let client_opt = match try_restore_session_to_state(session_option.take()).await {
+                Ok(opt) => opt,
+                Err(e) => {
                     enqueue_toast_notification(ToastNotificationRequest::new(
-                        format!("Cannot update login state. Error: {e}"),
+                        format!("Failed to restore session, falling back on login. Error: {e}"),
                         None,
                         ToastNotificationVariant::Error,
-                    ))
+                    ));
+                    None
                 }
-                (restored, true)
-            }
-            None => {
-                LOGIN_STORE_READY.wait();
-                if let Err(e) =
-                    &inner_updaters.update_login_state(LoginState::AwaitingForHomeserver, None)
-                {
-                    enqueue_toast_notification(ToastNotificationRequest::new(
-                        format!("Cannot update login state. Error: {e}"),
-                        None,
-                        ToastNotificationVariant::Error,
-                    ))
+            };
+
+            let (client, _has_been_restored) = match client_opt {
+                Some(restored) => {
+                    if let Err(e) = &inner_updaters.update_login_state(
+                        LoginState::Restored,
+                        restored.user_id().map(|v| v.to_string()),
+                    ) {
+                        enqueue_toast_notification(ToastNotificationRequest::new(
+                            format!("Cannot update login state. Error: {e}"),
+                            None,
+                            ToastNotificationVariant::Error,
+                        ))
+                    }
+                    (restored, true)
                 }
-                info!("Waiting for homeserver selection...");
-
-                let client = if let Ok((auth_type, client)) = check_homeserver_auth_type().await {
-                    let serialized_session = match auth_type {
-                        FrontendAuthTypeResponse::Oauth => init::oauth::register_and_login_oauth(
-                            &client,
-                            config.event_receivers.oauth_deeplink_receiver,
-                            &config.oauth_client_uri,
-                            &config.oauth_redirect_uri,
-                        )
-                        .await
-                        .expect("Failed to login with OAuth"),
-                        FrontendAuthTypeResponse::Matrix => {
-                            // wait for frontend payload
-                            let login_payload = config
-                                .event_receivers
-                                .matrix_login_receiver
-                                .recv()
-                                .await
-                                .expect("no login sender to listen to");
-                            init::login::login_and_persist_matrix_session(
-                                &client,
-                                login_payload.username.clone(),
-                                login_payload.password.clone(),
-                                login_payload.client_name.clone(),
-                            )
-                            .await
-                            .expect("Failed to login with Matrix Auth")
-                        }
-                        FrontendAuthTypeResponse::WrongUrl => {
+                None => {
+                    LOGIN_STORE_READY.wait_async().await;
+                    let mut login_state = LoginState::AwaitingForHomeserver;
+                    // Loop until the login succeeds, so the user can retry after a failure.
+                    let client = loop {
+                        if let Err(e) = &inner_updaters.update_login_state(login_state, None) {
                             enqueue_toast_notification(ToastNotificationRequest::new(
-                                "The homeserver URL is incorrect".to_owned(),
-                                Some("Please restart the app and try another one.".to_owned()),
+                                format!("Cannot update login state. Error: {e}"),
+                                None,
                                 ToastNotificationVariant::Error,
-                            ));
-                            "".to_owned()
+                            ))
+                        }
+                        info!("Waiting for homeserver selection...");
+
+                        let login_result: anyhow::Result<(Client, String)> = async {
+                            let (auth_type, client) = check_homeserver_auth_type().await?;
+                            let serialized_session = match auth_type {
+                                FrontendAuthTypeResponse::Oauth => {
+                                    if let Some(qr_code_data) =
+                                        init::qr_login::take_scanned_qr_code()
+                                    {
+                                        init::qr_login::login_with_scanned_qr_code(
+                                            &client,
+                                            qr_code_data,
+                                            &config.oauth_client_uri,
+                                        )
+                                        .await?
+                                    } else {
+                                        init::oauth::register_and_login_oauth(
+                                            &client,
+                                            &mut config.event_receivers.oauth_deeplink_receiver,
+                                            &config.oauth_client_uri,
+                                            &config.oauth_redirect_uri,
+                                        )
+                                        .await?
+                                    }
+                                }
+                                FrontendAuthTypeResponse::Matrix { identity_providers } => {
+                                    // wait for frontend payload, either to login or to register,
+                                    // or for a SSO callback if the homeserver supports it.
+                                    tokio::select! {
+                                        login_payload = config.event_receivers.matrix_login_receiver.recv() => {
+                                            let login_payload =
+                                                login_payload.ok_or(anyhow!("no login sender to listen to"))?;
+                                            init::login::login_and_persist_matrix_session(
+                                                &client,
+                                                login_payload.username,
+                                                login_payload.password,
+                                                login_payload.client_name,
+                                            )
+                                            .await?
+                                        }
+                                        registration_payload = init::registration::wait_for_registration_request(
+                                            &mut config.event_receivers.matrix_registration_receiver,
+                                        ) => {
+                                            let registration_payload = registration_payload
+                                                .ok_or(anyhow!("no registration sender to listen to"))?;
+                                            init::registration::register_and_persist_matrix_session(
+                                                &client,
+                                                registration_payload,
+                                                &mut config.event_receivers.matrix_registration_receiver,
+                                            )
+                                            .await?
+                                        }
+                                        sso_callback = config.event_receivers.oauth_deeplink_receiver.recv(),
+                                            if identity_providers.is_some() =>
+                                        {
+                                            let sso_callback =
+                                                sso_callback.ok_or(anyhow!("no url was sent"))?;
+                                            init::sso::finish_sso_login(&client, sso_callback).await?
+                                        }
+                                    }
+                                }
+                                FrontendAuthTypeResponse::Sso { .. } => {
+                                    init::sso::login_with_sso(
+                                        &client,
+                                        &mut config.event_receivers.oauth_deeplink_receiver,
+                                        &config.oauth_redirect_uri,
+                                    )
+                                    .await?
+                                }
+                                FrontendAuthTypeResponse::WrongUrl => {
+                                    bail!("The homeserver URL is incorrect")
+                                }
+                            };
+                            Ok((client, serialized_session))
+                        }
+                        .await;
+
+                        match login_result {
+                            Ok((client, serialized_session)) => {
+                                if let Err(e) = &inner_updaters
+                                    .persist_login_session(serialized_session)
+                                    .await
+                                {
+                                    enqueue_toast_notification(ToastNotificationRequest::new(
+                                        format!("Failed to persist login session. Error: {e}"),
+                                        None,
+                                        ToastNotificationVariant::Error,
+                                    ));
+                                }
+                                break client;
+                            }
+                            Err(e) => {
+                                error!("Login failed: {e:?}");
+                                init::login::discard_temp_client().await;
+                                login_state = LoginState::Failed {
+                                    reason: e.to_string(),
+                                };
+                            }
                         }
                     };
 
-                    if let Err(e) = &inner_updaters
-                        .persist_login_session(serialized_session)
-                        .await
+                    (client, false)
+                }
+            };
+
+            CURRENT_USER_ID
+                .set(client.user_id().unwrap().to_owned())
+                .expect("Couldn't set CURRENT_USER_ID singleton");
+
+            let user_avatar = client.account().get_avatar_url().await.map_or(None, |a| a);
+
+            let user_display_name = client
+                .account()
+                .get_display_name()
+                .await
+                .map_or(None, |n| n);
+
+            let device_name = client
+                .encryption()
+                .get_own_device()
+                .await
+                .ok()
+                .flatten()
+                .and_then(|d| d.display_name().map(|s| s.to_owned()));
+
+            if let Err(e) = inner_updaters.update_current_user_info(
+                CURRENT_USER_ID.get(),
+                user_avatar,
+                user_display_name,
+                device_name,
+            ) {
+                enqueue_toast_notification(ToastNotificationRequest::new(
+                    format!("Cannot update current user info. Error: {e}"),
+                    None,
+                    ToastNotificationVariant::Error,
+                ))
+            }
+
+            // Update frontend login state
+            if let Err(e) = &inner_updaters.update_login_state(
+                LoginState::LoggedIn,
+                CURRENT_USER_ID.get().map(|u| u.to_string()),
+            ) {
+                error!("Cannot update frontend login store. {e}");
+                enqueue_toast_notification(ToastNotificationRequest::new(
+                    format!("Cannot update login state. Error: {e}"),
+                    None,
+                    ToastNotificationVariant::Error,
+                ))
+            }
+
+            let mut verification_subscriber = client.encryption().verification_state();
+
+            let verification_state_updaters = inner_updaters.clone();
+            let verification_handle = tokio::task::spawn(async move {
+                while let Some(state) = verification_subscriber.next().await {
+                    if let Err(e) = verification_state_updaters
+                        .update_verification_state(FrontendVerificationState::new(state))
                     {
                         enqueue_toast_notification(ToastNotificationRequest::new(
-                            format!("Failed to persist login session. Error: {e}"),
+                            format!("Cannot update verification store. Error: {e}"),
                             None,
                             ToastNotificationVariant::Error,
-                        ));
+                        ))
                     }
-
-                    client
-                } else {
-                    panic!("Unknown homeserver auth type")
-                };
-
-                (client, false)
-            }
-        };
-
-        CURRENT_USER_ID
-            .set(client.user_id().unwrap().to_owned())
-            .expect("Couldn't set CURRENT_USER_ID singleton");
-
-        let user_avatar = client.account().get_avatar_url().await.map_or(None, |a| a);
-
-        let user_display_name = client
-            .account()
-            .get_display_name()
-            .await
-            .map_or(None, |n| n);
-
-        let device_name = client
-            .encryption()
-            .get_own_device()
-            .await
-            .ok()
-            .flatten()
-            .and_then(|d| d.display_name().map(|s| s.to_owned()));
-
-        if let Err(e) = inner_updaters.update_current_user_info(
-            Some(CURRENT_USER_ID.get().unwrap().to_owned()),
-            user_avatar,
-            user_display_name,
-            device_name,
-        ) {
-            enqueue_toast_notification(ToastNotificationRequest::new(
-                format!("Cannot update current user info. Error: {e}"),
-                None,
-                ToastNotificationVariant::Error,
-            ))
-        }
-
-        // Update frontend login state
-        if let Err(e) = &inner_updaters.update_login_state(
-            LoginState::LoggedIn,
-            CURRENT_USER_ID.get().map(|u| u.to_string()),
-        ) {
-            error!("Cannot update frontend login store. {e}");
-            enqueue_toast_notification(ToastNotificationRequest::new(
-                format!("Cannot update login state. Error: {e}"),
-                None,
-                ToastNotificationVariant::Error,
-            ))
-        }
-
-        let mut verification_subscriber = client.encryption().verification_state();
-
-        let verification_state_updaters = inner_updaters.clone();
-        tokio::task::spawn(async move {
-            while let Some(state) = verification_subscriber.next().await {
-                if let Err(e) = verification_state_updaters
-                    .update_verification_state(FrontendVerificationState::new(state))
-                {
-                    enqueue_toast_notification(ToastNotificationRequest::new(
-                        format!("Cannot update verification store. Error: {e}"),
-                        None,
-                        ToastNotificationVariant::Error,
-                    ))
                 }
-            }
-        });
-
-        let mut state_stream = client.encryption().recovery().state_stream();
-        let recovery_state_updaters = inner_updaters.clone();
-        tokio::task::spawn(async move {
-            while let Some(update) = state_stream.next().await {
-                recovery_state_updaters
-                    .update_recovery_state(update)
-                    .expect("couldn't update frontend recovery state")
-            }
-        });
-
-        let mut ui_event_receiver =
-            init::singletons::subscribe_to_events().expect("Couldn't get UI event receiver"); // subscribe to events so the sender(s) never fail
-
-        // Spawn the actual async worker thread.
-        let mut worker_join_handle = Handle::current().spawn(async_worker(matrix_request_receiver));
-
-        // // Start the main loop that drives the Matrix client SDK.
-        let mut main_loop_join_handle = Handle::current().spawn(async_main_loop(
-            client,
-            updaters_arc,
-            config.event_receivers.room_update_receiver,
-        ));
-
-        LOGIN_STORE_READY.wait();
-
-        #[allow(clippy::never_loop)] // unsure if needed, just following tokio's examples.
-        loop {
-            tokio::select! {
-                result = &mut main_loop_join_handle => {
-                    match result {
-                        Ok(Ok(())) => {
-                            error!("BUG: main async loop task ended unexpectedly!");
+            });
+
+            let mut state_stream = client.encryption().recovery().state_stream();
+            let recovery_state_updaters = inner_updaters.clone();
+            let recovery_handle = tokio::task::spawn(async move {
+                while let Some(update) = state_stream.next().await {
+                    recovery_state_updaters
+                        .update_recovery_state(update)
+                        .expect("couldn't update frontend recovery state")
+                }
+            });
+
+            // // Start the main loop that drives the Matrix client SDK.
+            let mut main_loop_join_handle =
+                Handle::current().spawn(async_main_loop(client, updaters_arc.clone()));
+
+            LOGIN_STORE_READY.wait_async().await;
+
+            #[allow(clippy::never_loop)] // unsure if needed, just following tokio's examples.
+            loop {
+                tokio::select! {
+                    result = &mut main_loop_join_handle => {
+                        match result {
+                            Ok(Ok(())) => {
+                                error!("BUG: main async loop task ended unexpectedly!");
+                            }
+                            Ok(Err(e)) => {
+                                error!("Error: main async loop task ended:\n\t{e:?}");
+                                enqueue_rooms_list_update(RoomsListUpdate::Status {
+                                    status: RoomsCollectionStatus::Error(e.to_string()),
+                                });
+                                enqueue_toast_notification(ToastNotificationRequest::new(
+                                    format!("Rooms list update error: {e}"),
+                                    None,
+                                    ToastNotificationVariant::Error,
+                                ));
+                            },
+                            Err(e) => {
+                                error!("BUG: failed to join main async loop task: {e:?}");
+                            }
                         }
-                        Ok(Err(e)) => {
-                            error!("Error: main async loop task ended:\n\t{e:?}");
-                            enqueue_rooms_list_update(RoomsListUpdate::Status {
-                                status: RoomsCollectionStatus::Error(e.to_string()),
-                            });
-                            enqueue_toast_notification(ToastNotificationRequest::new(
-                                format!("Rooms list update error: {e}"),
-                                None,
-                                ToastNotificationVariant::Error,
-                            ));
-                        },
-                        Err(e) => {
-                            error!("BUG: failed to join main async loop task: {e:?}");
+                        break 'session;
+                    }
+                    result = &mut worker_join_handle => {
+                        match result {
+                            Ok(Ok(())) => {
+                                error!("BUG: async worker task ended unexpectedly!");
+                            }
+                            Ok(Err(e)) => {
+                                error!("Error: async worker task ended:\n\t{e:?}");
+                                enqueue_rooms_list_update(RoomsListUpdate::Status {
+                                    status: RoomsCollectionStatus::Error(e.to_string()),
+                                });
+                                enqueue_toast_notification(ToastNotificationRequest::new(
+                                    format!("Rooms list update error: {e}"),
+                                    None,
+                                    ToastNotificationVariant::Error,
+                                ));
+                            },
+                            Err(e) => {
+                                error!("BUG: failed to join async worker task: {e:?}");
+                            }
                         }
+                        break 'session;
                     }
-                    break;
-                }
-                result = &mut worker_join_handle => {
-                    match result {
-                        Ok(Ok(())) => {
-                            error!("BUG: async worker task ended unexpectedly!");
+                    Some(done_sender) = session_end_receiver.recv() => {
+                        // The user logged out: stop everything that still holds
+                        // the old client, and wait until it is dropped.
+                        stop_task(main_loop_join_handle).await;
+                        // The worker waits until the tasks it spawned are stopped.
+                        let _ = worker_stop_sender.send(());
+                        let _ = worker_join_handle.await;
+                        stop_task(ui_worker_join_handle).await;
+                        stop_task(token_save_handle).await;
+                        stop_task(verification_handle).await;
+                        stop_task(recovery_handle).await;
+
+                        let client_session = init::logout::teardown_session().await;
+                        // The requests submitted during the old session must not reach the next one.
+                        {
+                            let mut request_receiver = matrix_request_receiver.lock().await;
+                            while request_receiver.try_recv().is_ok() {}
                         }
-                        Ok(Err(e)) => {
-                            error!("Error: async worker task ended:\n\t{e:?}");
-                            enqueue_rooms_list_update(RoomsListUpdate::Status {
-                                status: RoomsCollectionStatus::Error(e.to_string()),
-                            });
+
+                        if let Err(e) = inner_updaters.clear_login_session().await {
                             enqueue_toast_notification(ToastNotificationRequest::new(
-                                format!("Rooms list update error: {e}"),
+                                format!("Failed to clear the persisted session. Error: {e}"),
                                 None,
                                 ToastNotificationVariant::Error,
                             ));
-                        },
-                        Err(e) => {
-                            error!("BUG: failed to join async worker task: {e:?}");
                         }
+                        info!("Logged out, going back to the login step.");
+                        // The session that requested the end can now delete the old database.
+                        let _ = done_sender.send(client_session);
+                        continue 'session;
+                    }
+                    _ = ui_event_receiver.recv() => {
+                        #[cfg(debug_assertions)]
+                        tracing::trace!("Received UI update event");
                     }
-                    break;
-                }
-                _ = ui_event_receiver.recv() => {
-                    #[cfg(debug_assertions)]
-                    tracing::trace!("Received UI update event");
                 }
             }
         }
     });
-
     // Return broadcast receiver for the adapter to forward outgoing events
     broadcast_receiver
 }
 
+/// Aborts the given task and waits until it has stopped, so everything it held is dropped.
+async fn stop_task<T>(handle: JoinHandle<T>) {
+    handle.abort();
+    let _ = handle.await;
+}
+
 // Re-exports
 
+#[cfg(feature = "ts-rs")]
+pub use bindings::export_typescript_bindings;
+pub use error::{Error, ErrorCode};
 pub use init::session::FullMatrixSession;
 pub use init::singletons::{CLIENT, LOGIN_STORE_READY};
 pub use models::async_requests::*;
 pub use room::frontend_events::events_dto::FrontendTimelineItem;
-pub use room::room_screen::RoomScreen;
-pub use room::rooms_list::RoomsList;
+pub use room::room_screen::{RoomScreen, RoomScreenDiff};
+pub use room::rooms_list::{RoomsList, RoomsListDiff};
+pub use room::search::FrontendSearchResults;
 pub use stores::login_store::{FrontendSyncServiceState, FrontendVerificationState, LoginState};
 pub use user::user_profile::UserProfile;
 // The adapter needs some types in those modules
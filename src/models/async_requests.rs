@@ -1,24 +1,36 @@
 use matrix_sdk::{
     OwnedServerName, RoomMemberships,
-    media::MediaRequestParameters,
-    room::{RoomMember, edit::EditedContent},
+    media::{MediaFormat, MediaRequestParameters, MediaThumbnailSettings},
+    room::edit::EditedContent,
     ruma::{
-        OwnedEventId, OwnedMxcUri, OwnedRoomAliasId, OwnedRoomId, OwnedUserId,
+        MatrixToUri, MatrixUri, OwnedEventId, OwnedMxcUri, OwnedRoomAliasId, OwnedRoomId,
+        OwnedTransactionId, OwnedUserId,
         api::client::receipt::create_receipt::v3::ReceiptType,
-        events::room::message::RoomMessageEventContentWithoutRelation, matrix_uri::MatrixId,
+        events::room::{
+            ImageInfo, MediaSource, member::MembershipState,
+            message::RoomMessageEventContentWithoutRelation,
+        },
+        matrix_uri::MatrixId,
     },
 };
 use matrix_sdk_ui::timeline::TimelineEventItemId;
 use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 use tokio::sync::oneshot;
+use tracing::{debug, error};
 
 use crate::{
     UserProfile,
     events::timeline::{PaginationDirection, TimelineKind},
-    init::singletons::REQUEST_SENDER,
-    models::profile::ProfileModel,
-    room::frontend_events::timeline_item_id::FrontendTimelineEventItemId,
+    init::singletons::{REQUEST_SENDER, get_event_bridge},
+    models::{
+        events::{EmitEvent, MatrixRequestResponse, MatrixRequestResponseData},
+        profile::ProfileModel,
+    },
+    room::{
+        frontend_events::{poll::FrontendPollKind, timeline_item_id::FrontendTimelineEventItemId},
+        threads_list::FrontendThreadsListFilter,
+    },
 };
 
 // Re-exports
@@ -33,8 +45,122 @@ pub(crate) fn submit_async_request(req: MatrixRequest) {
         .expect("BUG: async worker task receiver has died!");
 }
 
+/// How the body of a text message is formatted.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind",
+    content = "data"
+)]
+pub enum TextMessageFormat {
+    /// The message is sent as is.
+    #[default]
+    Plain,
+    /// The message is parsed as markdown, and sent with its HTML rendering if it has any formatting.
+    Markdown,
+    /// The message is the plain text fallback of this pre-rendered HTML.
+    Html { formatted_body: String },
+}
+
+/// Where the result of a [`MatrixRequest`] is sent.
+pub enum RequestResponder<T> {
+    /// To a Rust caller awaiting the result.
+    Channel(oneshot::Sender<T>),
+    /// To the frontend, as an [`EmitEvent::RequestResponse`] with this request ID.
+    Frontend(String),
+}
+
+impl<T> From<oneshot::Sender<T>> for RequestResponder<T> {
+    fn from(sender: oneshot::Sender<T>) -> Self {
+        Self::Channel(sender)
+    }
+}
+
+impl<T> RequestResponder<T> {
+    pub(crate) fn respond(self, value: T)
+    where
+        T: IntoRequestResponse,
+    {
+        match self {
+            Self::Channel(sender) => {
+                if sender.send(value).is_err() {
+                    debug!("The receiver of a request response has been dropped.");
+                }
+            }
+            Self::Frontend(request_id) => {
+                emit_request_response(MatrixRequestResponse::new(
+                    request_id,
+                    value.into_response(),
+                ));
+            }
+        }
+    }
+
+    /// Whether the Rust caller stopped waiting for the result.
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(self, Self::Channel(sender) if sender.is_closed())
+    }
+
+    /// Sends the error to the frontend. Rust callers only see their channel closed.
+    pub(crate) fn respond_error(self, error: crate::Error) {
+        if let Self::Frontend(request_id) = self {
+            emit_request_response(MatrixRequestResponse::new(request_id, Err(error)));
+        }
+    }
+}
+
+fn emit_request_response(response: MatrixRequestResponse) {
+    match get_event_bridge() {
+        Ok(bridge) => bridge.emit(EmitEvent::RequestResponse(response)),
+        Err(e) => error!(
+            "Cannot emit response of request {}: {e}",
+            response.request_id()
+        ),
+    }
+}
+
+/// The results of requests that can be sent back to the frontend.
+pub(crate) trait IntoRequestResponse {
+    fn into_response(self) -> crate::Result<MatrixRequestResponseData>;
+}
+
+/// The result of the requests creating a thread or an event-focused timeline.
+#[derive(Debug)]
+pub struct TimelineCreated;
+
+impl IntoRequestResponse for TimelineCreated {
+    fn into_response(self) -> crate::Result<MatrixRequestResponseData> {
+        Ok(MatrixRequestResponseData::TimelineCreated)
+    }
+}
+
+impl IntoRequestResponse for Option<UserProfile> {
+    fn into_response(self) -> crate::Result<MatrixRequestResponseData> {
+        Ok(MatrixRequestResponseData::UserProfile(self))
+    }
+}
+
+impl IntoRequestResponse for Result<Vec<u8>, matrix_sdk::Error> {
+    fn into_response(self) -> crate::Result<MatrixRequestResponseData> {
+        Ok(MatrixRequestResponseData::Media(self?))
+    }
+}
+
+impl IntoRequestResponse for Result<Vec<ProfileModel>, matrix_sdk::Error> {
+    fn into_response(self) -> crate::Result<MatrixRequestResponseData> {
+        Ok(MatrixRequestResponseData::Users(self?))
+    }
+}
+
 /// The set of requests for async work that can be made to the worker thread.
+///
+/// Requests can be deserialized from the `{ "event": ..., "payload": ... }` JSON sent by the
+/// frontend. The payloads of the requests returning a result carry a `requestId`, the result is
+/// then emitted as an [`EmitEvent::RequestResponse`] with the same ID.
 #[allow(clippy::large_enum_variant)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS), ts(as = "MatrixRequestBinding"))]
 pub enum MatrixRequest {
     /// Request to paginate the older (or newer) events of a room or thread timeline.
     PaginateTimeline {
@@ -44,6 +170,8 @@ pub enum MatrixRequest {
         direction: PaginationDirection,
     },
     /// Request to edit the content of an event in the given room's timeline.
+    ///
+    /// Local echoes that haven't been sent yet are edited in place in the send queue.
     EditMessage {
         timeline_kind: TimelineKind,
         timeline_event_item_id: TimelineEventItemId,
@@ -54,11 +182,26 @@ pub enum MatrixRequest {
         timeline_kind: TimelineKind,
         event_id: OwnedEventId,
     },
+    /// Request to jump the `RoomScreen` showing the given timeline to the given event,
+    /// such as a search result. The timeline is paginated backwards until the event is loaded,
+    /// then the event ID is set as the `targetEventId` of the `RoomScreen`.
+    /// Older events should rather be opened in an event-focused timeline.
+    JumpToEvent {
+        timeline_kind: TimelineKind,
+        event_id: OwnedEventId,
+    },
     /// Request to create a thread timeline focused on the given thread root event in the given room.
     CreateThreadTimeline {
         room_id: OwnedRoomId,
         thread_root_event_id: OwnedEventId,
-        sender: oneshot::Sender<()>,
+        sender: RequestResponder<TimelineCreated>,
+    },
+    /// Request to create a timeline focused on the given event in the given room,
+    /// loading the events around it, e.g., to open a permalink or the quote of a reply.
+    CreateFocusedEventTimeline {
+        room_id: OwnedRoomId,
+        event_id: OwnedEventId,
+        sender: RequestResponder<TimelineCreated>,
     },
     /// Request to fetch profile information for all members of a room.
     /// This can be *very* slow depending on the number of members in the room.
@@ -89,7 +232,7 @@ pub enum MatrixRequest {
         local_only: bool,
         /// matrix-svelte-client: sender used if a command is awaiting for the
         /// profile. We send it directly through this channel
-        sender: Option<oneshot::Sender<Option<UserProfile>>>,
+        sender: Option<RequestResponder<Option<UserProfile>>>,
     },
     /// Request to fetch the number of unread messages in the given room.
     GetNumberUnreadMessages { timeline_kind: TimelineKind },
@@ -97,28 +240,71 @@ pub enum MatrixRequest {
     IgnoreUser {
         /// Whether to ignore (`true`) or unignore (`false`) the user.
         ignore: bool,
-        /// The room membership info of the user to (un)ignore.
-        room_member: RoomMember,
-        /// The room ID of the room where the user is a member,
-        /// which is only needed because it isn't present in the `RoomMember` object.
+        /// The user to (un)ignore.
+        user_id: OwnedUserId,
+        /// The room ID of the room where the user is a member.
         room_id: OwnedRoomId,
     },
     /// Request to resolve a room alias into a room ID and the servers that know about that room.
     ResolveRoomAlias(OwnedRoomAliasId),
     /// Request to fetch media from the server.
-    /// Upon completion of the async media request, the `on_fetched` function
-    /// will be invoked with four arguments: the `destination`, the `media_request`,
-    /// the result of the media fetch, and the `update_sender`.
+    /// The media content, or the error, is sent to the `content_sender`.
     FetchMedia {
         media_request: MediaRequestParameters,
-        content_sender: oneshot::Sender<Result<Vec<u8>, matrix_sdk::Error>>,
+        content_sender: RequestResponder<Result<Vec<u8>, matrix_sdk::Error>>,
     },
     /// Request to send a message to the given room.
     SendTextMessage {
         timeline_kind: TimelineKind,
+        /// The plain text body of the message, or its markdown source.
+        /// The IDs of the mentioned users are rendered as pills.
         message: String,
+        format: TextMessageFormat,
+        /// The users intentionally mentioned by this message.
+        mentioned_user_ids: Vec<OwnedUserId>,
+        /// Whether this message mentions the whole room (`@room`).
+        mention_room: bool,
         replied_to_id: Option<OwnedEventId>,
     },
+    /// Request to send an emote (`/me`) message to the given room.
+    SendEmote {
+        timeline_kind: TimelineKind,
+        /// The text of the emote, without the `/me` prefix.
+        message: String,
+        format: TextMessageFormat,
+    },
+    /// Request to share a static location in the given room.
+    SendLocation {
+        timeline_kind: TimelineKind,
+        /// The location as a `geo:` URI, as defined by RFC 5870.
+        geo_uri: String,
+        /// A description of the location, used as the body of the message.
+        description: Option<String>,
+    },
+    /// Request to send a sticker to the given room, usually an image of an image pack.
+    SendSticker {
+        timeline_kind: TimelineKind,
+        /// The text representation of the sticker.
+        body: String,
+        url: OwnedMxcUri,
+        info: ImageInfo,
+    },
+    /// Request to fetch the preview of a link found in a message of the given timeline.
+    ///
+    /// The timeline is refreshed via [`TimelineUpdate::LinkPreviewsChanged`] once the preview is fetched.
+    FetchLinkPreview {
+        timeline_kind: TimelineKind,
+        url: String,
+    },
+    /// Request to show or hide the URL previews in the given room.
+    ///
+    /// By default, previews are only shown in unencrypted rooms, since fetching them
+    /// discloses the links to the homeserver. The choice is saved in the room account data,
+    /// separately for encrypted rooms.
+    SetUrlPreviewsEnabled { room_id: OwnedRoomId, enabled: bool },
+    /// Request to retry decrypting the events of the given timeline that could not be decrypted,
+    /// e.g. after the keys have been restored from the key backup.
+    RetryDecryption { timeline_kind: TimelineKind },
     /// Sends a notice to the given room that the current user is or is not typing.
     ///
     /// This request does not return a response or notify the UI thread, and
@@ -133,6 +319,43 @@ pub enum MatrixRequest {
         /// Whether to subscribe or unsubscribe from typing notices for this room.
         subscribe: bool,
     },
+    /// Subscribe to the pinned events of the given room.
+    ///
+    /// The pinned events and their timeline items are delivered back to the main UI thread
+    /// via [`TimelineUpdate::PinnedEvents`], whenever they change.
+    SubscribeToPinnedEvents {
+        room_id: OwnedRoomId,
+        /// Whether to subscribe or unsubscribe from pinned events for this room.
+        subscribe: bool,
+    },
+    /// Fetch a page of the threads of the given room, most recently active first.
+    ///
+    /// The threads are delivered back to the main UI thread via [`TimelineUpdate::ThreadsList`].
+    /// Without a `from` token, the first page replaces the threads listed so far.
+    PaginateThreadsList {
+        room_id: OwnedRoomId,
+        filter: FrontendThreadsListFilter,
+        /// The `nextBatch` token of the previous page, if any.
+        from: Option<String>,
+    },
+    /// Fetch the root of a thread that received a reply but isn't in the threads list yet.
+    ///
+    /// The root is delivered back to the main UI thread via [`TimelineUpdate::NewThread`],
+    /// to be listed first without reloading the list.
+    FetchThreadRoot {
+        room_id: OwnedRoomId,
+        filter: FrontendThreadsListFilter,
+        thread_root_event_id: OwnedEventId,
+    },
+    /// Subscribe to the thread replies received in the given room,
+    /// to keep the threads list up to date.
+    ///
+    /// Each reply is delivered back to the main UI thread via [`TimelineUpdate::ThreadReply`].
+    SubscribeToThreadReplies {
+        room_id: OwnedRoomId,
+        /// Whether to subscribe or unsubscribe from thread replies for this room.
+        subscribe: bool,
+    },
     /// Subscribe to changes in the read receipts of our own user.
     ///
     /// This request does not return a response or notify the UI thread.
@@ -166,6 +389,67 @@ pub enum MatrixRequest {
         timeline_event_id: TimelineEventItemId,
         reason: Option<String>,
     },
+    /// Pins the given event in the given room, if the user is allowed to.
+    PinEvent {
+        room_id: OwnedRoomId,
+        event_id: OwnedEventId,
+    },
+    /// Unpins the given event in the given room, if the user is allowed to.
+    #[doc(alias("unpin"))]
+    UnpinEvent {
+        room_id: OwnedRoomId,
+        event_id: OwnedEventId,
+    },
+    /// Sends again a local echo whose sending failed.
+    RetrySend {
+        timeline_kind: TimelineKind,
+        transaction_id: OwnedTransactionId,
+    },
+    /// Removes a local echo from the send queue, before it is sent.
+    CancelSend {
+        timeline_kind: TimelineKind,
+        transaction_id: OwnedTransactionId,
+    },
+    /// Starts a poll in the given room, with 2 to 20 answers.
+    StartPoll {
+        timeline_kind: TimelineKind,
+        question: String,
+        answers: Vec<String>,
+        kind: FrontendPollKind,
+        max_selections: u8,
+    },
+    /// Votes for the given answers of a poll, replacing any previous vote.
+    /// An empty list of answers removes the vote.
+    SendPollResponse {
+        timeline_kind: TimelineKind,
+        poll_start_event_id: OwnedEventId,
+        answer_ids: Vec<String>,
+    },
+    /// Ends a poll, its results are then final.
+    EndPoll {
+        timeline_kind: TimelineKind,
+        poll_start_event_id: OwnedEventId,
+    },
+    /// Reports the given event as inappropriate to the homeserver administrators.
+    ///
+    /// There is no offensiveness score: `Room::report_content` no longer takes one
+    /// since matrix-sdk 0.17.
+    ReportEvent {
+        timeline_kind: TimelineKind,
+        event_id: OwnedEventId,
+        reason: Option<String>,
+    },
+    /// Reports the given room as inappropriate to the homeserver administrators (MSC4151).
+    /// The current user doesn't need to be joined to the room.
+    ReportRoom {
+        room_id: OwnedRoomId,
+        reason: String,
+    },
+    /// Reports the given user as inappropriate to the homeserver administrators (MSC4260).
+    ReportUser {
+        user_id: OwnedUserId,
+        reason: String,
+    },
     /// Sends a request to obtain the room's pill link info for the given Matrix ID.
     ///
     /// The MatrixLinkPillInfo::Loaded variant is sent back to the main UI thread via.
@@ -176,7 +460,7 @@ pub enum MatrixRequest {
     SearchUsers {
         search_term: String,
         limit: u64,
-        content_sender: oneshot::Sender<Result<Vec<ProfileModel>, matrix_sdk::Error>>,
+        content_sender: RequestResponder<Result<Vec<ProfileModel>, matrix_sdk::Error>>,
     },
     /// Create a DM room with a given UserId
     CreateDMRoom { user_id: OwnedUserId },
@@ -226,7 +510,7 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                 let data: PaginateTimelinePayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::PaginateTimeline {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                     num_events: data.num_events,
                     direction: data.direction,
                 })
@@ -235,8 +519,8 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                 let data: EditMessagePayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::EditMessage {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
-                    // We only use remote event_id for now. Transaction (local) ids could be supported in the future
+                    timeline_kind: data.target.into(),
+                    // Local echoes are identified by their transaction ID.
                     timeline_event_item_id: data.timeline_event_item_id.inner(),
                     // We only allow editing messages for now.
                     edited_content: EditedContent::RoomMessage(data.edited_content),
@@ -246,17 +530,52 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                 let data: FetchDetailsForEventPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::FetchDetailsForEvent {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                     event_id: data.event_id,
                 })
             }
-            // "syncRoomMemberList" => {
-            //     let data: SyncRoomMemberListPayload =
-            //         serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
-            //     Ok(MatrixRequest::SyncRoomMemberList {
-            //         room_id: data.room_id,
-            //     })
-            // }
+            "jumpToEvent" => {
+                let data: JumpToEventPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::JumpToEvent {
+                    timeline_kind: data.target.into(),
+                    event_id: data.event_id,
+                })
+            }
+            "createThreadTimeline" => {
+                let data: CreateThreadTimelinePayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::CreateThreadTimeline {
+                    room_id: data.room_id,
+                    thread_root_event_id: data.thread_root_event_id,
+                    sender: RequestResponder::Frontend(data.request_id),
+                })
+            }
+            "createFocusedEventTimeline" => {
+                let data: CreateFocusedEventTimelinePayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::CreateFocusedEventTimeline {
+                    room_id: data.room_id,
+                    event_id: data.event_id,
+                    sender: RequestResponder::Frontend(data.request_id),
+                })
+            }
+            "fetchThreadRoot" => {
+                let data: FetchThreadRootPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::FetchThreadRoot {
+                    room_id: data.room_id,
+                    filter: data.filter,
+                    thread_root_event_id: data.thread_root_event_id,
+                })
+            }
+            "syncRoomMemberList" => {
+                let data: SyncRoomMemberListPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SyncRoomMemberList {
+                    timeline_kind: data.target.into(),
+                })
+            }
             "joinRoom" => {
                 let data: JoinRoomPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
@@ -271,15 +590,30 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                     room_id: data.room_id,
                 })
             }
-            // "getRoomMembers" => {
-            //     let data: GetRoomMembersPayload =
-            //         serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
-            //     Ok(MatrixRequest::GetRoomMembers {
-            //         room_id: data.room_id,
-            //         memberships: data.memberships,
-            //         local_only: data.local_only,
-            //     })
-            // }
+            "getRoomMembers" => {
+                let data: GetRoomMembersPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                let mut memberships = RoomMemberships::empty();
+                for membership in data.memberships {
+                    memberships |= match membership {
+                        MembershipState::Join => RoomMemberships::JOIN,
+                        MembershipState::Invite => RoomMemberships::INVITE,
+                        MembershipState::Knock => RoomMemberships::KNOCK,
+                        MembershipState::Leave => RoomMemberships::LEAVE,
+                        MembershipState::Ban => RoomMemberships::BAN,
+                        other => {
+                            return Err(serde::de::Error::custom(format!(
+                                "unsupported membership: {other}"
+                            )));
+                        }
+                    };
+                }
+                Ok(MatrixRequest::GetRoomMembers {
+                    timeline_kind: data.target.into(),
+                    memberships,
+                    local_only: data.local_only,
+                })
+            }
             "getUserProfile" => {
                 let data: GetUserProfilePayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
@@ -287,39 +621,107 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                     user_id: data.user_id,
                     room_id: data.room_id,
                     local_only: data.local_only,
-                    sender: None,
+                    sender: data.request_id.map(RequestResponder::Frontend),
                 })
             }
             "getNumberUnreadMessages" => {
                 let data: GetNumberUnreadMessagesPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::GetNumberUnreadMessages {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
-                })
-            }
-            // "ignoreUser" => {
-            //     let data: IgnoreUserPayload =
-            //         serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
-            //     Ok(MatrixRequest::IgnoreUser {
-            //         ignore: data.ignore,
-            //         room_member: data.room_member,
-            //         room_id: data.room_id,
-            //     })
-            // }
+                    timeline_kind: data.target.into(),
+                })
+            }
+            "ignoreUser" => {
+                let data: IgnoreUserPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::IgnoreUser {
+                    ignore: data.ignore,
+                    user_id: data.user_id,
+                    room_id: data.room_id,
+                })
+            }
             "resolveRoomAlias" => {
                 let alias =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::ResolveRoomAlias(alias))
             }
+            "fetchMedia" => {
+                let data: FetchMediaPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::FetchMedia {
+                    media_request: MediaRequestParameters {
+                        source: data.source,
+                        format: data
+                            .thumbnail
+                            .map_or(MediaFormat::File, MediaFormat::Thumbnail),
+                    },
+                    content_sender: RequestResponder::Frontend(data.request_id),
+                })
+            }
             "sendTextMessage" => {
                 let data: SendTextMessagePayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::SendTextMessage {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                     message: data.message,
+                    format: data.format,
+                    mentioned_user_ids: data.mentioned_user_ids,
+                    mention_room: data.mention_room,
                     replied_to_id: data.reply_to_id,
                 })
             }
+            "sendEmote" => {
+                let data: SendEmotePayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SendEmote {
+                    timeline_kind: data.target.into(),
+                    message: data.message,
+                    format: data.format,
+                })
+            }
+            "sendLocation" => {
+                let data: SendLocationPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SendLocation {
+                    timeline_kind: data.target.into(),
+                    geo_uri: data.geo_uri,
+                    description: data.description,
+                })
+            }
+            "sendSticker" => {
+                let data: SendStickerPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SendSticker {
+                    timeline_kind: data.target.into(),
+                    // As in image packs, the body defaults to the shortcode.
+                    body: data.body.unwrap_or(data.shortcode),
+                    url: data.url,
+                    info: data.info.unwrap_or_default(),
+                })
+            }
+            "fetchLinkPreview" => {
+                let data: FetchLinkPreviewPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::FetchLinkPreview {
+                    timeline_kind: data.target.into(),
+                    url: data.url,
+                })
+            }
+            "setUrlPreviewsEnabled" => {
+                let data: SetUrlPreviewsEnabledPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SetUrlPreviewsEnabled {
+                    room_id: data.room_id,
+                    enabled: data.enabled,
+                })
+            }
+            "retryDecryption" => {
+                let data: RetryDecryptionPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::RetryDecryption {
+                    timeline_kind: data.target.into(),
+                })
+            }
             "sendTypingNotice" => {
                 let data: SendTypingNoticePayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
@@ -336,11 +738,36 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                     subscribe: data.subscribe,
                 })
             }
+            "subscribeToPinnedEvents" => {
+                let data: SubscribeToPinnedEventsPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SubscribeToPinnedEvents {
+                    room_id: data.room_id,
+                    subscribe: data.subscribe,
+                })
+            }
+            "paginateThreadsList" => {
+                let data: PaginateThreadsListPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::PaginateThreadsList {
+                    room_id: data.room_id,
+                    filter: data.filter,
+                    from: data.from,
+                })
+            }
+            "subscribeToThreadReplies" => {
+                let data: SubscribeToThreadRepliesPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SubscribeToThreadReplies {
+                    room_id: data.room_id,
+                    subscribe: data.subscribe,
+                })
+            }
             "subscribeToOwnUserReadReceiptsChanged" => {
                 let data: SubscribeToOwnUserReadReceiptsChangedPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::SubscribeToOwnUserReadReceiptsChanged {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                     subscribe: data.subscribe,
                 })
             }
@@ -348,33 +775,33 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                 let data: ReadReceiptPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::ReadReceipt {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                     event_id: data.event_id,
                     receipt_type: data.receipt_type,
                 })
             }
             "markRoomAsRead" => {
-                let data: MarkRoomAsRead =
+                let data: MarkRoomAsReadPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::MarkRoomAsRead {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                 })
             }
             "getRoomPowerLevels" => {
                 let data: GetRoomPowerLevelsPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::GetRoomPowerLevels {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                 })
             }
             "toggleReaction" => {
                 let data: ToggleReactionPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::ToggleReaction {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                     timeline_event_id: TimelineEventItemId::EventId(
                         OwnedEventId::try_from(data.timeline_event_id)
-                            .expect("Frontend sent incorrect event id"),
+                            .map_err(serde::de::Error::custom)?,
                     ), // We only use eventId, not transactions.
                     reaction: data.reaction,
                 })
@@ -383,19 +810,119 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                 let data: RedactMessagePayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
                 Ok(MatrixRequest::RedactMessage {
-                    timeline_kind: get_timeline_kind(data.room_id, data.thread_root_event_id),
+                    timeline_kind: data.target.into(),
                     timeline_event_id: TimelineEventItemId::EventId(data.timeline_event_id),
                     reason: data.reason,
                 })
             }
-            // "getMatrixRoomLinkPillInfo" => {
-            //     let data: GetMatrixRoomLinkPillInfoPayload =
-            //         serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
-            //     Ok(MatrixRequest::GetMatrixRoomLinkPillInfo {
-            //         matrix_id: data.matrix_id,
-            //         via: data.via,
-            //     })
-            // }
+            "pinEvent" => {
+                let data: PinEventPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::PinEvent {
+                    room_id: data.room_id,
+                    event_id: data.event_id,
+                })
+            }
+            "unpinEvent" => {
+                let data: PinEventPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::UnpinEvent {
+                    room_id: data.room_id,
+                    event_id: data.event_id,
+                })
+            }
+            "retrySend" => {
+                let data: RetrySendPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::RetrySend {
+                    timeline_kind: data.target.into(),
+                    transaction_id: data.transaction_id,
+                })
+            }
+            "cancelSend" => {
+                let data: CancelSendPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::CancelSend {
+                    timeline_kind: data.target.into(),
+                    transaction_id: data.transaction_id,
+                })
+            }
+            "startPoll" => {
+                let data: StartPollPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::StartPoll {
+                    timeline_kind: data.target.into(),
+                    question: data.question,
+                    answers: data.answers,
+                    kind: data.kind,
+                    max_selections: data.max_selections,
+                })
+            }
+            "sendPollResponse" => {
+                let data: SendPollResponsePayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SendPollResponse {
+                    timeline_kind: data.target.into(),
+                    poll_start_event_id: data.poll_start_event_id,
+                    answer_ids: data.answer_ids,
+                })
+            }
+            "endPoll" => {
+                let data: EndPollPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::EndPoll {
+                    timeline_kind: data.target.into(),
+                    poll_start_event_id: data.poll_start_event_id,
+                })
+            }
+            "reportEvent" => {
+                let data: ReportEventPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::ReportEvent {
+                    timeline_kind: data.target.into(),
+                    event_id: data.event_id,
+                    reason: data.reason,
+                })
+            }
+            "reportRoom" => {
+                let data: ReportRoomPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::ReportRoom {
+                    room_id: data.room_id,
+                    reason: data.reason,
+                })
+            }
+            "reportUser" => {
+                let data: ReportUserPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::ReportUser {
+                    user_id: data.user_id,
+                    reason: data.reason,
+                })
+            }
+            "getMatrixRoomLinkPillInfo" => {
+                let data: GetMatrixRoomLinkPillInfoPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                // Both `https://matrix.to/#/` and `matrix:` URIs are accepted.
+                let (matrix_id, via) = match MatrixToUri::parse(&data.matrix_uri) {
+                    Ok(uri) => (uri.id().clone(), uri.via().to_vec()),
+                    Err(_) => {
+                        let uri =
+                            MatrixUri::parse(&data.matrix_uri).map_err(serde::de::Error::custom)?;
+                        (uri.id().clone(), uri.via().to_vec())
+                    }
+                };
+                Ok(MatrixRequest::GetMatrixRoomLinkPillInfo { matrix_id, via })
+            }
+            "searchUsers" => {
+                let data: SearchUsersPayload =
+                    serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
+                Ok(MatrixRequest::SearchUsers {
+                    search_term: data.search_term,
+                    limit: data.limit,
+                    content_sender: RequestResponder::Frontend(data.request_id),
+                })
+            }
             "createRoom" => {
                 let data: CreateRoomPayload =
                     serde_json::from_value(payload.clone()).map_err(serde::de::Error::custom)?;
@@ -437,24 +964,49 @@ impl<'de> Deserialize<'de> for MatrixRequest {
                     "paginateTimeline",
                     "editMessage",
                     "fetchDetailsForEvent",
-                    // "syncRoomMemberList",
+                    "jumpToEvent",
+                    "createThreadTimeline",
+                    "createFocusedEventTimeline",
+                    "fetchThreadRoot",
+                    "syncRoomMemberList",
                     "joinRoom",
                     "leaveRoom",
-                    // "getRoomMembers",
+                    "getRoomMembers",
                     "getUserProfile",
                     "getNumberUnreadMessages",
-                    // "ignoreUser",
+                    "ignoreUser",
                     "resolveRoomAlias",
+                    "fetchMedia",
                     "sendTextMessage",
+                    "sendEmote",
+                    "sendLocation",
+                    "sendSticker",
+                    "fetchLinkPreview",
+                    "setUrlPreviewsEnabled",
+                    "retryDecryption",
                     "sendTypingNotice",
                     "subscribeToTypingNotices",
+                    "subscribeToPinnedEvents",
+                    "paginateThreadsList",
+                    "subscribeToThreadReplies",
                     "subscribeToOwnUserReadReceiptsChanged",
                     "readReceipt",
                     "markRoomAsRead",
                     "getRoomPowerLevels",
                     "toggleReaction",
                     "redactMessage",
-                    // "getMatrixRoomLinkPillInfo",
+                    "pinEvent",
+                    "unpinEvent",
+                    "retrySend",
+                    "cancelSend",
+                    "startPoll",
+                    "sendPollResponse",
+                    "endPoll",
+                    "reportEvent",
+                    "reportRoom",
+                    "reportUser",
+                    "getMatrixRoomLinkPillInfo",
+                    "searchUsers",
                     "createDMRoom",
                     "createRoom",
                     "inviteUsersInRoom",
@@ -466,198 +1018,774 @@ impl<'de> Deserialize<'de> for MatrixRequest {
 }
 
 // Helper structs for deserializing payloads
+
+/// The timeline addressed by a payload, flattened into it.
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
-struct PaginateTimelinePayload {
+struct TimelineTarget {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
+    /// Set to target this thread, instead of the main timeline.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     thread_root_event_id: Option<OwnedEventId>,
+    /// Set to target the timeline focused on this event, instead of the live one.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>", optional))]
+    focused_event_id: Option<OwnedEventId>,
+}
+
+impl From<TimelineTarget> for TimelineKind {
+    fn from(target: TimelineTarget) -> Self {
+        get_timeline_kind(
+            target.room_id,
+            target.thread_root_event_id,
+            target.focused_event_id,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct PaginateTimelinePayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
     num_events: u16,
     direction: PaginationDirection,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct EditMessagePayload {
-    room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
     timeline_event_item_id: FrontendTimelineEventItemId,
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(as = "crate::room::frontend_events::msg_like::MatrixMessageContent")
+    )]
     edited_content: RoomMessageEventContentWithoutRelation,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct FetchDetailsForEventPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    event_id: OwnedEventId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct JumpToEventPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    event_id: OwnedEventId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct CreateThreadTimelinePayload {
+    request_id: String,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    thread_root_event_id: OwnedEventId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct CreateFocusedEventTimelinePayload {
+    request_id: String,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    room_id: OwnedRoomId,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     event_id: OwnedEventId,
 }
 
-// #[derive(Deserialize)]
-// #[serde(rename_all = "camelCase")]
-// struct SyncRoomMemberListPayload {
-//     room_id: OwnedRoomId,
-// }
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct FetchThreadRootPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    room_id: OwnedRoomId,
+    /// All the threads if missing.
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(as = "Option<FrontendThreadsListFilter>", optional)
+    )]
+    filter: FrontendThreadsListFilter,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    thread_root_event_id: OwnedEventId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SyncRoomMemberListPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+}
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct JoinRoomPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct LeaveRoomPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
 }
 
-// #[derive(Deserialize)]
-// #[serde(rename_all = "camelCase")]
-// struct GetRoomMembersPayload {
-//     room_id: OwnedRoomId,
-//     memberships: RoomMemberships,
-//     local_only: bool,
-// }
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct GetRoomMembersPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    /// Members with any of these memberships are returned, or all members if empty.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
+    memberships: Vec<MembershipState>,
+    local_only: bool,
+}
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct GetUserProfilePayload {
+    /// If set, the profile is emitted back with this ID.
+    request_id: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     user_id: OwnedUserId,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     room_id: Option<OwnedRoomId>,
     local_only: bool,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct GetNumberUnreadMessagesPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct IgnoreUserPayload {
+    ignore: bool,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    user_id: OwnedUserId,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
 }
 
-// #[derive(Deserialize)]
-// #[serde(rename_all = "camelCase")]
-// struct IgnoreUserPayload {
-//     ignore: bool,
-//     room_member: RoomMember,
-//     room_id: OwnedRoomId,
-// }
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct FetchMediaPayload {
+    request_id: String,
+    #[cfg_attr(feature = "ts-rs", ts(type = "{ url: string } | { file: unknown }"))]
+    source: MediaSource,
+    /// The full file is fetched if no thumbnail is requested.
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(type = "{ method: string, width: number, height: number, animated: boolean } | null")
+    )]
+    thumbnail: Option<MediaThumbnailSettings>,
+}
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct SendTextMessagePayload {
-    room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
     message: String,
+    /// Plain if missing.
+    #[serde(default)]
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<TextMessageFormat>", optional))]
+    format: TextMessageFormat,
+    #[serde(default)]
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<Vec<String>>", optional))]
+    mentioned_user_ids: Vec<OwnedUserId>,
+    #[serde(default)]
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<bool>", optional))]
+    mention_room: bool,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     reply_to_id: Option<OwnedEventId>,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SendEmotePayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    message: String,
+    /// Plain if missing.
+    #[serde(default)]
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<TextMessageFormat>", optional))]
+    format: TextMessageFormat,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SendLocationPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    geo_uri: String,
+    description: Option<String>,
+}
+
+/// The fields of an image of an image pack (`im.ponies.room_emotes` or `im.ponies.user_emotes`).
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SendStickerPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    shortcode: String,
+    body: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    url: OwnedMxcUri,
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(type = "{ h?: number, w?: number, mimetype?: string, size?: number } | null")
+    )]
+    info: Option<ImageInfo>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct FetchLinkPreviewPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SetUrlPreviewsEnabledPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    room_id: OwnedRoomId,
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct RetryDecryptionPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct SendTypingNoticePayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
     typing: bool,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct SubscribeToTypingNoticesPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
     subscribe: bool,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
-struct SubscribeToOwnUserReadReceiptsChangedPayload {
+struct SubscribeToPinnedEventsPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
     subscribe: bool,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
-struct ReadReceiptPayload {
+struct PaginateThreadsListPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+    /// All the threads if missing.
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(as = "Option<FrontendThreadsListFilter>", optional)
+    )]
+    filter: FrontendThreadsListFilter,
+    from: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SubscribeToThreadRepliesPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    room_id: OwnedRoomId,
+    subscribe: bool,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SubscribeToOwnUserReadReceiptsChangedPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    subscribe: bool,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct ReadReceiptPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     event_id: OwnedEventId,
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(type = r#""m.read" | "m.read.private" | "m.fully_read""#)
+    )]
     receipt_type: ReceiptType,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
-struct MarkRoomAsRead {
-    room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+struct MarkRoomAsReadPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct GetRoomPowerLevelsPayload {
-    room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct ToggleReactionPayload {
-    room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
     timeline_event_id: String,
     reaction: String,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct RedactMessagePayload {
-    room_id: OwnedRoomId,
-    thread_root_event_id: Option<OwnedEventId>,
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     timeline_event_id: OwnedEventId,
     reason: Option<String>,
 }
 
-// #[derive(Deserialize)]
-// #[serde(rename_all = "camelCase")]
-// struct GetMatrixRoomLinkPillInfoPayload {
-//     matrix_id: MatrixId,
-//     via: Vec<OwnedServerName>,
-// }
+/// Payload of both the `pinEvent` and `unpinEvent` requests.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct PinEventPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    room_id: OwnedRoomId,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    event_id: OwnedEventId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct RetrySendPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    transaction_id: OwnedTransactionId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct CancelSendPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    transaction_id: OwnedTransactionId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct StartPollPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    question: String,
+    answers: Vec<String>,
+    kind: FrontendPollKind,
+    max_selections: u8,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SendPollResponsePayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    poll_start_event_id: OwnedEventId,
+    answer_ids: Vec<String>,
+}
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct EndPollPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    poll_start_event_id: OwnedEventId,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct ReportEventPayload {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "ts-rs", ts(flatten))]
+    target: TimelineTarget,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    event_id: OwnedEventId,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct ReportRoomPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    room_id: OwnedRoomId,
+    reason: String,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct ReportUserPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    user_id: OwnedUserId,
+    reason: String,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct GetMatrixRoomLinkPillInfoPayload {
+    /// A `https://matrix.to/#/` or `matrix:` URI.
+    matrix_uri: String,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct SearchUsersPayload {
+    request_id: String,
+    search_term: String,
+    #[cfg_attr(feature = "ts-rs", ts(type = "number"))]
+    limit: u64,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct CreateDMRoomPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     user_id: OwnedUserId,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct CreateRoomPayload {
     room_name: String,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     room_avatar: Option<OwnedMxcUri>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     invited_user_ids: Vec<OwnedUserId>,
     topic: Option<String>,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct InviteUsersInRoomPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     invited_user_ids: Vec<OwnedUserId>,
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 struct KickOrBanUserFromRoomPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     room_id: OwnedRoomId,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     user_id: OwnedUserId,
     reason: Option<String>,
     is_ban: bool,
 }
 
-pub(crate) fn get_timeline_kind(room_id: OwnedRoomId, root: Option<OwnedEventId>) -> TimelineKind {
-    if let Some(thread_root_event_id) = root {
-        TimelineKind::Thread {
+/// The shape of a serialized [`MatrixRequest`].
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[ts(
+    rename = "MatrixRequest",
+    rename_all = "camelCase",
+    tag = "event",
+    content = "payload"
+)]
+#[allow(dead_code)]
+enum MatrixRequestBinding {
+    PaginateTimeline(PaginateTimelinePayload),
+    EditMessage(EditMessagePayload),
+    FetchDetailsForEvent(FetchDetailsForEventPayload),
+    JumpToEvent(JumpToEventPayload),
+    CreateThreadTimeline(CreateThreadTimelinePayload),
+    CreateFocusedEventTimeline(CreateFocusedEventTimelinePayload),
+    FetchThreadRoot(FetchThreadRootPayload),
+    SyncRoomMemberList(SyncRoomMemberListPayload),
+    JoinRoom(JoinRoomPayload),
+    LeaveRoom(LeaveRoomPayload),
+    GetRoomMembers(GetRoomMembersPayload),
+    GetUserProfile(GetUserProfilePayload),
+    GetNumberUnreadMessages(GetNumberUnreadMessagesPayload),
+    IgnoreUser(IgnoreUserPayload),
+    ResolveRoomAlias(String),
+    FetchMedia(FetchMediaPayload),
+    SendTextMessage(SendTextMessagePayload),
+    SendEmote(SendEmotePayload),
+    SendLocation(SendLocationPayload),
+    SendSticker(SendStickerPayload),
+    FetchLinkPreview(FetchLinkPreviewPayload),
+    SetUrlPreviewsEnabled(SetUrlPreviewsEnabledPayload),
+    RetryDecryption(RetryDecryptionPayload),
+    SendTypingNotice(SendTypingNoticePayload),
+    SubscribeToTypingNotices(SubscribeToTypingNoticesPayload),
+    SubscribeToPinnedEvents(SubscribeToPinnedEventsPayload),
+    PaginateThreadsList(PaginateThreadsListPayload),
+    SubscribeToThreadReplies(SubscribeToThreadRepliesPayload),
+    SubscribeToOwnUserReadReceiptsChanged(SubscribeToOwnUserReadReceiptsChangedPayload),
+    ReadReceipt(ReadReceiptPayload),
+    MarkRoomAsRead(MarkRoomAsReadPayload),
+    GetRoomPowerLevels(GetRoomPowerLevelsPayload),
+    ToggleReaction(ToggleReactionPayload),
+    RedactMessage(RedactMessagePayload),
+    PinEvent(PinEventPayload),
+    UnpinEvent(PinEventPayload),
+    RetrySend(RetrySendPayload),
+    CancelSend(CancelSendPayload),
+    StartPoll(StartPollPayload),
+    SendPollResponse(SendPollResponsePayload),
+    EndPoll(EndPollPayload),
+    ReportEvent(ReportEventPayload),
+    ReportRoom(ReportRoomPayload),
+    ReportUser(ReportUserPayload),
+    GetMatrixRoomLinkPillInfo(GetMatrixRoomLinkPillInfoPayload),
+    SearchUsers(SearchUsersPayload),
+    #[ts(rename = "createDMRoom")]
+    CreateDMRoom(CreateDMRoomPayload),
+    CreateRoom(CreateRoomPayload),
+    InviteUsersInRoom(InviteUsersInRoomPayload),
+    KickOrBanUserFromRoom(KickOrBanUserFromRoomPayload),
+}
+
+/// Returns the kind of the timeline addressed by a payload: the timeline focused on
+/// `focused_event_id` if set, otherwise the thread of `root` if set, otherwise the main one.
+pub(crate) fn get_timeline_kind(
+    room_id: OwnedRoomId,
+    root: Option<OwnedEventId>,
+    focused_event_id: Option<OwnedEventId>,
+) -> TimelineKind {
+    match (focused_event_id, root) {
+        (Some(event_id), _) => TimelineKind::FocusedEvent { room_id, event_id },
+        (None, Some(thread_root_event_id)) => TimelineKind::Thread {
             room_id,
             thread_root_event_id,
-        }
-    } else {
-        TimelineKind::MainRoom { room_id }
+        },
+        (None, None) => TimelineKind::MainRoom { room_id },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn deserialize(event: &str, payload: Value) -> Result<MatrixRequest, serde_json::Error> {
+        serde_json::from_value(json!({ "event": event, "payload": payload }))
+    }
+
+    #[test]
+    fn deserializes_requests() {
+        let request = deserialize(
+            "paginateTimeline",
+            json!({ "roomId": "!room:example.org", "numEvents": 50, "direction": "backwards" }),
+        )
+        .unwrap();
+        assert!(matches!(
+            request,
+            MatrixRequest::PaginateTimeline {
+                timeline_kind: TimelineKind::MainRoom { .. },
+                num_events: 50,
+                direction: PaginationDirection::Backwards,
+            }
+        ));
+
+        let request = deserialize(
+            "fetchDetailsForEvent",
+            json!({
+                "roomId": "!room:example.org",
+                "threadRootEventId": "$root:example.org",
+                "eventId": "$event:example.org",
+            }),
+        )
+        .unwrap();
+        let MatrixRequest::FetchDetailsForEvent {
+            timeline_kind:
+                TimelineKind::Thread {
+                    room_id,
+                    thread_root_event_id,
+                },
+            ..
+        } = request
+        else {
+            panic!("expected a FetchDetailsForEvent request on a thread");
+        };
+        assert_eq!(room_id, "!room:example.org");
+        assert_eq!(thread_root_event_id, "$root:example.org");
+
+        // The focused event takes precedence over the thread.
+        let request = deserialize(
+            "paginateTimeline",
+            json!({
+                "roomId": "!room:example.org",
+                "threadRootEventId": "$root:example.org",
+                "focusedEventId": "$focused:example.org",
+                "numEvents": 20,
+                "direction": "forwards",
+            }),
+        )
+        .unwrap();
+        let MatrixRequest::PaginateTimeline {
+            timeline_kind: TimelineKind::FocusedEvent { event_id, .. },
+            num_events: 20,
+            direction: PaginationDirection::Forwards,
+        } = request
+        else {
+            panic!("expected a PaginateTimeline request on a focused timeline");
+        };
+        assert_eq!(event_id, "$focused:example.org");
+
+        let request = deserialize(
+            "fetchThreadRoot",
+            json!({
+                "roomId": "!room:example.org",
+                "filter": "participated",
+                "threadRootEventId": "$root:example.org",
+            }),
+        )
+        .unwrap();
+        let MatrixRequest::FetchThreadRoot {
+            room_id,
+            filter,
+            thread_root_event_id,
+        } = request
+        else {
+            panic!("expected a FetchThreadRoot request");
+        };
+        assert_eq!(room_id, "!room:example.org");
+        assert_eq!(filter, FrontendThreadsListFilter::Participated);
+        assert_eq!(thread_root_event_id, "$root:example.org");
+
+        // The filter defaults to all the threads.
+        let request = deserialize(
+            "fetchThreadRoot",
+            json!({ "roomId": "!room:example.org", "threadRootEventId": "$root:example.org" }),
+        )
+        .unwrap();
+        assert!(matches!(
+            request,
+            MatrixRequest::FetchThreadRoot {
+                filter: FrontendThreadsListFilter::All,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        assert!(deserialize("unknownRequest", json!({})).is_err());
+        assert!(
+            deserialize(
+                "fetchThreadRoot",
+                json!({ "roomId": "!room:example.org", "threadRootEventId": "not an event ID" }),
+            )
+            .is_err()
+        );
     }
 }
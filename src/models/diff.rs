@@ -0,0 +1,260 @@
+//! JSON-patch-like operations sent to the adapter in diff mode,
+//! see [`super::state_updater::StateUpdaterFunctions::use_diffs`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use serde::Serialize;
+
+/// An operation on a keyed collection of a frontend store.
+#[derive(Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "op")]
+pub enum DiffOp<K, V> {
+    /// Insert a new value. For ordered collections, `index` is its position once inserted.
+    Insert {
+        key: K,
+        index: Option<usize>,
+        value: V,
+    },
+    /// Replace the value with the given key.
+    Update { key: K, value: V },
+    /// Remove the value with the given key.
+    Remove { key: K },
+}
+
+/// Computes the ops turning the `old` ordered list into `new`.
+///
+/// Values present in both lists are compared with `is_same`, so only the inserted or
+/// updated values are mapped by `map`, which can also filter some values out.
+/// The ops must be applied in order: all the removals come first, then the inserts and
+/// updates by ascending index. Returns the ops and the new list, to diff the next update against.
+///
+/// This rescans both lists rather than forwarding the SDK's `VectorDiff`s: the timeline
+/// subscriber applies those to its own vector and only sends the resulting snapshot, several
+/// of them can be batched before the frontend is updated, and their indices don't match the
+/// frontend's once `map` filtered some items out. The items are behind `Arc`s, so the
+/// rescan is a linear walk of pointer comparisons that only maps the changed items.
+#[allow(clippy::type_complexity)]
+pub(crate) fn ordered_diff<K, T, V>(
+    old: &[(K, T)],
+    new: impl IntoIterator<Item = (K, T)>,
+    is_same: impl Fn(&T, &T) -> bool,
+    map: impl Fn(&T) -> Option<V>,
+) -> (Vec<DiffOp<K, V>>, Vec<(K, T)>)
+where
+    K: Clone + Eq + Hash,
+{
+    let old_indices: HashMap<&K, usize> = old
+        .iter()
+        .enumerate()
+        .map(|(index, (key, _))| (key, index))
+        .collect();
+
+    // Mapped values of the inserted or updated entries, the others are unchanged.
+    let mut entries: Vec<((K, T), Option<V>)> = Vec::new();
+    for (key, value) in new {
+        match old_indices.get(&key) {
+            Some(&index) if is_same(&old[index].1, &value) => entries.push(((key, value), None)),
+            _ => {
+                if let Some(mapped) = map(&value) {
+                    entries.push(((key, value), Some(mapped)));
+                }
+            }
+        }
+    }
+
+    let new_keys: HashSet<&K> = entries.iter().map(|((key, _), _)| key).collect();
+    let mut removals: Vec<DiffOp<K, V>> = old
+        .iter()
+        .filter(|(key, _)| !new_keys.contains(key))
+        .map(|(key, _)| DiffOp::Remove { key: key.clone() })
+        .collect();
+
+    // The kept entries must stay in the same relative order, the moved ones are
+    // removed then inserted again at their new position.
+    let mut ops = Vec::new();
+    let mut last_old_index = None;
+    for (new_index, ((key, value), mapped)) in entries.iter_mut().enumerate() {
+        let old_index = old_indices.get(key).copied();
+        let is_moved = match (old_index, last_old_index) {
+            (Some(old_index), Some(last)) if old_index < last => true,
+            (Some(old_index), _) => {
+                last_old_index = Some(old_index);
+                false
+            }
+            (None, _) => false,
+        };
+        if is_moved {
+            removals.push(DiffOp::Remove { key: key.clone() });
+        }
+        let value = match mapped.take() {
+            Some(mapped) => mapped,
+            // Unchanged values are only sent again when they moved.
+            None if is_moved => match map(value) {
+                Some(mapped) => mapped,
+                None => continue,
+            },
+            None => continue,
+        };
+        ops.push(if old_index.is_none() || is_moved {
+            DiffOp::Insert {
+                key: key.clone(),
+                index: Some(new_index),
+                value,
+            }
+        } else {
+            DiffOp::Update {
+                key: key.clone(),
+                value,
+            }
+        });
+    }
+
+    removals.extend(ops);
+    (
+        removals,
+        entries.into_iter().map(|(entry, _)| entry).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values are `(version, is_shown)` pairs, mapped to their version when shown.
+    type Entry = (char, (u32, bool));
+
+    fn diff(old: &[Entry], new: &[Entry]) -> (Vec<DiffOp<char, u32>>, Vec<Entry>) {
+        ordered_diff(
+            old,
+            new.to_vec(),
+            |a, b| a == b,
+            |&(version, is_shown)| is_shown.then_some(version),
+        )
+    }
+
+    /// Applies the ops to the frontend's list, the way the adapters do.
+    fn apply(list: &mut Vec<(char, u32)>, ops: Vec<DiffOp<char, u32>>) {
+        for op in ops {
+            match op {
+                DiffOp::Insert { key, index, value } => {
+                    list.insert(index.expect("ordered inserts have an index"), (key, value))
+                }
+                DiffOp::Update { key, value } => {
+                    list.iter_mut().find(|(k, _)| *k == key).unwrap().1 = value
+                }
+                DiffOp::Remove { key } => list.retain(|(k, _)| *k != key),
+            }
+        }
+    }
+
+    fn shown(keys: &str) -> Vec<Entry> {
+        keys.chars().map(|key| (key, (0, true))).collect()
+    }
+
+    #[test]
+    fn remove() {
+        let (ops, sent) = diff(&shown("abc"), &shown("ac"));
+        assert_eq!(ops, vec![DiffOp::Remove { key: 'b' }]);
+        assert_eq!(sent, shown("ac"));
+    }
+
+    #[test]
+    fn insert() {
+        let (ops, sent) = diff(&shown("ac"), &shown("abc"));
+        assert_eq!(
+            ops,
+            vec![DiffOp::Insert {
+                key: 'b',
+                index: Some(1),
+                value: 0
+            }]
+        );
+        assert_eq!(sent, shown("abc"));
+    }
+
+    #[test]
+    fn update() {
+        let mut new = shown("abc");
+        new[1].1.0 = 1;
+        let (ops, sent) = diff(&shown("abc"), &new);
+        assert_eq!(ops, vec![DiffOp::Update { key: 'b', value: 1 }]);
+        assert_eq!(sent, new);
+    }
+
+    #[test]
+    fn unchanged() {
+        let (ops, sent) = diff(&shown("abc"), &shown("abc"));
+        assert!(ops.is_empty());
+        assert_eq!(sent, shown("abc"));
+    }
+
+    #[test]
+    fn move_reinserts_after_removals() {
+        let mut list = vec![('a', 0), ('b', 0), ('c', 0), ('d', 0)];
+        let (ops, sent) = diff(&shown("abcd"), &shown("dbca"));
+        // All the removals come before the inserts.
+        let first_insert = ops
+            .iter()
+            .position(|op| matches!(op, DiffOp::Insert { .. }))
+            .unwrap();
+        assert!(
+            ops[first_insert..]
+                .iter()
+                .all(|op| !matches!(op, DiffOp::Remove { .. }))
+        );
+        apply(&mut list, ops);
+        assert_eq!(list, vec![('d', 0), ('b', 0), ('c', 0), ('a', 0)]);
+        assert_eq!(sent, shown("dbca"));
+    }
+
+    #[test]
+    fn mixed_changes_apply_in_order() {
+        let old = shown("abcdef");
+        let mut new = shown("xfbdyc");
+        new[2].1.0 = 2;
+        let mut list: Vec<_> = old
+            .iter()
+            .map(|&(key, (version, _))| (key, version))
+            .collect();
+        let (ops, sent) = diff(&old, &new);
+        apply(&mut list, ops);
+        assert_eq!(
+            list,
+            vec![('x', 0), ('f', 0), ('b', 2), ('d', 0), ('y', 0), ('c', 0)]
+        );
+        assert_eq!(sent, new);
+    }
+
+    #[test]
+    fn filtered_values_are_not_sent() {
+        let mut new = shown("abc");
+        new[1].1.1 = false;
+        let (ops, sent) = diff(&shown("c"), &new);
+        // The hidden value doesn't count in the indices of the next ones.
+        assert_eq!(
+            ops,
+            vec![DiffOp::Insert {
+                key: 'a',
+                index: Some(0),
+                value: 0
+            }]
+        );
+        assert_eq!(sent, shown("ac"));
+    }
+
+    #[test]
+    fn values_filtered_out_are_removed() {
+        let mut new = shown("abc");
+        new[1].1 = (1, false);
+        let mut list = vec![('a', 0), ('b', 0), ('c', 0)];
+        let (ops, sent) = diff(&shown("abc"), &new);
+        assert_eq!(ops, vec![DiffOp::Remove { key: 'b' }]);
+        apply(&mut list, ops);
+        assert_eq!(list, vec![('a', 0), ('c', 0)]);
+        assert_eq!(sent, shown("ac"));
+    }
+}
@@ -1,31 +1,45 @@
 use matrix_sdk::ruma::{MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedEventId, OwnedRoomId};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    error::SerializedError, models::profile::ProfileModel, user::user_profile::UserProfile,
+};
+
 // Listen to events
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum ListenEvent {
     VerificationResult,
     MatrixUpdateCurrentActiveRoom,
     MatrixLogin,
+    MatrixRegistration,
     CancelVerification,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct MatrixVerificationResponse {
     pub confirmed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct MatrixUpdateCurrentActiveRoom {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub room_id: OwnedRoomId,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub thread_root_event_id: Option<OwnedEventId>,
+    /// Set to open the room on a timeline loaded around this event, e.g., a permalink target.
+    #[serde(default)]
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>", optional))]
+    pub focused_event_id: Option<OwnedEventId>,
     pub room_name: String,
 }
 
 /// The user's account credentials to create a new Matrix session
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct MatrixLoginPayload {
     pub username: String,
@@ -34,6 +48,43 @@ pub struct MatrixLoginPayload {
     pub client_name: String,
 }
 
+/// The account to create on the homeserver
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct MatrixRegistrationPayload {
+    pub username: String,
+    pub password: String,
+    pub client_name: String,
+}
+
+/// Registration events sent by the frontend. The `Register` event starts the
+/// registration, then the others answer the UIAA stages described by
+/// [`EmitEvent::RegistrationStage`].
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "payload"
+)]
+pub enum MatrixRegistrationEvent {
+    Register(MatrixRegistrationPayload),
+    /// The user accepted the policies of the `terms` stage.
+    AcceptTerms,
+    RegistrationToken {
+        token: String,
+    },
+    /// Ask the homeserver to send a validation link to this email.
+    RequestEmailValidation {
+        email: String,
+    },
+    /// The user clicked the validation link.
+    EmailValidated,
+    Cancel,
+}
+
 // Emit events
 
 #[derive(Debug, Clone)]
@@ -42,11 +93,154 @@ pub enum EmitEvent {
     ToastNotification(ToastNotificationRequest),
     OsNotification(OsNotificationRequest),
     OAuthUrl(String),
+    /// The URL to open to log in with the legacy SSO flow.
+    SsoUrl(String),
     ResetCrossSigngingUrl(String),
     NewlyCreatedRoomId(OwnedRoomId),
+    RegistrationStage(RegistrationStageRequest),
+    QrLoginProgress(QrLoginProgress),
+    /// The result of a [`crate::MatrixRequest`] sent by the frontend with a `requestId`.
+    RequestResponse(MatrixRequestResponse),
+}
+
+/// The response to a request, correlated with its `requestId`.
+/// Either `response` or `error` is set.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct MatrixRequestResponse {
+    request_id: String,
+    response: Option<MatrixRequestResponseData>,
+    error: Option<SerializedError>,
+}
+
+impl MatrixRequestResponse {
+    pub(crate) fn new(
+        request_id: String,
+        result: crate::Result<MatrixRequestResponseData>,
+    ) -> Self {
+        match result {
+            Ok(response) => Self {
+                request_id,
+                response: Some(response),
+                error: None,
+            },
+            Err(e) => Self {
+                request_id,
+                response: None,
+                error: Some(SerializedError::from(&e)),
+            },
+        }
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind",
+    content = "data"
+)]
+pub enum MatrixRequestResponseData {
+    /// A thread or an event-focused timeline has been created.
+    TimelineCreated,
+    UserProfile(Option<UserProfile>),
+    /// The content of the fetched media.
+    Media(Vec<u8>),
+    Users(Vec<ProfileModel>),
+}
+
+/// The steps of a QR code login (MSC4108), either on the new device or on the device
+/// granting the login.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind",
+    content = "data"
+)]
+pub enum QrLoginProgress {
+    Starting,
+    /// The bytes to encode in the QR code, to be scanned by the new device.
+    QrReady {
+        qr_code: Vec<u8>,
+    },
+    /// The new device scanned the QR code. The user must enter the check code it displays,
+    /// then the frontend submits it with `submit_qr_login_check_code`.
+    CheckCodeRequired,
+    /// The check code to display on the new device, to be entered on the other device.
+    CheckCode {
+        check_code: u8,
+    },
+    /// The user must open this URL to approve the new device.
+    WaitingForAuth {
+        verification_uri: String,
+    },
+    /// The new device waits for the other device to approve the login.
+    WaitingForToken {
+        user_code: String,
+    },
+    SyncingSecrets,
+    Done,
+}
+
+/// A UIAA stage the user has to complete to register its account.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrationStageRequest {
+    pub stage: RegistrationStage,
+    /// The error returned by the homeserver for the previous attempt, if any.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind",
+    content = "data"
+)]
+pub enum RegistrationStage {
+    /// The user must accept these policies.
+    Terms { policies: Vec<RegistrationPolicy> },
+    /// The user must provide a registration token.
+    RegistrationToken,
+    /// The user must provide an email to validate.
+    EmailIdentity,
+    /// A validation link has been sent to this email.
+    AwaitingEmailValidation { email: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrationPolicy {
+    pub id: String,
+    pub version: String,
+    pub name: String,
+    pub url: String,
+}
+
+/// A registration flow advertised by the homeserver.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendRegistrationFlow {
+    pub stages: Vec<String>,
+    /// Whether this lib can drive all the stages of this flow.
+    pub is_supported: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct MatrixVerificationEmojis {
     emojis: String,
@@ -59,6 +253,7 @@ impl MatrixVerificationEmojis {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct MatrixRoomStoreCreateRequest {
     id: String,
@@ -71,6 +266,7 @@ impl MatrixRoomStoreCreateRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct ToastNotificationRequest {
     message: String,
@@ -79,6 +275,7 @@ pub struct ToastNotificationRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum ToastNotificationVariant {
     Default,
@@ -113,6 +310,7 @@ impl ToastNotificationRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct OsNotificationRequest {
     pub summary: String,
@@ -128,6 +326,7 @@ impl OsNotificationRequest {
 // Channel events
 
 #[derive(Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(
     rename_all = "camelCase",
     rename_all_fields = "camelCase",
@@ -150,6 +349,7 @@ pub enum MediaStreamEvent {
 }
 
 #[derive(Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(
     rename_all = "camelCase",
     rename_all_fields = "camelCase",
@@ -164,18 +364,22 @@ pub enum VerifyDeviceEvent {
 
 // Commands
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct FrontendDevice {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub device_id: OwnedDeviceId,
     pub is_verified: bool,
     pub is_verified_with_cross_signing: bool,
     pub display_name: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(type = "number | null"))]
     pub last_seen_ts: Option<MilliSecondsSinceUnixEpoch>,
     pub guessed_type: DeviceGuessedType,
     pub is_current_device: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum DeviceGuessedType {
     Android,
@@ -1,23 +1,102 @@
-use matrix_sdk::ruma::{OwnedMxcUri, OwnedRoomId};
+use matrix_sdk::{
+    ComposerDraft, ComposerDraftType,
+    ruma::{OwnedEventId, OwnedMxcUri, OwnedRoomId},
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 /// Payload to edit current user's information.
 /// Only the Some(...) fields are updated, None are ignored.
 pub struct EditUserInformationPayload {
     pub new_display_name: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub new_avatar_uri: Option<OwnedMxcUri>,
     pub new_device_name: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 /// Payload to edit current user's information.
 /// Only the Some(...) fields are updated, None are ignored.
 pub struct EditRoomInformationPayload {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub room_id: OwnedRoomId,
     pub new_display_name: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub new_avatar_uri: Option<OwnedMxcUri>,
     pub topic: Option<String>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+/// Draft of the message composer of a room or thread timeline,
+/// stored in the client's state store.
+pub struct FrontendComposerDraft {
+    pub text: String,
+    /// The HTML representation of the draft, if it is formatted.
+    pub html_text: Option<String>,
+    pub draft_type: FrontendComposerDraftType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind",
+    content = "data"
+)]
+pub enum FrontendComposerDraftType {
+    NewMessage,
+    /// The draft is a reply to the given event.
+    Reply {
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+        event_id: OwnedEventId,
+    },
+    /// The draft is an edit of the given event.
+    Edit {
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+        event_id: OwnedEventId,
+    },
+}
+
+impl From<ComposerDraft> for FrontendComposerDraft {
+    fn from(draft: ComposerDraft) -> Self {
+        Self {
+            text: draft.plain_text,
+            html_text: draft.html_text,
+            draft_type: match draft.draft_type {
+                ComposerDraftType::NewMessage => FrontendComposerDraftType::NewMessage,
+                ComposerDraftType::Reply { event_id } => {
+                    FrontendComposerDraftType::Reply { event_id }
+                }
+                ComposerDraftType::Edit { event_id } => {
+                    FrontendComposerDraftType::Edit { event_id }
+                }
+            },
+        }
+    }
+}
+
+impl From<FrontendComposerDraft> for ComposerDraft {
+    fn from(draft: FrontendComposerDraft) -> Self {
+        Self {
+            plain_text: draft.text,
+            html_text: draft.html_text,
+            draft_type: match draft.draft_type {
+                FrontendComposerDraftType::NewMessage => ComposerDraftType::NewMessage,
+                FrontendComposerDraftType::Reply { event_id } => {
+                    ComposerDraftType::Reply { event_id }
+                }
+                FrontendComposerDraftType::Edit { event_id } => {
+                    ComposerDraftType::Edit { event_id }
+                }
+            },
+            attachments: Vec::new(),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! ViewModels exposed to the adapter
 
 pub(crate) mod async_requests;
+pub mod diff;
 pub mod event_bridge;
 pub mod events;
 pub mod misc;
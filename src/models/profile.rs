@@ -3,11 +3,14 @@ use matrix_sdk::ruma::{
 };
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileModel {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub user_id: OwnedUserId,
     pub display_name: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub avatar_url: Option<OwnedMxcUri>,
 }
 
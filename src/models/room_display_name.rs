@@ -4,6 +4,13 @@ use matrix_sdk::RoomDisplayName;
 use serde::{Serialize, Serializer};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(
+        type = r#"{ kind: "named" | "aliased" | "calculated" | "empty_was", name: string } | { kind: "empty" }"#
+    )
+)]
 pub struct FrontendRoomDisplayName(RoomDisplayName);
 
 impl Deref for FrontendRoomDisplayName {
@@ -5,10 +5,15 @@ use matrix_sdk::{
 };
 
 use crate::{
-    room::{room_screen::RoomScreen, rooms_list::RoomsList},
+    room::{
+        room_screen::{RoomScreen, RoomScreenDiff},
+        rooms_list::{RoomsList, RoomsListDiff},
+    },
     stores::login_store::{FrontendSyncServiceState, FrontendVerificationState, LoginState},
 };
+use anyhow::bail;
 use async_trait::async_trait;
+use tracing::warn;
 
 /// Super trait that defines the required "updaters" functions that will translate a library
 /// state change into a frontend one.
@@ -18,6 +23,18 @@ pub trait StateUpdater: StateUpdaterFunctions + std::fmt::Debug + Send + Sync {}
 pub trait StateUpdaterFunctions {
     fn update_rooms_list(&self, rooms_list: &RoomsList) -> anyhow::Result<()>;
     fn update_room(&self, room: &RoomScreen) -> anyhow::Result<()>;
+    /// Opt-in diff mode. When it returns `true`, the full `RoomsList` and `RoomScreen` are only
+    /// sent once (and again when they are reset), then `update_rooms_list_diff` and
+    /// `update_room_diff` are called with the changes since the previous update.
+    fn use_diffs(&self) -> bool {
+        false
+    }
+    fn update_rooms_list_diff(&self, _diff: &RoomsListDiff) -> anyhow::Result<()> {
+        bail!("Diff mode is enabled, but update_rooms_list_diff isn't implemented")
+    }
+    fn update_room_diff(&self, _diff: &RoomScreenDiff) -> anyhow::Result<()> {
+        bail!("Diff mode is enabled, but update_room_diff isn't implemented")
+    }
     fn update_sync_service(
         &self,
         sync_service_state: FrontendSyncServiceState,
@@ -42,4 +59,10 @@ pub trait StateUpdaterFunctions {
     async fn persist_refreshed_session(&self, refreshed_session: AuthSession)
     -> anyhow::Result<()>;
     async fn persist_login_session(&self, session: String) -> anyhow::Result<()>;
+    /// Called after a logout, the adapter must forget the persisted `FullMatrixSession`.
+    /// Otherwise, the revoked session would be restored at the next startup.
+    async fn clear_login_session(&self) -> anyhow::Result<()> {
+        warn!("clear_login_session isn't implemented, the persisted session is kept");
+        Ok(())
+    }
 }
@@ -1,7 +1,11 @@
 use bitflags::bitflags;
 use std::sync::Arc;
 
-use matrix_sdk::ruma::{OwnedEventId, UInt, event_id, events::room::message::MessageType};
+use matrix_sdk::{
+    Room,
+    deserialized_responses::TimelineEvent,
+    ruma::{OwnedEventId, UInt, event_id, events::room::message::MessageType},
+};
 use matrix_sdk_ui::timeline::{
     EventTimelineItem, MsgLikeKind, TimelineEventItemId, TimelineItem, TimelineItemContent,
     TimelineItemKind, VirtualTimelineItem,
@@ -10,14 +14,21 @@ use serde::{Serialize, Serializer};
 
 use crate::{
     events::timeline::TimelineKind,
-    room::frontend_events::{
-        msg_like::{FrontendStickerEventContent, SerializableReactions},
-        state_event::{
-            FrontendAnyOtherStateEventContentChange, FrontendMemberProfileChange,
-            FrontendRoomMembershipChange, FrontendStateEvent,
+    init::singletons::CURRENT_USER_ID,
+    room::{
+        frontend_events::{
+            msg_like::{FrontendStickerEventContent, SerializableReactions},
+            poll::FrontendPollState,
+            send_state::FrontendEventSendState,
+            shield::FrontendShieldState,
+            state_event::{
+                FrontendAnyOtherStateEventContentChange, FrontendMemberProfileChange,
+                FrontendRoomMembershipChange, FrontendStateEvent,
+            },
+            thread_summary::get_frontend_thread_summary,
+            timeline_item_id::FrontendTimelineEventItemId,
         },
-        thread_summary::get_frontend_thread_summary,
-        timeline_item_id::FrontendTimelineEventItemId,
+        link_preview::get_or_fetch_link_preview,
     },
     user::user_power_level::UserPowerLevels,
     utils::get_or_fetch_event_sender,
@@ -29,22 +40,30 @@ use super::{
 };
 
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct FrontendTimelineItem {
     unique_id: String,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     event_id: Option<OwnedEventId>,
     #[serde(flatten)]
     timeline_item_id: FrontendTimelineEventItemId,
     #[serde(flatten)]
     data: FrontendTimelineItemData,
+    #[cfg_attr(feature = "ts-rs", ts(type = "number | null"))]
     timestamp: Option<UInt>, // We keep the timestamp at root to sort events
     is_own: bool,
     is_local: bool,
+    /// The send state of local echoes, `null` for remote events.
+    send_state: Option<FrontendEventSendState>,
+    /// The authenticity of the event, in encrypted rooms.
+    shield: FrontendShieldState,
     abilities: MessageAbilities,
 }
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(
     rename_all = "camelCase",
     rename_all_fields = "camelCase",
@@ -60,6 +79,7 @@ pub enum FrontendTimelineItemData {
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct FrontendTimelineErrorItem {
     error: String,
@@ -85,6 +105,8 @@ pub fn to_frontend_timeline_item(
                 .into(),
                 data: FrontendTimelineItemData::Virtual(FrontendVirtualTimelineItem::DateDivider),
                 is_local: true,
+                send_state: None,
+                shield: FrontendShieldState::None,
                 is_own: true,
                 timestamp: Some(timestamp.0),
                 abilities: MessageAbilities::empty(),
@@ -98,6 +120,8 @@ pub fn to_frontend_timeline_item(
                 .into(),
                 data: FrontendTimelineItemData::Virtual(FrontendVirtualTimelineItem::ReadMarker),
                 is_local: true,
+                send_state: None,
+                shield: FrontendShieldState::None,
                 is_own: true,
                 timestamp: None,
                 abilities: MessageAbilities::empty(),
@@ -111,6 +135,8 @@ pub fn to_frontend_timeline_item(
                 .into(),
                 data: FrontendTimelineItemData::Virtual(FrontendVirtualTimelineItem::TimelineStart),
                 is_local: true,
+                send_state: None,
+                shield: FrontendShieldState::None,
                 is_own: true,
                 timestamp: None,
                 abilities: MessageAbilities::empty(),
@@ -121,7 +147,7 @@ pub fn to_frontend_timeline_item(
 
 fn map_msg_event_content(content: MessageType) -> FrontendMsgLikeKind {
     match content {
-        MessageType::Audio(c) => FrontendMsgLikeKind::Audio(c),
+        MessageType::Audio(c) => FrontendMsgLikeKind::Audio(c.into()),
         MessageType::File(c) => FrontendMsgLikeKind::File(c),
         MessageType::Image(c) => FrontendMsgLikeKind::Image(c),
         MessageType::Text(c) => FrontendMsgLikeKind::Text(c),
@@ -140,6 +166,11 @@ bitflags! {
     ///
     /// This is used to determine which buttons to show in the message context menu.
     #[derive(Copy, Clone, Debug)]
+    #[cfg_attr(
+        feature = "ts-rs",
+        derive(ts_rs::TS),
+        ts(type = r#"Array<"canReact" | "canReplyTo" | "canEdit" | "canPin" | "canUnpin" | "canDelete" | "canReport">"#)
+    )]
     pub struct MessageAbilities: u8 {
         /// Whether the user can react to this message.
         const CanReact = 1 << 0;
@@ -153,6 +184,8 @@ bitflags! {
         const CanUnpin = 1 << 4;
         /// Whether the user can delete/redact this message.
         const CanDelete = 1 << 5;
+        /// Whether the user can report this message to the homeserver administrators.
+        const CanReport = 1 << 6;
     }
 }
 impl MessageAbilities {
@@ -173,6 +206,11 @@ impl MessageAbilities {
         //       before deciding which ability to set.
         // abilities.set(Self::CanUnPin, user_power_levels.can_pin_unpin());
         abilities.set(Self::CanReact, user_power_levels._can_send_reaction());
+        // Only remote events of other users can be reported.
+        abilities.set(
+            Self::CanReport,
+            !event_tl_item.is_own() && event_tl_item.event_id().is_some(),
+        );
         abilities
     }
 }
@@ -204,6 +242,9 @@ impl Serialize for MessageAbilities {
         if self.contains(MessageAbilities::CanDelete) {
             seq.serialize_element("canDelete")?;
         }
+        if self.contains(MessageAbilities::CanReport) {
+            seq.serialize_element("canReport")?;
+        }
 
         seq.end()
     }
@@ -223,6 +264,8 @@ pub(crate) fn map_event_timeline_item(
     let sender_id = event_tl_item.sender().to_string();
     let abilities = MessageAbilities::from_user_power_and_event(user_power_levels, event_tl_item);
     let event_id = event_tl_item.event_id().map(|id| id.to_owned());
+    let send_state = event_tl_item.send_state().map(FrontendEventSendState::from);
+    let shield = FrontendShieldState::from(event_tl_item);
     map_timeline_event_item_content(
         event_tl_item.content(),
         unique_id,
@@ -234,6 +277,52 @@ pub(crate) fn map_event_timeline_item(
         sender_id,
         abilities,
         event_id,
+        send_state,
+        shield,
+    )
+    .map(|mut item| {
+        if let FrontendTimelineItemData::MsgLike(content) = &mut item.data {
+            content.link_preview = get_or_fetch_link_preview(&content.kind, kind);
+        }
+        item
+    })
+}
+
+/// Maps an event that isn't part of a timeline, such as a search result.
+///
+/// Such items have no reactions, send state, shield or abilities, since they are only displayed
+/// until the user jumps to them in the room's timeline.
+pub(crate) async fn map_standalone_event(
+    room: &Room,
+    event: TimelineEvent,
+) -> Option<FrontendTimelineItem> {
+    let event_id = event.event_id()?;
+    let sender_id = event.sender()?;
+    let timestamp = event.timestamp().map(|ts| ts.get());
+    let is_own = CURRENT_USER_ID
+        .get()
+        .is_some_and(|user_id| user_id == sender_id);
+    let sender = room
+        .get_member_no_sync(&sender_id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|member| member.display_name().map(ToOwned::to_owned))
+        .unwrap_or_else(|| sender_id.to_string());
+    let content = TimelineItemContent::from_event(room, event).await?;
+    map_timeline_event_item_content(
+        &content,
+        event_id.to_string(),
+        TimelineEventItemId::EventId(event_id.clone()).into(),
+        is_own,
+        false,
+        timestamp,
+        Some(sender),
+        sender_id.to_string(),
+        MessageAbilities::empty(),
+        Some(event_id),
+        None,
+        FrontendShieldState::None,
     )
 }
 
@@ -249,220 +338,98 @@ pub(super) fn map_timeline_event_item_content(
     sender_id: String,
     abilities: MessageAbilities,
     event_id: Option<OwnedEventId>,
+    send_state: Option<FrontendEventSendState>,
+    shield: FrontendShieldState,
 ) -> Option<FrontendTimelineItem> {
+    let data = map_timeline_item_data(timeline_item_content, sender, sender_id)?;
+    // The events that failed to parse are shown like local errors.
+    let is_error = matches!(data, FrontendTimelineItemData::Error(_));
+    Some(FrontendTimelineItem {
+        unique_id,
+        event_id: event_id.filter(|_| !is_error),
+        timeline_item_id,
+        data,
+        timestamp: timestamp.filter(|_| !is_error),
+        is_own: is_own || is_error,
+        is_local: is_local || is_error,
+        send_state,
+        shield,
+        abilities,
+    })
+}
+
+fn map_timeline_item_data(
+    timeline_item_content: &TimelineItemContent,
+    sender: Option<String>,
+    sender_id: String,
+) -> Option<FrontendTimelineItemData> {
     match timeline_item_content {
         TimelineItemContent::MsgLike(msg_like) => {
-            let in_reply_to_id = msg_like.in_reply_to.clone().map(|r| r.event_id);
-            let thread_root = msg_like.thread_root.clone();
-            let thread_summary = msg_like
-                .thread_summary
-                .clone()
-                .and_then(get_frontend_thread_summary);
-            match msg_like.kind.clone() {
-                MsgLikeKind::Message(message) => Some(FrontendTimelineItem {
-                    unique_id,
-                    event_id,
-                    timeline_item_id,
-                    is_local,
-                    is_own,
-                    timestamp,
-                    abilities,
-                    data: FrontendTimelineItemData::MsgLike(FrontendMsgLikeContent {
-                        edited: message.is_edited(),
-                        reactions: SerializableReactions(msg_like.reactions.clone()),
-                        sender_id,
-                        sender,
-                        thread_root,
-                        thread_summary,
-                        in_reply_to_id,
-                        kind: map_msg_event_content(message.msgtype().clone()),
-                    }),
-                }),
-                MsgLikeKind::Sticker(sticker) => Some(FrontendTimelineItem {
-                    unique_id,
-                    event_id,
-                    timeline_item_id,
-                    is_local,
-                    is_own,
-                    timestamp,
-                    abilities,
-                    data: FrontendTimelineItemData::MsgLike(FrontendMsgLikeContent {
-                        edited: false,
-                        reactions: SerializableReactions(msg_like.reactions.clone()),
-                        sender_id,
-                        sender,
-                        thread_root,
-                        thread_summary,
-                        in_reply_to_id,
-                        kind: FrontendMsgLikeKind::Sticker(Box::new(
-                            FrontendStickerEventContent::from(sticker.content().clone()),
-                        )),
-                    }),
-                }),
-                MsgLikeKind::Redacted => Some(FrontendTimelineItem {
-                    unique_id,
-                    event_id,
-                    timeline_item_id,
-                    is_local,
-                    is_own,
-                    timestamp,
-                    abilities,
-                    data: FrontendTimelineItemData::MsgLike(FrontendMsgLikeContent {
-                        edited: true,
-                        reactions: SerializableReactions(msg_like.reactions.clone()),
-                        sender_id,
-                        sender,
-                        thread_root,
-                        thread_summary,
-                        in_reply_to_id,
-                        kind: FrontendMsgLikeKind::Redacted,
-                    }),
-                }),
-                MsgLikeKind::UnableToDecrypt(_) => Some(FrontendTimelineItem {
-                    unique_id,
-                    event_id,
-                    timeline_item_id,
-                    is_local,
-                    is_own,
-                    timestamp,
-                    abilities,
-                    data: FrontendTimelineItemData::MsgLike(FrontendMsgLikeContent {
-                        edited: false,
-                        reactions: SerializableReactions(msg_like.reactions.clone()),
-                        sender_id,
-                        sender,
-                        thread_root,
-                        thread_summary,
-                        in_reply_to_id,
-                        kind: FrontendMsgLikeKind::UnableToDecrypt,
-                    }),
-                }),
+            let (edited, kind) = match msg_like.kind.clone() {
+                MsgLikeKind::Message(message) => (
+                    message.is_edited(),
+                    map_msg_event_content(message.msgtype().clone()),
+                ),
+                MsgLikeKind::Sticker(sticker) => (
+                    false,
+                    FrontendMsgLikeKind::Sticker(Box::new(FrontendStickerEventContent::from(
+                        sticker.content().clone(),
+                    ))),
+                ),
+                MsgLikeKind::Redacted => (true, FrontendMsgLikeKind::Redacted),
+                MsgLikeKind::UnableToDecrypt(message) => (
+                    false,
+                    FrontendMsgLikeKind::UnableToDecrypt((&message).into()),
+                ),
                 // TODO: map locations
-                MsgLikeKind::LiveLocation(_) => None,
-
-                MsgLikeKind::Poll(_) => Some(FrontendTimelineItem {
-                    unique_id,
-                    event_id,
-                    timeline_item_id,
-                    is_local,
-                    is_own,
-                    timestamp,
-                    abilities,
-                    data: FrontendTimelineItemData::MsgLike(FrontendMsgLikeContent {
-                        edited: false,
-                        reactions: SerializableReactions(msg_like.reactions.clone()),
-                        sender_id,
-                        sender,
-                        thread_root,
-                        thread_summary,
-                        in_reply_to_id,
-                        kind: FrontendMsgLikeKind::Poll,
-                    }),
-                }),
-
-                MsgLikeKind::Other(_) => Some(FrontendTimelineItem {
-                    unique_id,
-                    event_id,
-                    timeline_item_id,
-                    is_local,
-                    is_own,
-                    timestamp,
-                    abilities,
-                    data: FrontendTimelineItemData::MsgLike(FrontendMsgLikeContent {
-                        edited: false,
-                        reactions: SerializableReactions(msg_like.reactions.clone()),
-                        sender_id,
-                        sender,
-                        thread_root,
-                        thread_summary,
-                        in_reply_to_id,
-                        kind: FrontendMsgLikeKind::Unknown,
-                    }),
-                }),
-            }
+                MsgLikeKind::LiveLocation(_) => return None,
+                MsgLikeKind::Poll(poll_state) => (
+                    poll_state.is_edit(),
+                    FrontendMsgLikeKind::Poll(FrontendPollState::from(&poll_state)),
+                ),
+                MsgLikeKind::Other(_) => (false, FrontendMsgLikeKind::Unknown),
+            };
+            Some(FrontendTimelineItemData::MsgLike(FrontendMsgLikeContent {
+                edited,
+                reactions: SerializableReactions(msg_like.reactions.clone()),
+                sender_id,
+                sender,
+                thread_root: msg_like.thread_root.clone(),
+                thread_summary: msg_like
+                    .thread_summary
+                    .clone()
+                    .and_then(get_frontend_thread_summary),
+                in_reply_to_id: msg_like.in_reply_to.clone().map(|r| r.event_id),
+                link_preview: None,
+                kind,
+            }))
         }
-        TimelineItemContent::OtherState(state) => Some(FrontendTimelineItem {
-            unique_id,
-            event_id,
-            timeline_item_id,
-            is_local,
-            is_own,
-            timestamp,
-            abilities,
-            data: FrontendTimelineItemData::StateChange(FrontendStateEvent::OtherState(
-                FrontendAnyOtherStateEventContentChange::from(state.content().clone()),
+        TimelineItemContent::OtherState(state) => Some(FrontendTimelineItemData::StateChange(
+            FrontendStateEvent::OtherState(FrontendAnyOtherStateEventContentChange::from(
+                state.content().clone(),
             )),
-        }),
-        TimelineItemContent::MembershipChange(change) => Some(FrontendTimelineItem {
-            unique_id,
-            event_id,
-            timeline_item_id,
-            is_local,
-            is_own,
-            timestamp,
-            abilities,
-            data: FrontendTimelineItemData::StateChange(FrontendStateEvent::MembershipChange(
+        )),
+        TimelineItemContent::MembershipChange(change) => Some(
+            FrontendTimelineItemData::StateChange(FrontendStateEvent::MembershipChange(
                 FrontendRoomMembershipChange::from(change.clone()),
             )),
-        }),
-
-        TimelineItemContent::ProfileChange(change) => Some(FrontendTimelineItem {
-            unique_id,
-            event_id,
-            timeline_item_id,
-            is_local,
-            is_own,
-            timestamp,
-            abilities,
-            data: FrontendTimelineItemData::StateChange(FrontendStateEvent::ProfileChange(
-                FrontendMemberProfileChange::from(change.clone()),
-            )),
-        }),
-
+        ),
+        TimelineItemContent::ProfileChange(change) => Some(FrontendTimelineItemData::StateChange(
+            FrontendStateEvent::ProfileChange(FrontendMemberProfileChange::from(change.clone())),
+        )),
         TimelineItemContent::RtcNotification { .. } | TimelineItemContent::CallInvite => {
-            Some(FrontendTimelineItem {
-                unique_id,
-                event_id,
-                timeline_item_id,
-                is_local,
-                is_own,
-                timestamp,
-                abilities,
-                data: FrontendTimelineItemData::Call,
-            })
+            Some(FrontendTimelineItemData::Call)
         }
-
         TimelineItemContent::FailedToParseMessageLike {
             event_type: _,
             error,
-        } => Some(FrontendTimelineItem {
-            unique_id,
-            event_id: None,
-            timeline_item_id,
-            data: FrontendTimelineItemData::Error(FrontendTimelineErrorItem {
-                error: error.to_string(),
-            }),
-            is_local: true,
-            is_own: true,
-            timestamp: None,
-            abilities,
-        }),
-
-        TimelineItemContent::FailedToParseState {
+        }
+        | TimelineItemContent::FailedToParseState {
             state_key: _,
             event_type: _,
             error,
-        } => Some(FrontendTimelineItem {
-            unique_id,
-            event_id: None,
-            timeline_item_id,
-            data: FrontendTimelineItemData::Error(FrontendTimelineErrorItem {
-                error: error.to_string(),
-            }),
-            is_local: true,
-            is_own: true,
-            timestamp: None,
-            abilities,
-        }),
+        } => Some(FrontendTimelineItemData::Error(FrontendTimelineErrorItem {
+            error: error.to_string(),
+        })),
     }
 }
@@ -1,6 +1,9 @@
 pub(crate) mod events_dto;
 pub(crate) mod msg_like;
+pub(crate) mod poll;
+pub(crate) mod send_state;
+pub(crate) mod shield;
 pub(crate) mod state_event;
-pub(super) mod thread_summary;
+pub(crate) mod thread_summary;
 pub(crate) mod timeline_item_id;
 pub(crate) mod virtual_event;
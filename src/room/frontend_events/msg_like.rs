@@ -8,17 +8,25 @@ use matrix_sdk::ruma::{
             AudioMessageEventContent, EmoteMessageEventContent, FileMessageEventContent,
             ImageMessageEventContent, KeyVerificationRequestEventContent,
             LocationMessageEventContent, NoticeMessageEventContent,
-            ServerNoticeMessageEventContent, TextMessageEventContent, VideoMessageEventContent,
+            ServerNoticeMessageEventContent, TextMessageEventContent, UnstableAmplitude,
+            VideoMessageEventContent,
         },
         sticker::{StickerEventContent, StickerMediaSource},
     },
 };
-use matrix_sdk_ui::timeline::{ReactionInfo, ReactionStatus, ReactionsByKeyBySender};
+use matrix_sdk_base::crypto::types::events::UtdCause;
+use matrix_sdk_ui::timeline::{
+    EncryptedMessage, ReactionInfo, ReactionStatus, ReactionsByKeyBySender,
+};
 use serde::{Serialize, Serializer};
 
-use crate::room::frontend_events::thread_summary::FrontendThreadSummary;
+use crate::room::{
+    frontend_events::{poll::FrontendPollState, thread_summary::FrontendThreadSummary},
+    link_preview::FrontendLinkPreview,
+};
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(
     rename_all = "camelCase",
     rename_all_fields = "camelCase",
@@ -26,47 +34,60 @@ use crate::room::frontend_events::thread_summary::FrontendThreadSummary;
     content = "body"
 )]
 pub enum FrontendMsgLikeKind {
-    /// An audio message.
-    Audio(AudioMessageEventContent),
+    /// An audio message, or a voice message.
+    Audio(
+        #[cfg_attr(feature = "ts-rs", ts(as = "MatrixAudioMessageContent"))]
+        FrontendAudioMessageEventContent,
+    ),
 
     /// An emote message.
-    Emote(EmoteMessageEventContent),
+    Emote(#[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))] EmoteMessageEventContent),
 
     /// A file message.
-    File(FileMessageEventContent),
+    File(#[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))] FileMessageEventContent),
 
     /// An image message.
-    Image(ImageMessageEventContent),
+    Image(#[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))] ImageMessageEventContent),
 
     /// A location message.
-    Location(LocationMessageEventContent),
+    Location(
+        #[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))] LocationMessageEventContent,
+    ),
 
     /// A notice message.
-    Notice(NoticeMessageEventContent),
+    Notice(
+        #[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))] NoticeMessageEventContent,
+    ),
 
     /// A server notice message.
-    ServerNotice(ServerNoticeMessageEventContent),
+    ServerNotice(
+        #[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))]
+        ServerNoticeMessageEventContent,
+    ),
 
     /// A text message.
-    Text(TextMessageEventContent),
+    Text(#[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))] TextMessageEventContent),
 
     /// A video message.
-    Video(VideoMessageEventContent),
+    Video(#[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))] VideoMessageEventContent),
 
     /// A request to initiate a key verification.
-    VerificationRequest(KeyVerificationRequestEventContent),
+    VerificationRequest(
+        #[cfg_attr(feature = "ts-rs", ts(as = "MatrixMessageContent"))]
+        KeyVerificationRequestEventContent,
+    ),
 
     /// An `m.sticker` event.
     Sticker(Box<FrontendStickerEventContent>),
 
-    /// An `m.poll.start` event.
-    Poll, //(PollState), // Todo: implement poll state display
+    /// An `m.poll.start` event, with the responses received so far.
+    Poll(FrontendPollState),
 
     /// A redacted message.
     Redacted,
 
     /// An `m.room.encrypted` event that could not be decrypted.
-    UnableToDecrypt,
+    UnableToDecrypt(FrontendUnableToDecryptInfo),
 
     /// An unknown type of message
     Unknown,
@@ -76,6 +97,7 @@ pub enum FrontendMsgLikeKind {
 /// different room message types with their respective reactions and thread
 /// information.
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct FrontendMsgLikeContent {
     #[serde(flatten)]
@@ -83,11 +105,16 @@ pub struct FrontendMsgLikeContent {
     /// Map of user reactions to this message
     pub reactions: SerializableReactions,
     /// Event ID of the thread root, if this is a threaded message.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub thread_root: Option<OwnedEventId>,
     // /// Information about the thread this message is the root of, if any.
     pub thread_summary: Option<FrontendThreadSummary>,
     /// The event's id this message is replying to, if any.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub in_reply_to_id: Option<OwnedEventId>,
+    /// The preview of the first link of the message, once fetched,
+    /// if URL previews are enabled in the room.
+    pub link_preview: Option<FrontendLinkPreview>,
     /// Wether the event has been edited at least once
     pub edited: bool,
     /// Sender display name (could be none if not resolved yet)
@@ -96,9 +123,165 @@ pub struct FrontendMsgLikeContent {
     pub sender_id: String,
 }
 
+/// The shape of the message contents serialized by ruma, as defined by the Matrix spec.
+/// Only the fields used to display a message are typed.
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[ts(optional_fields)]
+#[allow(dead_code)]
+pub(crate) struct MatrixMessageContent {
+    body: String,
+    format: Option<String>,
+    formatted_body: Option<String>,
+    filename: Option<String>,
+    url: Option<String>,
+    file: Option<serde_json::Value>,
+    info: Option<serde_json::Value>,
+    geo_uri: Option<String>,
+    msgtype: Option<String>,
+}
+
+/// The content of an audio message, with the voice message details normalized.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendAudioMessageEventContent {
+    #[serde(flatten)]
+    content: AudioMessageEventContent,
+    /// Whether this is a recorded voice message (MSC3245), rather than an audio file.
+    is_voice: bool,
+    /// The waveform of the audio, with values between 0 and 1.
+    waveform: Option<Vec<f32>>,
+    /// The duration of the audio in milliseconds.
+    duration: Option<u64>,
+}
+
+/// The shape of a serialized [`FrontendAudioMessageEventContent`].
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[ts(rename_all = "camelCase")]
+#[allow(dead_code)]
+pub(crate) struct MatrixAudioMessageContent {
+    #[ts(flatten)]
+    content: MatrixMessageContent,
+    is_voice: bool,
+    waveform: Option<Vec<f32>>,
+    #[ts(type = "number | null")]
+    duration: Option<u64>,
+}
+
+impl From<AudioMessageEventContent> for FrontendAudioMessageEventContent {
+    fn from(content: AudioMessageEventContent) -> Self {
+        let waveform = content
+            .audio
+            .as_ref()
+            .filter(|audio| !audio.waveform.is_empty())
+            .map(|audio| {
+                audio
+                    .waveform
+                    .iter()
+                    .map(|amplitude| {
+                        (u64::from(amplitude.get()) as f32 / UnstableAmplitude::MAX as f32).min(1.0)
+                    })
+                    .collect()
+            });
+        let duration = content
+            .audio
+            .as_ref()
+            .map(|audio| audio.duration)
+            .or_else(|| content.info.as_ref().and_then(|info| info.duration))
+            .map(|duration| duration.as_millis() as u64);
+        Self {
+            is_voice: content.voice.is_some(),
+            waveform,
+            duration,
+            content,
+        }
+    }
+}
+
+/// Why and how an `m.room.encrypted` event could not be decrypted.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendUnableToDecryptInfo {
+    /// The most likely reason why the event could not be decrypted.
+    pub cause: FrontendUtdCause,
+    /// The ID of the Megolm session used to encrypt the event, if it was sent in a room.
+    pub session_id: Option<String>,
+}
+
+impl From<&EncryptedMessage> for FrontendUnableToDecryptInfo {
+    fn from(message: &EncryptedMessage) -> Self {
+        match message {
+            EncryptedMessage::MegolmV1AesSha2 {
+                session_id, cause, ..
+            } => Self {
+                cause: (*cause).into(),
+                session_id: Some(session_id.clone()),
+            },
+            _ => Self {
+                cause: FrontendUtdCause::Unknown,
+                session_id: None,
+            },
+        }
+    }
+}
+
+/// The reason why an event could not be decrypted, as guessed by the SDK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum FrontendUtdCause {
+    /// No known reason, most likely the keys have not been received yet.
+    Unknown,
+    /// The event was sent before we joined the room, and the keys were not shared with us.
+    SentBeforeWeJoined,
+    /// The sender was verified before, but their identity changed since.
+    VerificationViolation,
+    /// The sender device is not signed by its owner.
+    UnsignedDevice,
+    /// The sender device could not be found.
+    UnknownDevice,
+    /// The event was sent before this device was logged in, and key backup is disabled.
+    HistoricalMessageAndBackupIsDisabled,
+    /// The sender did not share the keys because this device is unverified or insecure.
+    WithheldForUnverifiedOrInsecureDevice,
+    /// The sender chose not to share the keys with us.
+    WithheldBySender,
+    /// The event was sent before this device was logged in, and this device is unverified.
+    HistoricalMessageAndDeviceIsUnverified,
+}
+
+impl From<UtdCause> for FrontendUtdCause {
+    fn from(cause: UtdCause) -> Self {
+        match cause {
+            UtdCause::Unknown => Self::Unknown,
+            UtdCause::SentBeforeWeJoined => Self::SentBeforeWeJoined,
+            UtdCause::VerificationViolation => Self::VerificationViolation,
+            UtdCause::UnsignedDevice => Self::UnsignedDevice,
+            UtdCause::UnknownDevice => Self::UnknownDevice,
+            UtdCause::HistoricalMessageAndBackupIsDisabled => {
+                Self::HistoricalMessageAndBackupIsDisabled
+            }
+            UtdCause::WithheldForUnverifiedOrInsecureDevice => {
+                Self::WithheldForUnverifiedOrInsecureDevice
+            }
+            UtdCause::WithheldBySender => Self::WithheldBySender,
+            UtdCause::HistoricalMessageAndDeviceIsUnverified => {
+                Self::HistoricalMessageAndDeviceIsUnverified
+            }
+        }
+    }
+}
+
 // Wrap ReactionsByKeyBySender, and implement Serialize on it
 
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(as = "IndexMap<String, IndexMap<String, SerializableReactionInfo>>")
+)]
 pub struct SerializableReactions(pub ReactionsByKeyBySender);
 
 impl Serialize for SerializableReactions {
@@ -125,7 +308,9 @@ impl Serialize for SerializableReactions {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 pub struct SerializableReactionInfo {
+    #[cfg_attr(feature = "ts-rs", ts(type = "number"))]
     pub timestamp: MilliSecondsSinceUnixEpoch,
     pub status: SerializableReactionStatus,
 }
@@ -154,6 +339,7 @@ impl Serialize for SerializableReactionInfo {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS), ts(rename_all = "camelCase"))]
 pub enum SerializableReactionStatus {
     LocalToLocal,
     LocalToRemote,
@@ -186,6 +372,7 @@ impl Serialize for SerializableReactionStatus {
 
 // New type pattern to add the msgtype field to serialization
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS), ts(as = "MatrixMessageContent"))]
 pub struct FrontendStickerEventContent(StickerEventContent);
 
 impl Deref for FrontendStickerEventContent {
@@ -0,0 +1,106 @@
+use matrix_sdk::ruma::{MilliSecondsSinceUnixEpoch, events::poll::start::PollKind};
+use matrix_sdk_ui::timeline::PollState;
+use serde::{Deserialize, Serialize};
+
+use crate::init::singletons::CURRENT_USER_ID;
+
+/// The state of a poll, with the responses received so far.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendPollState {
+    question: String,
+    /// For undisclosed polls, the votes should only be displayed once the poll has ended.
+    kind: FrontendPollKind,
+    #[cfg_attr(feature = "ts-rs", ts(type = "number"))]
+    max_selections: u64,
+    answers: Vec<FrontendPollAnswer>,
+    /// The IDs of the answers selected by the current user.
+    own_votes: Vec<String>,
+    /// The number of users who voted.
+    voters_count: usize,
+    ended: bool,
+    #[cfg_attr(feature = "ts-rs", ts(type = "number | null"))]
+    end_time: Option<MilliSecondsSinceUnixEpoch>,
+    /// Text representation of the poll, for clients that don't support polls.
+    fallback_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendPollAnswer {
+    id: String,
+    text: String,
+    votes_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum FrontendPollKind {
+    /// The votes are visible up until and including when the poll is closed.
+    Disclosed,
+    /// The results are revealed once the poll is closed.
+    Undisclosed,
+}
+
+impl From<FrontendPollKind> for PollKind {
+    fn from(value: FrontendPollKind) -> Self {
+        match value {
+            FrontendPollKind::Disclosed => PollKind::Disclosed,
+            FrontendPollKind::Undisclosed => PollKind::Undisclosed,
+        }
+    }
+}
+
+impl From<&PollState> for FrontendPollState {
+    fn from(poll_state: &PollState) -> Self {
+        let results = poll_state.results();
+        let own_user_id = CURRENT_USER_ID.get();
+        let mut own_votes = Vec::new();
+        let mut voters = Vec::new();
+        let answers = results
+            .answers
+            .into_iter()
+            .map(|answer| {
+                let votes = results
+                    .votes
+                    .get(&answer.id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                if own_user_id
+                    .as_ref()
+                    .is_some_and(|user_id| votes.iter().any(|voter| voter == user_id.as_str()))
+                {
+                    own_votes.push(answer.id.clone());
+                }
+                for voter in votes {
+                    if !voters.contains(voter) {
+                        voters.push(voter.clone());
+                    }
+                }
+                FrontendPollAnswer {
+                    votes_count: votes.len(),
+                    id: answer.id,
+                    text: answer.text,
+                }
+            })
+            .collect();
+
+        Self {
+            question: results.question,
+            kind: match results.kind {
+                PollKind::Disclosed => FrontendPollKind::Disclosed,
+                _ => FrontendPollKind::Undisclosed,
+            },
+            max_selections: results.max_selections,
+            answers,
+            own_votes,
+            voters_count: voters.len(),
+            ended: results.end_time.is_some(),
+            end_time: results.end_time,
+            fallback_text: poll_state.fallback_text(),
+        }
+    }
+}
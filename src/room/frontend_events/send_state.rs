@@ -0,0 +1,53 @@
+use matrix_sdk::ruma::OwnedEventId;
+use matrix_sdk_ui::timeline::EventSendState;
+use serde::Serialize;
+
+/// The send state of a local echo, i.e. an event sent by the current user
+/// that hasn't been received back from the server yet.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind",
+    content = "data"
+)]
+pub enum FrontendEventSendState {
+    /// The event is waiting in the send queue, or is being sent.
+    Sending,
+    /// The event has been sent, and will be replaced by its remote echo.
+    Sent {
+        #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+        event_id: OwnedEventId,
+    },
+    /// Sending failed, for instance because the network is unreachable. The room's send queue
+    /// is paused, and restarts automatically once the sync service is running again,
+    /// or with a `RetrySend` request.
+    FailedRecoverable { error: String },
+    /// Sending failed and won't be retried automatically. The event can be retried
+    /// with a `RetrySend` request, or dropped with a `CancelSend` request.
+    FailedUnrecoverable { error: String },
+}
+
+impl From<&EventSendState> for FrontendEventSendState {
+    fn from(send_state: &EventSendState) -> Self {
+        match send_state {
+            EventSendState::NotSentYet { .. } => Self::Sending,
+            EventSendState::Sent { event_id } => Self::Sent {
+                event_id: event_id.clone(),
+            },
+            EventSendState::SendingFailed {
+                error,
+                is_recoverable: true,
+            } => Self::FailedRecoverable {
+                error: error.to_string(),
+            },
+            EventSendState::SendingFailed {
+                error,
+                is_recoverable: false,
+            } => Self::FailedUnrecoverable {
+                error: error.to_string(),
+            },
+        }
+    }
+}
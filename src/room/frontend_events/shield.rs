@@ -0,0 +1,124 @@
+use matrix_sdk::{
+    Room, RoomMemberships,
+    deserialized_responses::{VerificationLevel, VerificationState},
+};
+use matrix_sdk_ui::timeline::{
+    EventTimelineItem, TimelineEventShieldState, TimelineEventShieldStateCode,
+};
+use serde::Serialize;
+
+/// The authenticity of an event in an encrypted room, to decorate it with a shield.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase", tag = "level")]
+pub enum FrontendShieldState {
+    /// No shield: the room is not encrypted, or the event is a local echo
+    /// or could not be decrypted.
+    #[default]
+    None,
+    /// The event was sent by a verified device of its sender.
+    Verified,
+    /// A grey shield: the event was sent by a trusted device, but its sender isn't verified,
+    /// or its authenticity cannot be guaranteed, e.g. when its keys came from the key backup.
+    Grey { code: FrontendShieldStateCode },
+    /// A red shield: the event was sent in clear, or by a device that cannot be trusted.
+    Red { code: FrontendShieldStateCode },
+}
+
+/// The reason for a grey or red shield.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum FrontendShieldStateCode {
+    /// The keys of the event were not shared by its sender directly.
+    AuthenticityNotGuaranteed,
+    /// The sending device isn't known yet.
+    UnknownDevice,
+    /// The sending device hasn't been verified by its owner.
+    UnsignedDevice,
+    /// The sender hasn't been verified by the current user.
+    UnverifiedIdentity,
+    /// The sender was previously verified but changed their identity.
+    VerificationViolation,
+    /// The sender of the event doesn't own the sending device.
+    MismatchedSender,
+    /// The event was not encrypted, in an encrypted room.
+    SentInClear,
+}
+
+impl From<TimelineEventShieldStateCode> for FrontendShieldStateCode {
+    fn from(code: TimelineEventShieldStateCode) -> Self {
+        match code {
+            TimelineEventShieldStateCode::AuthenticityNotGuaranteed => {
+                Self::AuthenticityNotGuaranteed
+            }
+            TimelineEventShieldStateCode::UnknownDevice => Self::UnknownDevice,
+            TimelineEventShieldStateCode::UnsignedDevice => Self::UnsignedDevice,
+            TimelineEventShieldStateCode::UnverifiedIdentity => Self::UnverifiedIdentity,
+            TimelineEventShieldStateCode::VerificationViolation => Self::VerificationViolation,
+            TimelineEventShieldStateCode::MismatchedSender => Self::MismatchedSender,
+            TimelineEventShieldStateCode::SentInClear => Self::SentInClear,
+        }
+    }
+}
+
+impl From<&EventTimelineItem> for FrontendShieldState {
+    fn from(event_tl_item: &EventTimelineItem) -> Self {
+        // The lax mode doesn't warn about senders the user never verified,
+        // they are shown with a grey shield instead.
+        match event_tl_item.get_shield(false) {
+            TimelineEventShieldState::Red { code } => Self::Red { code: code.into() },
+            TimelineEventShieldState::Grey { code } => Self::Grey { code: code.into() },
+            TimelineEventShieldState::None => match event_tl_item
+                .encryption_info()
+                .map(|info| &info.verification_state)
+            {
+                Some(VerificationState::Verified) => Self::Verified,
+                Some(VerificationState::Unverified(VerificationLevel::UnverifiedIdentity)) => {
+                    Self::Grey {
+                        code: FrontendShieldStateCode::UnverifiedIdentity,
+                    }
+                }
+                _ => Self::None,
+            },
+        }
+    }
+}
+
+/// Computes the shield of a whole room from its encryption state
+/// and the identities of its joined members:
+/// * no shield if the room is not encrypted,
+/// * red if any member changed their identity after being verified,
+/// * verified if every other member is verified,
+/// * grey otherwise.
+pub(crate) async fn room_shield(room: &Room) -> FrontendShieldState {
+    if !room.encryption_state().is_encrypted() {
+        return FrontendShieldState::None;
+    }
+    let Ok(members) = room.members_no_sync(RoomMemberships::JOIN).await else {
+        return FrontendShieldState::None;
+    };
+    let encryption = room.client().encryption();
+    let mut all_verified = true;
+    for member in members
+        .iter()
+        .filter(|member| member.user_id() != room.own_user_id())
+    {
+        match encryption.get_user_identity(member.user_id()).await {
+            Ok(Some(identity)) if identity.has_verification_violation() => {
+                return FrontendShieldState::Red {
+                    code: FrontendShieldStateCode::VerificationViolation,
+                };
+            }
+            Ok(Some(identity)) if identity.is_verified() => {}
+            _ => all_verified = false,
+        }
+    }
+    if all_verified {
+        FrontendShieldState::Verified
+    } else {
+        FrontendShieldState::Grey {
+            code: FrontendShieldStateCode::UnverifiedIdentity,
+        }
+    }
+}
@@ -35,6 +35,7 @@ use serde::Serialize;
 use serde::Serializer;
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(
     rename_all = "camelCase",
     rename_all_fields = "camelCase",
@@ -126,7 +127,9 @@ where
 //
 
 // Newtype for AnyOtherStateEventContentChange
+// The state event contents are serialized by ruma, as defined by the Matrix spec.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS), ts(as = "serde_json::Value"))]
 pub struct FrontendAnyOtherStateEventContentChange(AnyOtherStateEventContentChange);
 
 impl Deref for FrontendAnyOtherStateEventContentChange {
@@ -300,6 +303,13 @@ impl Serialize for FrontendAnyOtherStateEventContentChange {
 //
 // Newtype for MembershipChange to add Serialize
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(
+        type = r#""None" | "Error" | "Joined" | "Left" | "Banned" | "Unbanned" | "Kicked" | "Invited" | "KickedAndBanned" | "InvitationAccepted" | "InvitationRejected" | "InvitationRevoked" | "Knocked" | "KnockAccepted" | "KnockRetracted" | "KnockDenied" | "NotImplemented""#
+    )
+)]
 pub struct FrontendMembershipChange(MembershipChange);
 
 impl Deref for FrontendMembershipChange {
@@ -346,6 +356,11 @@ impl Serialize for FrontendMembershipChange {
 
 // Newtype for RoomMembershipChange
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(as = "RoomMembershipChangeBinding")
+)]
 pub struct FrontendRoomMembershipChange(RoomMembershipChange);
 
 impl Deref for FrontendRoomMembershipChange {
@@ -393,6 +408,16 @@ impl Serialize for FrontendRoomMembershipChange {
     }
 }
 
+/// The shape of a serialized [`FrontendRoomMembershipChange`].
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[allow(dead_code)]
+struct RoomMembershipChangeBinding {
+    user_id: String,
+    content: serde_json::Value,
+    change: Option<FrontendMembershipChange>,
+}
+
 // Helper methods for easier usage
 impl FrontendRoomMembershipChange {
     pub fn _new(room_membership_change: RoomMembershipChange) -> Self {
@@ -467,6 +492,11 @@ where
 
 // Newtype for MemberProfileChange
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(as = "MemberProfileChangeBinding")
+)]
 pub struct FrontendMemberProfileChange(MemberProfileChange);
 
 impl Deref for FrontendMemberProfileChange {
@@ -517,6 +547,25 @@ impl Serialize for FrontendMemberProfileChange {
     }
 }
 
+/// The shape of a serialized [`FrontendMemberProfileChange`].
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[allow(dead_code)]
+struct MemberProfileChangeBinding {
+    user_id: String,
+    displayname_change: Option<ProfileChangeBinding>,
+    avatar_url_change: Option<ProfileChangeBinding>,
+}
+
+#[cfg(feature = "ts-rs")]
+#[derive(ts_rs::TS)]
+#[ts(rename = "ProfileChange")]
+#[allow(dead_code)]
+struct ProfileChangeBinding {
+    old: Option<String>,
+    new: Option<String>,
+}
+
 // Helper methods for easier usage
 impl FrontendMemberProfileChange {
     pub fn _new(member_profile_change: MemberProfileChange) -> Self {
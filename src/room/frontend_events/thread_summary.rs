@@ -1,5 +1,9 @@
-use matrix_sdk::ruma::{OwnedEventId, OwnedUserId};
-use matrix_sdk_ui::timeline::ThreadSummary;
+use matrix_sdk::{
+    Room,
+    deserialized_responses::TimelineEvent,
+    ruma::{OwnedEventId, OwnedUserId},
+};
+use matrix_sdk_ui::timeline::{ThreadSummary, TimelineItemContent};
 use serde::Serialize;
 
 use crate::events::{
@@ -26,13 +30,75 @@ pub fn get_frontend_thread_summary(thread_summary: ThreadSummary) -> Option<Fron
     }
 }
 
+/// Returns the thread summary of a thread root returned by the `/threads` endpoint,
+/// built from the latest event bundled with it.
+pub(crate) async fn get_thread_root_summary(
+    room: &Room,
+    root: &TimelineEvent,
+) -> Option<FrontendThreadSummary> {
+    let num_replies = root.thread_summary.summary()?.num_replies;
+    let latest_event = root.bundled_latest_thread_event.clone()?;
+    let (event_formatted_summary, sender_id) = preview_of_event(room, *latest_event).await?;
+    Some(FrontendThreadSummary::new(
+        event_formatted_summary,
+        sender_id,
+        num_replies,
+    ))
+}
+
+/// Returns the formatted text preview of the given event, and its sender.
+pub(crate) async fn preview_of_event(
+    room: &Room,
+    event: TimelineEvent,
+) -> Option<(String, OwnedUserId)> {
+    let sender_id = event.sender()?;
+    let sender_username = room
+        .get_member_no_sync(&sender_id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|member| member.display_name().map(ToOwned::to_owned))
+        .unwrap_or_else(|| sender_id.to_string());
+    let content = TimelineItemContent::from_event(room, event).await?;
+    let preview = text_preview_of_timeline_item(&content, &sender_username)
+        .format_with(&sender_username, true);
+    Some((preview, sender_id))
+}
+
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 /// The mapped thread summary containing the latest item in this thread + additionnal infos.
 pub struct FrontendThreadSummary {
     event_formatted_summary: String,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     sender_id: OwnedUserId,
     num_replies: u32,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     private_read_receipt_event_id: Option<OwnedEventId>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     public_read_receipt_event_id: Option<OwnedEventId>,
 }
+
+impl FrontendThreadSummary {
+    pub(crate) fn new(
+        event_formatted_summary: String,
+        sender_id: OwnedUserId,
+        num_replies: u32,
+    ) -> Self {
+        Self {
+            event_formatted_summary,
+            sender_id,
+            num_replies,
+            private_read_receipt_event_id: None,
+            public_read_receipt_event_id: None,
+        }
+    }
+
+    /// Sets the given reply as the latest event of this thread.
+    pub(crate) fn add_reply(&mut self, event_formatted_summary: String, sender_id: OwnedUserId) {
+        self.event_formatted_summary = event_formatted_summary;
+        self.sender_id = sender_id;
+        self.num_replies += 1;
+    }
+}
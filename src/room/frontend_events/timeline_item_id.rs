@@ -48,6 +48,40 @@ impl Serialize for FrontendTimelineEventItemId {
     }
 }
 
+// Implemented by hand, since the `FrontendTimelineItem` flattens it.
+#[cfg(feature = "ts-rs")]
+impl ts_rs::TS for FrontendTimelineEventItemId {
+    type WithoutGenerics = Self;
+    type OptionInnerType = Self;
+
+    fn name() -> String {
+        "FrontendTimelineEventItemId".to_owned()
+    }
+
+    // The `isLocal` flag is only read when deserializing, it is serialized
+    // by the flattening `FrontendTimelineItem` itself.
+    fn inline() -> String {
+        "{ timelineItemId: string, isLocal: boolean }".to_owned()
+    }
+
+    fn inline_flattened() -> String {
+        "{ timelineItemId: string }".to_owned()
+    }
+
+    fn decl() -> String {
+        format!("type {} = {};", Self::name(), Self::inline())
+    }
+
+    fn decl_concrete() -> String {
+        Self::decl()
+    }
+
+    // Without an output path, the type would not be declared in the exported bindings.
+    fn output_path() -> Option<std::path::PathBuf> {
+        Some(std::path::PathBuf::from("FrontendTimelineEventItemId.ts"))
+    }
+}
+
 impl<'de> Deserialize<'de> for FrontendTimelineEventItemId {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
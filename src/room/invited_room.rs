@@ -6,22 +6,28 @@ use serde::Serialize;
 use crate::models::room_display_name::FrontendRoomDisplayName;
 
 #[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct InvitedRoomInfo {
     /// The matrix ID of this room.
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub room_id: OwnedRoomId,
     /// The displayable name of this room, if known.
     pub room_name: FrontendRoomDisplayName,
     /// The canonical alias for this room, if any.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub canonical_alias: Option<OwnedRoomAliasId>,
     /// The alternative aliases for this room, if any.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     pub alt_aliases: Vec<OwnedRoomAliasId>,
     /// The avatar for this room: either an array of bytes holding the avatar image
     /// or a string holding the first Unicode character of the room name.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub room_avatar: Option<OwnedMxcUri>,
     /// Info about the user who invited us to this room, if available.
     pub inviter_info: Option<InviterInfo>,
     /// The timestamp and Html text content of the latest message in this room.
+    #[cfg_attr(feature = "ts-rs", ts(type = "[number, string] | null"))]
     pub latest: Option<(MilliSecondsSinceUnixEpoch, String)>,
     /// The state of this how this invite is being handled by the client backend
     /// and what should be shown in the UI.
@@ -32,10 +38,13 @@ pub struct InvitedRoomInfo {
 
 /// Info about the user who invited us to a room.
 #[derive(Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct InviterInfo {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub user_id: OwnedUserId,
     pub display_name: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub avatar: Option<OwnedMxcUri>,
 }
 impl std::fmt::Debug for InviterInfo {
@@ -50,19 +59,24 @@ impl std::fmt::Debug for InviterInfo {
 
 /// The state of a pending invite.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum InviteState {
     /// Waiting for the user to accept or decline the invite.
     #[default]
     WaitingOnUserInput,
     /// Waiting for the server to respond to the user's "join room" action.
+    #[cfg_attr(feature = "ts-rs", ts(rename = "_WaitingForJoinResult"))]
     _WaitingForJoinResult,
     /// Waiting for the server to respond to the user's "leave room" action.
+    #[cfg_attr(feature = "ts-rs", ts(rename = "_WaitingForLeaveResult"))]
     _WaitingForLeaveResult,
     /// The invite was accepted and the room was successfully joined.
     /// We're now waiting for our client to receive the joined room from the homeserver.
+    #[cfg_attr(feature = "ts-rs", ts(rename = "_WaitingForJoinedRoom"))]
     _WaitingForJoinedRoom,
     /// The invite was declined and the room was successfully left.
     /// This should result in the InviteScreen being closed.
+    #[cfg_attr(feature = "ts-rs", ts(rename = "_RoomLeft"))]
     _RoomLeft,
 }
@@ -15,23 +15,21 @@ use crate::{
     init::singletons::{UIUpdateMessage, broadcast_event},
     room::{
         invited_room::{InvitedRoomInfo, InviterInfo},
+        link_preview::{load_url_previews_setting, reload_url_previews_setting},
         rooms_list::{JoinedRoomInfo, RoomsListUpdate, enqueue_rooms_list_update},
     },
     user::user_power_level::UserPowerLevels,
 };
 use matrix_sdk::{
-    RoomDisplayName, RoomHero, RoomState,
+    EncryptionState, RoomDisplayName, RoomHero, RoomState,
     event_handler::EventHandlerDropGuard,
-    ruma::{
-        MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedMxcUri, OwnedRoomId, OwnedUserId,
-        events::tag::Tags,
-    },
+    ruma::{MilliSecondsSinceUnixEpoch, OwnedMxcUri, OwnedRoomId, OwnedUserId, events::tag::Tags},
 };
 use matrix_sdk_ui::{
     RoomListService, Timeline,
     timeline::{RoomExt, TimelineFocus},
 };
-use tokio::{runtime::Handle, sync::watch};
+use tokio::{runtime::Handle, sync::watch, task::JoinHandle};
 use tracing::{debug, error, info, warn};
 
 /// Backend-specific details about a joined room that our client currently knows about.
@@ -40,24 +38,29 @@ pub struct JoinedRoomDetails {
     room_id: OwnedRoomId,
     /// Details about the main timeline for this room.
     pub(crate) main_timeline: PerTimelineDetails,
-    /// Thread-focused timelines for this room, keyed by thread root event ID.
-    pub(crate) thread_timelines: HashMap<OwnedEventId, PerTimelineDetails>,
-    /// The set of thread timelines currently being created, to avoid duplicate in-flight work.
-    pub(crate) pending_thread_timelines: HashSet<OwnedEventId>,
+    /// Thread-focused and event-focused timelines for this room, keyed by their kind.
+    pub(crate) focused_timelines: HashMap<TimelineKind, PerTimelineDetails>,
+    /// The set of focused timelines currently being created, to avoid duplicate in-flight work.
+    pub(crate) pending_focused_timelines: HashSet<TimelineKind>,
     /// A drop guard for the event handler that represents a subscription to typing notices for this room.
     pub(crate) typing_notice_subscriber: Option<EventHandlerDropGuard>,
-    /// A drop guard for the event handler that represents a subscription to pinned events for this room.
-    pinned_events_subscriber: Option<EventHandlerDropGuard>,
+    /// The task that keeps the pinned events of this room up to date, while the room is displayed.
+    pub(crate) pinned_events_subscriber: Option<JoinHandle<()>>,
+    /// A drop guard for the event handler that keeps the threads list of this room up to date.
+    pub(crate) thread_replies_subscriber: Option<EventHandlerDropGuard>,
 }
 impl Drop for JoinedRoomDetails {
     fn drop(&mut self) {
         debug!("Dropping JoinedRoomDetails for room {}", self.room_id);
         self.main_timeline.timeline_subscriber_handler_task.abort();
-        for thread_timeline in self.thread_timelines.values() {
-            thread_timeline.timeline_subscriber_handler_task.abort();
+        for focused_timeline in self.focused_timelines.values() {
+            focused_timeline.timeline_subscriber_handler_task.abort();
         }
         drop(self.typing_notice_subscriber.take());
-        drop(self.pinned_events_subscriber.take());
+        drop(self.thread_replies_subscriber.take());
+        if let Some(task) = self.pinned_events_subscriber.take() {
+            task.abort();
+        }
     }
 }
 
@@ -76,6 +79,8 @@ pub struct RoomListServiceRoomInfo {
     is_direct: bool,
     is_marked_unread: bool,
     is_tombstoned: bool,
+    /// Whether the room is known to be unencrypted, which shows URL previews by default.
+    is_unencrypted: bool,
     tags: Option<Tags>,
     topic: Option<String>,
     user_power_levels: Option<UserPowerLevels>,
@@ -109,6 +114,7 @@ impl RoomListServiceRoomInfo {
             is_direct: is_direct.unwrap_or(false),
             is_marked_unread: room.is_marked_unread(),
             is_tombstoned: room.is_tombstoned(),
+            is_unencrypted: matches!(room.encryption_state(), EncryptionState::NotEncrypted),
             tags: tags.ok().flatten(),
             topic: room.topic(),
             user_power_levels,
@@ -246,11 +252,21 @@ pub async fn add_new_room(
         timeline.clone(),
         timeline_update_sender.clone(),
         request_receiver,
-        None,
+        TimelineKind::MainRoom {
+            room_id: new_room.room_id.clone(),
+        },
     ));
 
     let latest_event = new_room.room.latest_event().await;
     let latest = get_latest_event_details(latest_event);
+    let draft = new_room
+        .room
+        .load_composer_draft(None)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|draft| draft_preview(&draft.plain_text));
+    load_url_previews_setting(&new_room.room).await;
 
     info!(
         "Adding new joined room {}, name: {:?}",
@@ -267,10 +283,11 @@ pub async fn add_new_room(
                 timeline_update_sender,
                 timeline_subscriber_handler_task,
             },
-            thread_timelines: HashMap::new(),
-            pending_thread_timelines: HashSet::new(),
+            focused_timelines: HashMap::new(),
+            pending_focused_timelines: HashSet::new(),
             typing_notice_subscriber: None,
             pinned_events_subscriber: None,
+            thread_replies_subscriber: None,
         },
     );
     // We need to add the room to the `ALL_JOINED_ROOMS` list before we can
@@ -279,6 +296,7 @@ pub async fn add_new_room(
     enqueue_rooms_list_update(RoomsListUpdate::AddJoinedRoom(JoinedRoomInfo {
         room_id: new_room.room_id.clone(),
         latest,
+        draft,
         tags: new_room.tags.clone().unwrap_or_default(),
         topic: new_room.topic.clone(),
         num_unread_messages: new_room.num_unread_messages,
@@ -460,6 +478,10 @@ pub async fn update_room(
                 });
             }
 
+            if old_room.is_unencrypted != new_room.is_unencrypted {
+                reload_url_previews_setting(&new_room.room).await;
+            }
+
             let mut __timeline_update_sender_opt = None;
             let mut get_timeline_update_sender = |room_id| {
                 if __timeline_update_sender_opt.is_none()
@@ -629,10 +651,7 @@ where
 
     let details = match kind {
         TimelineKind::MainRoom { .. } => Some(&mut room_info.main_timeline),
-        TimelineKind::Thread {
-            thread_root_event_id,
-            ..
-        } => room_info.thread_timelines.get_mut(thread_root_event_id),
+        _ => room_info.focused_timelines.get_mut(kind),
     }?;
 
     Some(f(details))
@@ -654,3 +673,19 @@ pub(crate) fn get_timeline_and_sender(
 pub(crate) fn get_timeline(kind: &TimelineKind) -> Option<Arc<Timeline>> {
     with_per_timeline_details(kind, |details| details.timeline.clone())
 }
+
+/// Drops the given focused timeline and stops its subscriber task, if it exists.
+pub(crate) fn remove_focused_timeline(kind: &TimelineKind) {
+    let Some(lock) = try_get_room_details(kind.room_id()) else {
+        return;
+    };
+    if let Some(details) = lock.lock().unwrap().focused_timelines.remove(kind) {
+        details.timeline_subscriber_handler_task.abort();
+    }
+}
+
+/// Returns the preview of a composer draft shown in the rooms list, or `None` if it is empty.
+pub(crate) fn draft_preview(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_owned())
+}
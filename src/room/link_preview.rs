@@ -0,0 +1,283 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{LazyLock, Mutex},
+};
+
+use linkify::{LinkFinder, LinkKind};
+use matrix_sdk::{
+    Client, EncryptionState, Room,
+    ruma::{
+        OwnedMxcUri, OwnedRoomId, RoomId,
+        api::client::authenticated_media::get_media_preview,
+        events::{AnyRoomAccountDataEventContent, RoomAccountDataEventType},
+        serde::Raw,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    events::timeline::{TimelineKind, TimelineUpdate},
+    init::singletons::{UIUpdateMessage, broadcast_event},
+    models::async_requests::{MatrixRequest, submit_async_request},
+    room::{frontend_events::msg_like::FrontendMsgLikeKind, joined_room::get_timeline_and_sender},
+};
+
+/// The room account data event that stores whether URL previews are shown in an unencrypted room,
+/// as used by other clients.
+pub(crate) const URL_PREVIEWS_EVENT_TYPE: &str = "org.matrix.room.preview_urls";
+
+/// The room account data event in which Element stores its room settings, including
+/// whether URL previews are shown in an encrypted room under [`URL_PREVIEWS_E2E_SETTING`].
+pub(crate) const ROOM_SETTINGS_EVENT_TYPE: &str = "im.vector.web.settings";
+
+/// The setting that opts in to URL previews in an encrypted room.
+///
+/// The [`URL_PREVIEWS_EVENT_TYPE`] choice only applies to unencrypted rooms: it may have been
+/// made before the room was encrypted, and other clients set it for unencrypted rooms only.
+const URL_PREVIEWS_E2E_SETTING: &str = "urlPreviewsEnabled_e2e";
+
+/// The metadata of a web page linked in a message, fetched with the homeserver `/preview_url` API.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendLinkPreview {
+    url: String,
+    title: Option<String>,
+    description: Option<String>,
+    /// The preview image, uploaded to the media repository by the homeserver.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
+    image: Option<OwnedMxcUri>,
+    site_name: Option<String>,
+}
+
+/// The OpenGraph properties returned by the `/preview_url` API.
+#[derive(Deserialize)]
+struct OpenGraphData {
+    #[serde(rename = "og:title")]
+    title: Option<String>,
+    #[serde(rename = "og:description")]
+    description: Option<String>,
+    #[serde(rename = "og:image")]
+    image: Option<OwnedMxcUri>,
+    #[serde(rename = "og:site_name")]
+    site_name: Option<String>,
+}
+
+/// The content of the [`URL_PREVIEWS_EVENT_TYPE`] room account data event.
+#[derive(Deserialize)]
+struct UrlPreviewsContent {
+    disable: Option<bool>,
+}
+
+/// The link previews requested so far, by URL.
+/// A `None` value means that the preview is being fetched, or that the page has none.
+static LINK_PREVIEWS: LazyLock<Mutex<HashMap<String, Option<FrontendLinkPreview>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The rooms in which URL previews are shown.
+static URL_PREVIEWS_ENABLED_ROOMS: LazyLock<Mutex<HashSet<OwnedRoomId>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Returns the preview of the first link of the given message, if URL previews are enabled
+/// in its room.
+///
+/// If the preview was never requested, this submits a request to fetch it and returns `None`.
+/// The timeline is updated once the preview has been fetched.
+pub(crate) fn get_or_fetch_link_preview(
+    kind: &FrontendMsgLikeKind,
+    timeline_kind: &TimelineKind,
+) -> Option<FrontendLinkPreview> {
+    if !url_previews_enabled(timeline_kind.room_id()) {
+        return None;
+    }
+    let url = first_link(kind)?;
+    let mut previews = LINK_PREVIEWS.lock().unwrap();
+    if let Some(preview) = previews.get(&url) {
+        return preview.clone();
+    }
+    previews.insert(url.clone(), None);
+    submit_async_request(MatrixRequest::FetchLinkPreview {
+        timeline_kind: timeline_kind.clone(),
+        url,
+    });
+    None
+}
+
+/// Returns the first web link in the body of the given text message, if any.
+fn first_link(kind: &FrontendMsgLikeKind) -> Option<String> {
+    let body = match kind {
+        FrontendMsgLikeKind::Text(content) => &content.body,
+        FrontendMsgLikeKind::Notice(content) => &content.body,
+        FrontendMsgLikeKind::Emote(content) => &content.body,
+        _ => return None,
+    };
+    let mut finder = LinkFinder::new();
+    finder.kinds(&[LinkKind::Url]);
+    finder
+        .links(body)
+        .map(|link| link.as_str())
+        .find(|link| link.starts_with("https://") || link.starts_with("http://"))
+        .map(ToOwned::to_owned)
+}
+
+/// Fetches the preview of the given URL from the homeserver, and caches it.
+///
+/// Returns `Ok(None)` if the page has nothing to preview. On error, the URL is left
+/// without preview until the session ends, rather than being requested again on every update.
+pub(crate) async fn fetch_link_preview(
+    client: &Client,
+    url: String,
+) -> crate::Result<Option<FrontendLinkPreview>> {
+    let request = get_media_preview::v1::Request::new(url.clone());
+    let response = client
+        .send(request)
+        .await
+        .map_err(matrix_sdk::Error::from)?;
+    let preview = response
+        .data
+        .and_then(|data| serde_json::from_str::<OpenGraphData>(data.get()).ok())
+        .filter(|og| og.title.is_some() || og.description.is_some() || og.image.is_some())
+        .map(|og| FrontendLinkPreview {
+            url: url.clone(),
+            title: og.title,
+            description: og.description,
+            image: og.image,
+            site_name: og.site_name,
+        });
+    LINK_PREVIEWS.lock().unwrap().insert(url, preview.clone());
+    Ok(preview)
+}
+
+/// Returns whether URL previews are shown in the given room.
+pub(crate) fn url_previews_enabled(room_id: &RoomId) -> bool {
+    URL_PREVIEWS_ENABLED_ROOMS.lock().unwrap().contains(room_id)
+}
+
+/// Loads whether URL previews are shown in the given room from its account data.
+pub(crate) async fn load_url_previews_setting(room: &Room) {
+    let disable = room_account_data(room, URL_PREVIEWS_EVENT_TYPE)
+        .await
+        .and_then(|content| serde_json::from_value::<UrlPreviewsContent>(content).ok())
+        .and_then(|content| content.disable);
+    let e2e_enabled = room_account_data(room, ROOM_SETTINGS_EVENT_TYPE)
+        .await
+        .and_then(|content| content.get(URL_PREVIEWS_E2E_SETTING)?.as_bool());
+    // The encryption state is unknown until the room state is loaded, err on the safe side.
+    let is_unencrypted = matches!(room.encryption_state(), EncryptionState::NotEncrypted);
+    let enabled = url_previews_setting(is_unencrypted, disable, e2e_enabled);
+    set_url_previews_enabled_locally(room.room_id().to_owned(), enabled);
+}
+
+/// Whether URL previews are shown in a room, given its encryption and the choices saved
+/// in its account data.
+///
+/// Without an explicit choice of the user, previews are shown in unencrypted rooms only,
+/// since fetching them discloses the links to the homeserver.
+fn url_previews_setting(
+    is_unencrypted: bool,
+    disable: Option<bool>,
+    e2e_enabled: Option<bool>,
+) -> bool {
+    if is_unencrypted {
+        !disable.unwrap_or(false)
+    } else {
+        e2e_enabled.unwrap_or(false)
+    }
+}
+
+/// Returns the content of the given room account data event, if any.
+async fn room_account_data(room: &Room, event_type: &str) -> Option<serde_json::Value> {
+    room.account_data(RoomAccountDataEventType::from(event_type))
+        .await
+        .ok()
+        .flatten()?
+        .get_field("content")
+        .ok()
+        .flatten()
+}
+
+/// Loads whether URL previews are shown in the given room again, after its account data
+/// or its encryption changed, and refreshes its timeline if the previews were shown or hidden.
+pub(crate) async fn reload_url_previews_setting(room: &Room) {
+    let was_enabled = url_previews_enabled(room.room_id());
+    load_url_previews_setting(room).await;
+    if url_previews_enabled(room.room_id()) != was_enabled {
+        notify_link_previews_changed(room.room_id().to_owned());
+    }
+}
+
+/// Refreshes the main timeline of the given room, to show or hide its link previews.
+pub(crate) fn notify_link_previews_changed(room_id: OwnedRoomId) {
+    let timeline_kind = TimelineKind::MainRoom { room_id };
+    if let Some((_timeline, sender)) = get_timeline_and_sender(&timeline_kind)
+        && sender.send(TimelineUpdate::LinkPreviewsChanged).is_ok()
+    {
+        broadcast_event(UIUpdateMessage::RefreshUI);
+    }
+}
+
+/// Shows or hides the URL previews in the given room, and saves the choice in its account data.
+///
+/// In encrypted rooms, the choice is saved with the other room settings of Element,
+/// separately from the choice for unencrypted rooms.
+pub(crate) async fn set_url_previews_enabled(room: &Room, enabled: bool) -> crate::Result<()> {
+    let (event_type, content) = if matches!(room.encryption_state(), EncryptionState::NotEncrypted)
+    {
+        (URL_PREVIEWS_EVENT_TYPE, json!({ "disable": !enabled }))
+    } else {
+        // Keep the other settings stored in the same event.
+        let mut settings = room_account_data(room, ROOM_SETTINGS_EVENT_TYPE)
+            .await
+            .filter(serde_json::Value::is_object)
+            .unwrap_or_else(|| json!({}));
+        settings[URL_PREVIEWS_E2E_SETTING] = json!(enabled);
+        (ROOM_SETTINGS_EVENT_TYPE, settings)
+    };
+    let content = Raw::<AnyRoomAccountDataEventContent>::from_json(
+        serde_json::value::to_raw_value(&content).map_err(anyhow::Error::from)?,
+    );
+    room.set_account_data_raw(RoomAccountDataEventType::from(event_type), content)
+        .await?;
+    set_url_previews_enabled_locally(room.room_id().to_owned(), enabled);
+    Ok(())
+}
+
+fn set_url_previews_enabled_locally(room_id: OwnedRoomId, enabled: bool) {
+    let mut rooms = URL_PREVIEWS_ENABLED_ROOMS.lock().unwrap();
+    if enabled {
+        rooms.insert(room_id);
+    } else {
+        rooms.remove(&room_id);
+    }
+}
+
+/// Clears the cached link previews and URL previews settings, when the session ends.
+pub(crate) fn clear_link_previews() {
+    LINK_PREVIEWS.lock().unwrap().clear();
+    URL_PREVIEWS_ENABLED_ROOMS.lock().unwrap().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::url_previews_setting;
+
+    #[test]
+    fn unencrypted_rooms_show_previews_unless_disabled() {
+        assert!(url_previews_setting(true, None, None));
+        assert!(url_previews_setting(true, Some(false), None));
+        assert!(!url_previews_setting(true, Some(true), None));
+        // The opt-in for encrypted rooms doesn't matter.
+        assert!(!url_previews_setting(true, Some(true), Some(true)));
+    }
+
+    #[test]
+    fn encrypted_rooms_hide_previews_without_an_e2e_opt_in() {
+        assert!(!url_previews_setting(false, None, None));
+        // An opt-in saved for unencrypted rooms doesn't apply.
+        assert!(!url_previews_setting(false, Some(false), None));
+        assert!(!url_previews_setting(false, Some(false), Some(false)));
+        assert!(url_previews_setting(false, None, Some(true)));
+        assert!(url_previews_setting(false, Some(true), Some(true)));
+    }
+}
@@ -1,7 +1,10 @@
 pub(crate) mod frontend_events;
 pub(crate) mod invited_room;
 pub(crate) mod joined_room;
+pub(crate) mod link_preview;
 pub(crate) mod notifications;
 pub(crate) mod room_filter;
 pub(crate) mod room_screen;
 pub(crate) mod rooms_list;
+pub(crate) mod search;
+pub(crate) mod threads_list;
@@ -15,7 +15,11 @@ use matrix_sdk::ruma::api::client::push::{Pusher, PusherIds, PusherInit, PusherK
 use matrix_sdk::{
     Room,
     notification_settings::{NotificationSettings, RoomNotificationMode},
-    ruma::{MilliSecondsSinceUnixEpoch, events::AnySyncTimelineEvent, serde::Raw},
+    ruma::{
+        MilliSecondsSinceUnixEpoch,
+        events::{AnySyncTimelineEvent, Mentions},
+        serde::Raw,
+    },
 };
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use serde_json::{Map, json};
@@ -164,6 +168,7 @@ pub async fn register_os_desktop_notifications(client: &Client) {
 
                     match notification.event {
                         RawAnySyncOrStrippedTimelineEvent::Sync(e) => {
+                            let mentions = event_mentions(&e);
                             match parse_full_notification(e, room, true).await {
                                 Ok((summary, body, server_ts)) => {
                                     use crate::models::events::OsNotificationRequest;
@@ -172,7 +177,7 @@ pub async fn register_os_desktop_notifications(client: &Client) {
                                         return;
                                     }
 
-                                    if is_missing_mention(&body, mode, &client) {
+                                    if is_missing_mention(&body, mentions, mode, &client) {
                                         return;
                                     }
 
@@ -227,11 +232,41 @@ pub async fn global_or_room_mode(
         .await
 }
 
+/// The intentional mentions (`m.mentions`) of the given event, if it has any.
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
-fn is_missing_mention(body: &Option<String>, mode: RoomNotificationMode, client: &Client) -> bool {
-    if let Some(body) = body
-        && mode == RoomNotificationMode::MentionsAndKeywordsOnly
-    {
+fn event_mentions(event: &Raw<AnySyncTimelineEvent>) -> Option<Mentions> {
+    #[derive(serde::Deserialize)]
+    struct ContentWithMentions {
+        #[serde(rename = "m.mentions")]
+        mentions: Option<Mentions>,
+    }
+
+    event
+        .get_field::<ContentWithMentions>("content")
+        .ok()
+        .flatten()?
+        .mentions
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn is_missing_mention(
+    body: &Option<String>,
+    mentions: Option<Mentions>,
+    mode: RoomNotificationMode,
+    client: &Client,
+) -> bool {
+    if mode != RoomNotificationMode::MentionsAndKeywordsOnly {
+        return false;
+    }
+    // Events with intentional mentions must not be matched against their body.
+    if let Some(mentions) = mentions {
+        let mentioned = mentions.room
+            || client
+                .user_id()
+                .is_some_and(|user_id| mentions.user_ids.contains(user_id));
+        return !mentioned;
+    }
+    if let Some(body) = body {
         let mentioned = match client.user_id() {
             Some(user_id) => body.contains(user_id.localpart()),
             _ => false,
@@ -296,6 +331,9 @@ pub fn event_notification_body(event: &AnySyncTimelineEvent, sender_name: &str)
             use matrix_sdk::ruma::events::room::message::MessageType;
 
             let body = match message.msgtype {
+                MessageType::Audio(content) if content.voice.is_some() => {
+                    format!("{sender_name} sent a voice message.")
+                }
                 MessageType::Audio(_) => {
                     format!("{sender_name} sent an audio file.")
                 }
@@ -13,15 +13,27 @@ use tracing::{debug, error, trace, warn};
 
 use crate::{
     events::timeline::{
-        PaginationDirection, TIMELINE_STATES, TimelineEndpoints, TimelineKind, TimelineUiState,
-        TimelineUpdate, take_timeline_endpoints,
+        BackwardsPaginateUntilEventRequest, PaginationDirection, TIMELINE_STATES,
+        TimelineEndpoints, TimelineKind, TimelineUiState, TimelineUiStateDiff, TimelineUpdate,
+        take_timeline_endpoints,
     },
+    init::singletons::CURRENT_USER_ID,
     models::{
-        async_requests::{MatrixRequest, submit_async_request},
+        async_requests::{MatrixRequest, TimelineCreated, submit_async_request},
+        diff::ordered_diff,
         events::{ToastNotificationRequest, ToastNotificationVariant},
         state_updater::StateUpdater,
     },
-    room::notifications::enqueue_toast_notification,
+    room::{
+        frontend_events::{
+            events_dto::{FrontendTimelineItem, to_frontend_timeline_item},
+            msg_like::{FrontendUnableToDecryptInfo, FrontendUtdCause},
+            shield::FrontendShieldState,
+        },
+        joined_room::remove_focused_timeline,
+        notifications::enqueue_toast_notification,
+        threads_list::{FrontendThreadsList, FrontendThreadsListFilter},
+    },
     user::user_power_level::{FrontendUserPowerLevel, UserPowerLevels},
     utils::room_name_or_id,
 };
@@ -29,6 +41,7 @@ use crate::{
 /// A serializable struct representing the state of a given Matrix Room.
 /// Fields are not exposed to the adapter directly, the adapter can only serialize this struct.
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct RoomScreen {
     /// The timeline currently displayed by this RoomScreen, if any.
@@ -38,9 +51,22 @@ pub struct RoomScreen {
     /// The persistent UI-relevant states for the room that this widget is currently displaying.
     tl_state: Option<TimelineUiState>,
     /// Known members of this room
+    #[cfg_attr(feature = "ts-rs", ts(as = "BTreeMap<String, FrontendRoomMember>"))]
     members: BTreeMap<OwnedUserId, FrontendRoomMember>,
     /// The set of pinned events in this room.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     pinned_events: Vec<OwnedEventId>,
+    /// The timeline items of the pinned events loaded so far.
+    pinned_items: Vec<FrontendTimelineItem>,
+    /// The event to scroll to, once a `JumpToEvent` request has loaded it in the timeline.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
+    target_event_id: Option<OwnedEventId>,
+    /// The threads of this room loaded so far, once requested with a `PaginateThreadsList` request.
+    threads_list: Option<FrontendThreadsList>,
+    /// The events of the loaded timeline that could not be decrypted.
+    utd_counts: FrontendUtdCounts,
+    /// The shield of the room, e.g. red if a member changed their identity after being verified.
+    shield: FrontendShieldState,
     /// Whether this room has been successfully loaded (received from the homeserver).
     is_loaded: bool,
     /// Whether or not all rooms have been loaded (received from the homeserver).
@@ -48,6 +74,48 @@ pub struct RoomScreen {
     /// The state updater passed by the adapter
     #[serde(skip)]
     state_updaters: Arc<Box<dyn StateUpdater>>,
+    /// Diff mode only: whether the whole room must be sent on the next update.
+    #[serde(skip)]
+    needs_full_update: bool,
+    /// Diff mode only: whether the members changed since the previous update.
+    #[serde(skip)]
+    members_changed: bool,
+    /// Diff mode only: the timeline items known by the adapter, by `unique_id`.
+    #[serde(skip)]
+    sent_items: Vec<(String, Arc<TimelineItem>)>,
+    /// The pinned events timeline items, mapped again when the power levels change.
+    #[serde(skip)]
+    pinned_timeline_items: Vector<Arc<TimelineItem>>,
+    /// Notified once the thread or event-focused timeline to show has been created.
+    #[serde(skip)]
+    pending_timeline_creation: Option<oneshot::Receiver<TimelineCreated>>,
+}
+
+/// The changes of the [`RoomScreen`] since the previous update, in diff mode.
+/// Timeline items are patched by `unique_id`, the members are only sent when they changed,
+/// and the other fields are always sent in full.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct RoomScreenDiff<'a> {
+    timeline_kind: &'a Option<TimelineKind>,
+    room_name: &'a str,
+    tl_state: Option<TimelineUiStateDiff<'a>>,
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(as = "Option<BTreeMap<String, FrontendRoomMember>>")
+    )]
+    members: Option<&'a BTreeMap<OwnedUserId, FrontendRoomMember>>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
+    pinned_events: &'a [OwnedEventId],
+    pinned_items: &'a [FrontendTimelineItem],
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
+    target_event_id: &'a Option<OwnedEventId>,
+    threads_list: &'a Option<FrontendThreadsList>,
+    utd_counts: &'a FrontendUtdCounts,
+    shield: FrontendShieldState,
+    is_loaded: bool,
+    all_rooms_loaded: bool,
 }
 impl Drop for RoomScreen {
     fn drop(&mut self) {
@@ -74,12 +142,71 @@ impl RoomScreen {
             all_rooms_loaded: false,
             is_loaded: false,
             pinned_events: Vec::new(),
+            pinned_items: Vec::new(),
+            target_event_id: None,
+            threads_list: None,
+            utd_counts: FrontendUtdCounts::default(),
+            shield: FrontendShieldState::None,
             state_updaters: updaters,
+            needs_full_update: true,
+            members_changed: false,
+            sent_items: Vec::new(),
+            pinned_timeline_items: Vector::new(),
+            pending_timeline_creation: None,
         }
     }
 
-    fn update_frontend_state(&self) {
-        if let Err(e) = self.state_updaters.update_room(self) {
+    fn update_frontend_state(&mut self) {
+        let result = if !self.state_updaters.use_diffs() {
+            self.state_updaters.update_room(self)
+        } else if self.needs_full_update {
+            self.needs_full_update = false;
+            self.members_changed = false;
+            self.sent_items = self
+                .tl_state
+                .as_ref()
+                .map(|tl| {
+                    tl.items
+                        .iter()
+                        .filter(|item| {
+                            to_frontend_timeline_item(item, &tl.kind, &tl.user_power).is_some()
+                        })
+                        .map(|item| (item.unique_id().0.clone(), item.clone()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.state_updaters.update_room(self)
+        } else {
+            let tl_state = self.tl_state.as_ref().map(|tl| {
+                let (items, sent_items) = ordered_diff(
+                    &self.sent_items,
+                    tl.items
+                        .iter()
+                        .map(|item| (item.unique_id().0.clone(), item.clone())),
+                    Arc::ptr_eq,
+                    |item| to_frontend_timeline_item(item, &tl.kind, &tl.user_power),
+                );
+                self.sent_items = sent_items;
+                TimelineUiStateDiff { state: tl, items }
+            });
+            let members = self.members_changed.then_some(&self.members);
+            self.members_changed = false;
+            self.state_updaters.update_room_diff(&RoomScreenDiff {
+                timeline_kind: &self.timeline_kind,
+                room_name: &self.room_name,
+                tl_state,
+                members,
+                pinned_events: &self.pinned_events,
+                pinned_items: &self.pinned_items,
+                target_event_id: &self.target_event_id,
+                threads_list: &self.threads_list,
+                utd_counts: &self.utd_counts,
+                shield: self.shield,
+                is_loaded: self.is_loaded,
+                all_rooms_loaded: self.all_rooms_loaded,
+            })
+        };
+        if let Err(e) = result {
             enqueue_toast_notification(ToastNotificationRequest::new(
                 format!(
                     "Cannot update room state for room {}. Error: {e}",
@@ -93,6 +220,9 @@ impl RoomScreen {
 
     /// Processes all pending background updates to the currently-shown timeline.
     pub fn process_timeline_updates(&mut self) {
+        if self.poll_timeline_creation() {
+            return;
+        }
         let curr_first_id: usize = 0; // TODO: replace this dummy value
         let mut _typing_users = None;
 
@@ -195,6 +325,7 @@ impl RoomScreen {
                         tl.items.len()
                     );
                     if is_valid {
+                        self.target_event_id = Some(target_event_id);
                     } else {
                         // Here, the target event was not found in the current timeline,
                         // or we found it previously but it is no longer in the timeline (or has moved),
@@ -208,6 +339,19 @@ impl RoomScreen {
 
                     should_continue_backwards_pagination = false;
                 }
+                TimelineUpdate::JumpToEvent(target_event_id) => {
+                    self.target_event_id = None;
+                    let room_id = tl.kind.room_id().clone();
+                    tl.request_sender.send_modify(|requests| {
+                        requests.retain(|r| r.room_id != room_id);
+                        requests.push(BackwardsPaginateUntilEventRequest {
+                            room_id,
+                            target_event_id,
+                            starting_index: tl.items.len(),
+                            current_tl_len: tl.items.len(),
+                        });
+                    });
+                }
                 TimelineUpdate::PaginationRunning(direction) => {
                     trace!(
                         "Pagination running in room {} in {direction} direction",
@@ -215,7 +359,7 @@ impl RoomScreen {
                     );
                     if direction == PaginationDirection::Backwards {
                         self.is_loaded = false;
-                    } else {
+                    } else if tl.fully_paginated_forwards {
                         warn!("Unexpected PaginationRunning update in the Forwards direction");
                     }
                 }
@@ -237,6 +381,9 @@ impl RoomScreen {
                         if fully_paginated {
                             self.is_loaded = true;
                         }
+                    } else if matches!(tl.kind, TimelineKind::FocusedEvent { .. }) {
+                        // Once fully paginated forwards, the frontend can switch to the main timeline.
+                        tl.fully_paginated_forwards = fully_paginated;
                     } else {
                         warn!("Unexpected PaginationIdle update in the Forwards direction");
                     }
@@ -264,6 +411,7 @@ impl RoomScreen {
                     // We clear the map before so we're sure there aren't
                     // any members at previous membership state.
                     self.members.clear();
+                    self.members_changed = true;
                     members.iter().for_each(|member| {
                         self.members.insert(
                             member.user_id().to_owned(),
@@ -280,6 +428,9 @@ impl RoomScreen {
                     });
                     debug!("{:?}", self.members);
                 }
+                TimelineUpdate::RoomShield(shield) => {
+                    self.shield = shield;
+                }
                 TimelineUpdate::_MediaFetched => {
                     trace!(
                         "Timeline::handle_event(): media fetched for room {}",
@@ -313,12 +464,75 @@ impl RoomScreen {
                 }
 
                 TimelineUpdate::UserPowerLevels(user_power_level) => {
+                    // The abilities of all the items may have changed.
+                    self.needs_full_update = true;
                     tl.user_power = user_power_level;
+                    self.pinned_items = map_pinned_items(&self.pinned_timeline_items, tl);
+                }
+
+                TimelineUpdate::LinkPreviewsChanged => {
+                    // The link previews are set when mapping the items.
+                    self.needs_full_update = true;
+                    self.pinned_items = map_pinned_items(&self.pinned_timeline_items, tl);
                 }
 
                 TimelineUpdate::OwnUserReadReceipt(receipt) => {
                     tl.latest_own_user_receipt = Some(receipt);
                 }
+
+                TimelineUpdate::PinnedEvents { event_ids, items } => {
+                    self.pinned_events = event_ids;
+                    self.pinned_items = map_pinned_items(&items, tl);
+                    self.pinned_timeline_items = items;
+                }
+
+                TimelineUpdate::ThreadsList {
+                    filter,
+                    threads,
+                    next_batch,
+                    reset,
+                } => match self.threads_list.as_mut() {
+                    Some(list) if !reset && list.filter() == filter => {
+                        list.extend(threads, next_batch)
+                    }
+                    _ => {
+                        self.threads_list =
+                            Some(FrontendThreadsList::new(filter, threads, next_batch))
+                    }
+                },
+
+                TimelineUpdate::ThreadReply {
+                    thread_root_event_id,
+                    event_formatted_summary,
+                    sender_id,
+                } => {
+                    let Some(list) = self.threads_list.as_mut() else {
+                        continue;
+                    };
+                    let is_own = CURRENT_USER_ID
+                        .get()
+                        .is_some_and(|user_id| user_id == sender_id);
+                    if !list.add_reply(&thread_root_event_id, event_formatted_summary, sender_id)
+                        && (list.filter() == FrontendThreadsListFilter::All || is_own)
+                    {
+                        // A new thread, or one that isn't loaded yet: fetch its root to list it first.
+                        submit_async_request(MatrixRequest::FetchThreadRoot {
+                            room_id: tl.kind.room_id().clone(),
+                            filter: list.filter(),
+                            thread_root_event_id,
+                        });
+                    }
+                }
+
+                TimelineUpdate::NewThread { filter, thread } => {
+                    if let Some(list) = self
+                        .threads_list
+                        .as_mut()
+                        .filter(|list| list.filter() == filter)
+                    {
+                        list.insert_first(*thread);
+                    }
+                }
             }
         }
 
@@ -332,6 +546,7 @@ impl RoomScreen {
         }
 
         if num_updates > 0 {
+            self.utd_counts = FrontendUtdCounts::from_items(&tl.items);
             debug!(
                 "Applied {} timeline updates for room {}, redrawing with {} items...",
                 num_updates,
@@ -349,7 +564,6 @@ impl RoomScreen {
             .timeline_kind
             .clone()
             .expect("BUG: Timeline::show_timeline(): no timeline_kind was set.");
-        let room_id = kind.room_id().clone();
         // just an optional sanity check
         assert!(
             self.tl_state.is_none(),
@@ -361,71 +575,119 @@ impl RoomScreen {
             let mut lock = TIMELINE_STATES.lock().unwrap();
             lock.remove(&kind)
         };
-        let (tl_state, first_time_showing_room) = if let Some(existing) = state_opt {
-            (existing, false)
-        } else {
-            // This part differs a bit from robrix. Here we wait for
-            // the thread timeline to be built before proceeding.
-            let timeline_endpoints = match take_timeline_endpoints(&kind) {
-                Some(te) => te,
-                None if let Some(thread_root) = kind.thread_root_event_id() => {
-                    let (tx, rx) = oneshot::channel();
-                    submit_async_request(MatrixRequest::CreateThreadTimeline {
-                        room_id: room_id.clone(),
-                        thread_root_event_id: thread_root.clone(),
-                        sender: tx,
-                    });
-                    match futures::executor::block_on(rx) {
-                        Ok(_) => take_timeline_endpoints(&kind).expect("msg"),
-                        Err(e) => {
-                            warn!("Timeline hasn't been created. {e}");
-                            return;
-                        }
-                    }
+        match state_opt {
+            Some(existing) => self.restore_timeline(existing, false),
+            None => match take_timeline_endpoints(&kind) {
+                Some(timeline_endpoints) => self.show_new_timeline(kind, timeline_endpoints),
+                None => self.request_timeline_creation(kind),
+            },
+        }
+    }
+
+    /// Requests the creation of the thread or event-focused timeline of the given kind.
+    ///
+    /// This doesn't wait for the timeline to be built, which can take a while for an
+    /// event-focused timeline: it is shown by `process_timeline_updates` once created.
+    fn request_timeline_creation(&mut self, kind: TimelineKind) {
+        let (tx, rx) = oneshot::channel();
+        let request = match kind {
+            TimelineKind::Thread {
+                room_id,
+                thread_root_event_id,
+            } => MatrixRequest::CreateThreadTimeline {
+                room_id,
+                thread_root_event_id,
+                sender: tx.into(),
+            },
+            TimelineKind::FocusedEvent { room_id, event_id } => {
+                MatrixRequest::CreateFocusedEventTimeline {
+                    room_id,
+                    event_id,
+                    sender: tx.into(),
                 }
-                None if !self.is_loaded && self.all_rooms_loaded => panic!(
+            }
+            TimelineKind::MainRoom { .. } if !self.is_loaded && self.all_rooms_loaded => {
+                panic!(
                     "BUG: timeline {kind} is not loaded, but its RoomScreen \
                         was not waiting for its timeline to be loaded either."
-                ),
-                None => return,
-            };
-
-            let TimelineEndpoints {
-                update_receiver,
-                _update_sender: _,
-                request_sender,
-                _successor_room: _,
-            } = timeline_endpoints;
-
-            // Start with the basic tombstone info, and fetch the full details
-            // if the room has been tombstoned.
-            // let tombstone_info = if let Some(sr) = successor_room {
-            //     submit_async_request(MatrixRequest::GetSuccessorRoomDetails {
-            //         tombstoned_room_id: room_id.clone(),
-            //     });
-            //     Some(SuccessorRoomDetails::Basic(sr))
-            // } else {
-            //     None
-            // };
-
-            let tl_state = TimelineUiState {
-                kind,
-                // Initially, we assume the user has all power levels by default.
-                // This avoids unexpectedly hiding any UI elements that should be visible to the user.
-                // This doesn't mean that the user can actually perform all actions;
-                // the power levels will be updated from the homeserver once the room is opened.
-                user_power: UserPowerLevels::all(),
-                // Room members start as None and get populated when fetched from the server
-                // We assume timelines being viewed for the first time haven't been fully paginated.
-                fully_paginated: false,
-                items: Vector::new(),
-                update_receiver,
-                request_sender,
-                scrolled_past_read_marker: false,
-                latest_own_user_receipt: None,
-            };
-            (tl_state, true)
+                )
+            }
+            TimelineKind::MainRoom { .. } => return,
         };
+        submit_async_request(request);
+        self.pending_timeline_creation = Some(rx);
+    }
+
+    /// Shows the requested thread or event-focused timeline if it has been created.
+    ///
+    /// Returns `true` while the timeline is still being created.
+    fn poll_timeline_creation(&mut self) -> bool {
+        let Some(receiver) = self.pending_timeline_creation.as_mut() else {
+            return false;
+        };
+        let result = match receiver.try_recv() {
+            Err(oneshot::error::TryRecvError::Empty) => return true,
+            result => result,
+        };
+        self.pending_timeline_creation = None;
+        let Some(kind) = self.timeline_kind.clone() else {
+            return false;
+        };
+        if result.is_err() {
+            warn!("Timeline {kind} hasn't been created.");
+            return false;
+        }
+        match take_timeline_endpoints(&kind) {
+            Some(timeline_endpoints) => self.show_new_timeline(kind, timeline_endpoints),
+            None => error!("BUG: the endpoints of the created timeline {kind} were not found."),
+        }
+        false
+    }
+
+    /// Shows a timeline that has never been shown before, from its endpoints.
+    fn show_new_timeline(&mut self, kind: TimelineKind, timeline_endpoints: TimelineEndpoints) {
+        let TimelineEndpoints {
+            update_receiver,
+            _update_sender: _,
+            request_sender,
+            _successor_room: _,
+        } = timeline_endpoints;
+
+        // Start with the basic tombstone info, and fetch the full details
+        // if the room has been tombstoned.
+        // let tombstone_info = if let Some(sr) = successor_room {
+        //     submit_async_request(MatrixRequest::GetSuccessorRoomDetails {
+        //         tombstoned_room_id: room_id.clone(),
+        //     });
+        //     Some(SuccessorRoomDetails::Basic(sr))
+        // } else {
+        //     None
+        // };
+
+        let fully_paginated_forwards = !matches!(kind, TimelineKind::FocusedEvent { .. });
+        let tl_state = TimelineUiState {
+            kind,
+            // Initially, we assume the user has all power levels by default.
+            // This avoids unexpectedly hiding any UI elements that should be visible to the user.
+            // This doesn't mean that the user can actually perform all actions;
+            // the power levels will be updated from the homeserver once the room is opened.
+            user_power: UserPowerLevels::all(),
+            // Room members start as None and get populated when fetched from the server
+            // We assume timelines being viewed for the first time haven't been fully paginated.
+            fully_paginated: false,
+            fully_paginated_forwards,
+            items: Vector::new(),
+            update_receiver,
+            request_sender,
+            scrolled_past_read_marker: false,
+            latest_own_user_receipt: None,
+        };
+        self.restore_timeline(tl_state, true);
+    }
+
+    /// Stores the given timeline state into this `RoomScreen`, and subscribes to its updates.
+    fn restore_timeline(&mut self, tl_state: TimelineUiState, first_time_showing_room: bool) {
+        let room_id = tl_state.kind.room_id().clone();
 
         // TODO: support typing notices in frontend
         // Subscribe to typing notices, but hide the typing notice view initially.
@@ -438,6 +700,16 @@ impl RoomScreen {
             timeline_kind: tl_state.kind.clone(),
             subscribe: true,
         });
+        if matches!(tl_state.kind, TimelineKind::MainRoom { .. }) {
+            submit_async_request(MatrixRequest::SubscribeToPinnedEvents {
+                room_id: room_id.clone(),
+                subscribe: true,
+            });
+            submit_async_request(MatrixRequest::SubscribeToThreadReplies {
+                room_id: room_id.clone(),
+                subscribe: true,
+            });
+        }
         // Kick off a back pagination request for this room. This is "urgent",
         // because we want to show the user some messages as soon as possible
         // when they first open the room, and there might not be any messages yet.
@@ -477,25 +749,47 @@ impl RoomScreen {
         let Some(timeline_kind) = self.timeline_kind.clone() else {
             return;
         };
-
-        self.save_state();
+        // A timeline still being created is not shown, and not kept if it is event-focused.
+        self.pending_timeline_creation = None;
+
+        // Event-focused timelines are only kept while they are shown, otherwise one would
+        // be left behind for every event the user jumped to. The others are kept for later.
+        if matches!(timeline_kind, TimelineKind::FocusedEvent { .. }) {
+            self.tl_state = None;
+            remove_focused_timeline(&timeline_kind);
+        } else {
+            self.save_state();
+        }
 
         // When closing a room view, we do the following with non-persistent states.
         // (This should be the inverse of what's done in `show_timeline()`.)
         // * Unsubscribe from typing notices, since we don't care about them
         //   when a given room isn't visible.
-        // * Unsubscribe from updates to this room's pinned events, for the same reason.
+        // * Unsubscribe from updates to this room's pinned events and threads, for the same reason.
         // * Unsubscribe from updates to our own user's read receipts, for the same reason.
         if matches!(timeline_kind, TimelineKind::MainRoom { .. }) {
             submit_async_request(MatrixRequest::SubscribeToTypingNotices {
                 room_id: timeline_kind.room_id().clone(),
                 subscribe: false,
             });
-            // submit_async_request(MatrixRequest::SubscribeToPinnedEvents {
-            //     room_id: timeline_kind.room_id().clone(),
-            //     subscribe: false,
-            // });
+            submit_async_request(MatrixRequest::SubscribeToPinnedEvents {
+                room_id: timeline_kind.room_id().clone(),
+                subscribe: false,
+            });
+            submit_async_request(MatrixRequest::SubscribeToThreadReplies {
+                room_id: timeline_kind.room_id().clone(),
+                subscribe: false,
+            });
         }
+        // The pinned events are sent again when subscribing.
+        self.pinned_events.clear();
+        self.pinned_items.clear();
+        self.pinned_timeline_items.clear();
+        self.target_event_id = None;
+        // The threads list may miss replies received while hidden, it is requested again.
+        self.threads_list = None;
+        self.utd_counts = FrontendUtdCounts::default();
+        self.shield = FrontendShieldState::None;
         submit_async_request(MatrixRequest::SubscribeToOwnUserReadReceiptsChanged {
             timeline_kind,
             subscribe: false,
@@ -555,6 +849,17 @@ impl RoomScreen {
     }
 }
 
+/// Maps the pinned events timeline items with the power levels of the given main timeline.
+fn map_pinned_items(
+    items: &Vector<Arc<TimelineItem>>,
+    tl: &TimelineUiState,
+) -> Vec<FrontendTimelineItem> {
+    items
+        .iter()
+        .filter_map(|item| to_frontend_timeline_item(item, &tl.kind, &tl.user_power))
+        .collect()
+}
+
 /// Returns info about the item in the list of `new_items` that matches the event ID
 /// of a visible item in the given `curr_items` list.
 ///
@@ -612,7 +917,34 @@ fn find_new_item_matching_current_item(
     None
 }
 
+/// The number of events of a timeline that could not be decrypted, by cause.
+#[derive(Debug, Default, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendUtdCounts {
+    total: u32,
+    by_cause: BTreeMap<FrontendUtdCause, u32>,
+}
+
+impl FrontendUtdCounts {
+    fn from_items(items: &Vector<Arc<TimelineItem>>) -> Self {
+        let mut counts = Self::default();
+        for message in items
+            .iter()
+            .filter_map(|item| item.as_event()?.content().as_unable_to_decrypt())
+        {
+            counts.total += 1;
+            *counts
+                .by_cause
+                .entry(FrontendUnableToDecryptInfo::from(message).cause)
+                .or_default() += 1;
+        }
+        counts
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct FrontendRoomMember {
     name: String,
@@ -620,12 +952,15 @@ pub struct FrontendRoomMember {
     max_power_level: FrontendUserPowerLevel,
     display_name_ambiguous: bool,
     is_ignored: bool,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     avatar: Option<OwnedMxcUri>,
     role: FrontendRoomMemberRole,
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     membership: MembershipState,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all_fields = "camelCase", rename_all = "camelCase")]
 /// Same as RoomMemberRole with Serialize
 pub enum FrontendRoomMemberRole {
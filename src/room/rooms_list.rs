@@ -25,6 +25,7 @@ use crate::{
     events::timeline::TimelineKind,
     init::singletons::{ALL_ROOMS_LOADED, UIUpdateMessage, broadcast_event},
     models::{
+        diff::DiffOp,
         events::{ToastNotificationRequest, ToastNotificationVariant},
         room_display_name::FrontendRoomDisplayName,
         state_updater::StateUpdater,
@@ -59,6 +60,9 @@ pub enum RoomsListUpdate {
     AddJoinedRoom(JoinedRoomInfo),
     /// Clear all rooms in the list of all rooms.
     ClearRooms,
+    /// Close the current active room and reset the whole list to its initial state.
+    /// This is used when the user logs out.
+    ResetAll,
     /// Update the latest event content and timestamp for the given room.
     UpdateLatestEvent {
         room_id: OwnedRoomId,
@@ -66,6 +70,11 @@ pub enum RoomsListUpdate {
         /// The Html-formatted text preview of the latest message.
         latest_message_text: String,
     },
+    /// Update the preview of the main timeline's composer draft for the given room.
+    UpdateDraft {
+        room_id: OwnedRoomId,
+        draft: Option<String>,
+    },
     /// Update the number of unread messages and mentions for the given room.
     UpdateNumUnreadMessages {
         room_id: OwnedRoomId,
@@ -129,36 +138,72 @@ pub fn enqueue_rooms_list_update(update: RoomsListUpdate) {
     broadcast_event(UIUpdateMessage::RefreshUI);
 }
 
+impl RoomsListUpdate {
+    /// The room whose info is changed by this update, if any.
+    fn room_id(&self) -> Option<&OwnedRoomId> {
+        match self {
+            RoomsListUpdate::AddInvitedRoom(room) => Some(&room.room_id),
+            RoomsListUpdate::AddJoinedRoom(room) => Some(&room.room_id),
+            RoomsListUpdate::UpdateLatestEvent { room_id, .. }
+            | RoomsListUpdate::UpdateDraft { room_id, .. }
+            | RoomsListUpdate::UpdateNumUnreadMessages { room_id, .. }
+            | RoomsListUpdate::UpdateRoomName { room_id, .. }
+            | RoomsListUpdate::UpdateTopic { room_id, .. }
+            | RoomsListUpdate::UpdateRoomAvatar { room_id, .. }
+            | RoomsListUpdate::UpdateIsDirect { room_id, .. }
+            | RoomsListUpdate::RemoveRoom { room_id, .. }
+            | RoomsListUpdate::Tags { room_id, .. }
+            | RoomsListUpdate::TombstonedRoom { room_id } => Some(room_id),
+            _ => None,
+        }
+    }
+}
+
 /// UI-related info about a joined room.
 ///
 /// This includes info needed display a preview of that room in the RoomsList
 /// and to filter the list of rooms based on the current search filter.
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct JoinedRoomInfo {
     /// The matrix ID of this room.
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub(crate) room_id: OwnedRoomId,
     /// The displayable name of this room, if known.
     pub(crate) room_name: FrontendRoomDisplayName,
     /// The number of unread messages in this room.
+    #[cfg_attr(feature = "ts-rs", ts(type = "number"))]
     pub(crate) num_unread_messages: u64,
     /// The number of unread mentions in this room.
+    #[cfg_attr(feature = "ts-rs", ts(type = "number"))]
     pub(crate) num_unread_mentions: u64,
     /// Whether the room is manually marked as unread.
     pub(crate) is_marked_unread: bool,
     /// The canonical alias for this room, if any.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub(crate) canonical_alias: Option<OwnedRoomAliasId>,
     /// The alternative aliases for this room, if any.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     pub(crate) alt_aliases: Vec<OwnedRoomAliasId>,
     /// The tags associated with this room, if any.
     /// This includes things like is_favourite, is_low_priority,
     /// whether the room is a server notice room, etc.
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(type = "{ [key in string]?: { order?: number } }")
+    )]
     pub(crate) tags: Tags,
     /// The topic of the current room
     pub(crate) topic: Option<String>,
     /// The timestamp and Html text content of the latest message in this room.
+    #[cfg_attr(feature = "ts-rs", ts(type = "[number, string] | null"))]
     pub(crate) latest: Option<(MilliSecondsSinceUnixEpoch, String)>,
+    /// The plain text of the unsent draft of the main timeline, if any.
+    /// It should be previewed as "Draft: ..." instead of the latest message.
+    pub(crate) draft: Option<String>,
     /// The avatar for this room
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub(crate) avatar: Option<OwnedMxcUri>,
     /// Whether this room has been paginated at least once.
     /// We pre-paginate visible rooms at least once in order to
@@ -170,10 +215,17 @@ pub struct JoinedRoomInfo {
     /// Whether this a DM room or not.
     pub(crate) is_direct: bool,
     /// UserId of the user if the room is direct
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub(crate) direct_user_id: Option<OwnedUserId>,
     /// Whether this room is tombstoned (shut down and replaced with a successor room).
     pub(crate) is_tombstoned: bool,
     /// Room "heroes", ~ main users of this room
+    #[cfg_attr(
+        feature = "ts-rs",
+        ts(
+            type = "Array<{ user_id: string, display_name: string | null, avatar_url: string | null }>"
+        )
+    )]
     pub(crate) heroes: Vec<RoomHero>,
 }
 
@@ -205,20 +257,23 @@ pub fn handle_rooms_loading_state(mut loading_state: Subscriber<RoomListLoadingS
 /// The struct containing all the data related to the homepage rooms list.
 /// Fields are not exposed to the adapter directly, the adapter can only serialize this struct.
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub struct RoomsList {
-    // We manually put the Record type, otherwise it generates a weird type with undefined
     /// The list of all rooms that the user has been invited to.
+    #[cfg_attr(feature = "ts-rs", ts(as = "HashMap<String, InvitedRoomInfo>"))]
     invited_rooms: HashMap<OwnedRoomId, InvitedRoomInfo>,
 
-    // We manually put the Record type, otherwise it generates a weird type with undefined
     /// The set of all joined rooms and their cached preview info.
+    #[cfg_attr(feature = "ts-rs", ts(as = "HashMap<String, JoinedRoomInfo>"))]
     all_joined_rooms: HashMap<OwnedRoomId, JoinedRoomInfo>,
 
     /// The list of all room IDs in display order, matching the order from the room list service.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     all_known_rooms_order: VecDeque<OwnedRoomId>,
 
     /// Rooms that are explicitly hidden and should never be shown in the rooms list.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     hidden_rooms: HashSet<OwnedRoomId>,
 
     /// The currently-active filter function for the list of rooms.
@@ -237,12 +292,14 @@ pub struct RoomsList {
     /// The list of invited rooms currently displayed in the UI, in order from top to bottom.
     /// This is a strict subset of the rooms present in `all_invited_rooms`, and should be determined
     /// by applying the `display_filter` to the set of `all_invited_rooms`.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     displayed_invited_rooms: Vec<OwnedRoomId>,
 
     /// The list of direct rooms currently displayed in the UI, in order from top to bottom.
     /// This is a strict subset of the rooms present in `all_joined_rooms`,
     /// and should be determined by applying the `display_filter && is_direct`
     /// to the set of `all_joined_rooms`.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     displayed_direct_rooms: Vec<OwnedRoomId>,
 
     /// The list of regular (non-direct) joined rooms currently displayed in the UI,
@@ -252,6 +309,7 @@ pub struct RoomsList {
     /// to the set of `all_joined_rooms`.
     ///
     /// **Direct rooms are excluded** from this; they are in `displayed_direct_rooms`.
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
     displayed_regular_rooms: Vec<OwnedRoomId>,
 
     /// The latest status message that should be displayed in the bottom status label.
@@ -267,9 +325,48 @@ pub struct RoomsList {
     /// The state updater passed by the adapter for this struct
     #[serde(skip)]
     state_updaters: Arc<Box<dyn StateUpdater>>,
+    /// Diff mode only: whether the whole list must be sent on the next update.
+    #[serde(skip)]
+    needs_full_update: bool,
+    /// Diff mode only: the rooms changed since the previous update.
+    #[serde(skip)]
+    changed_rooms: HashSet<OwnedRoomId>,
+    /// Diff mode only: the joined rooms known by the adapter.
+    #[serde(skip)]
+    sent_joined_rooms: HashSet<OwnedRoomId>,
+    /// Diff mode only: the invited rooms known by the adapter.
+    #[serde(skip)]
+    sent_invited_rooms: HashSet<OwnedRoomId>,
+}
+
+/// The changes of the [`RoomsList`] since the previous update, in diff mode.
+/// Rooms are patched by room ID, the other fields are lightweight and always sent in full.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct RoomsListDiff<'a> {
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<DiffOp<String, InvitedRoomInfo>>"))]
+    invited_rooms: Vec<DiffOp<&'a OwnedRoomId, &'a InvitedRoomInfo>>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<DiffOp<String, JoinedRoomInfo>>"))]
+    all_joined_rooms: Vec<DiffOp<&'a OwnedRoomId, &'a JoinedRoomInfo>>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
+    all_known_rooms_order: &'a VecDeque<OwnedRoomId>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
+    hidden_rooms: &'a HashSet<OwnedRoomId>,
+    filter_keywords: &'a str,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
+    displayed_invited_rooms: &'a [OwnedRoomId],
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
+    displayed_direct_rooms: &'a [OwnedRoomId],
+    #[cfg_attr(feature = "ts-rs", ts(as = "Vec<String>"))]
+    displayed_regular_rooms: &'a [OwnedRoomId],
+    status: &'a RoomsCollectionStatus,
+    current_active_room: &'a Option<TimelineKind>,
+    max_known_rooms: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(
     rename_all = "camelCase",
     rename_all_fields = "camelCase",
@@ -300,11 +397,51 @@ impl RoomsList {
             current_active_room_killer: None,
             max_known_rooms: None,
             state_updaters: updaters,
+            needs_full_update: true,
+            changed_rooms: HashSet::new(),
+            sent_joined_rooms: HashSet::new(),
+            sent_invited_rooms: HashSet::new(),
         }
     }
 
-    fn update_frontend_state(&self) {
-        if let Err(e) = self.state_updaters.update_rooms_list(self) {
+    fn update_frontend_state(&mut self) {
+        let result = if !self.state_updaters.use_diffs() || self.needs_full_update {
+            self.needs_full_update = false;
+            self.changed_rooms.clear();
+            self.sent_joined_rooms = self.all_joined_rooms.keys().cloned().collect();
+            self.sent_invited_rooms = self.invited_rooms.keys().cloned().collect();
+            self.state_updaters.update_rooms_list(self)
+        } else {
+            let changed_rooms = std::mem::take(&mut self.changed_rooms);
+            let mut invited_rooms = Vec::new();
+            let mut all_joined_rooms = Vec::new();
+            for room_id in &changed_rooms {
+                all_joined_rooms.extend(room_diff_op(
+                    room_id,
+                    &self.all_joined_rooms,
+                    &mut self.sent_joined_rooms,
+                ));
+                invited_rooms.extend(room_diff_op(
+                    room_id,
+                    &self.invited_rooms,
+                    &mut self.sent_invited_rooms,
+                ));
+            }
+            self.state_updaters.update_rooms_list_diff(&RoomsListDiff {
+                invited_rooms,
+                all_joined_rooms,
+                all_known_rooms_order: &self.all_known_rooms_order,
+                hidden_rooms: &self.hidden_rooms,
+                filter_keywords: &self.filter_keywords,
+                displayed_invited_rooms: &self.displayed_invited_rooms,
+                displayed_direct_rooms: &self.displayed_direct_rooms,
+                displayed_regular_rooms: &self.displayed_regular_rooms,
+                status: &self.status,
+                current_active_room: &self.current_active_room,
+                max_known_rooms: self.max_known_rooms,
+            })
+        };
+        if let Err(e) = result {
             enqueue_toast_notification(ToastNotificationRequest::new(
                 format!("Cannot update room list store. Error: {e}"),
                 None,
@@ -322,6 +459,9 @@ impl RoomsList {
             num_updates += 1;
 
             debug!("Processing update type: {update:?}");
+            if let Some(room_id) = update.room_id() {
+                self.changed_rooms.insert(room_id.clone());
+            }
 
             match update {
                 RoomsListUpdate::AddInvitedRoom(invited_room) => {
@@ -385,6 +525,13 @@ impl RoomsList {
                         warn!("Error: couldn't find room {room_id} to update latest event");
                     }
                 }
+                RoomsListUpdate::UpdateDraft { room_id, draft } => {
+                    if let Some(room) = self.all_joined_rooms.get_mut(&room_id) {
+                        room.draft = draft;
+                    } else {
+                        warn!("Error: couldn't find room {room_id} to update draft");
+                    }
+                }
                 RoomsListUpdate::UpdateNumUnreadMessages {
                     room_id,
                     is_marked_unread,
@@ -543,6 +690,7 @@ impl RoomsList {
                     self.update_status_rooms_count();
                 }
                 RoomsListUpdate::ClearRooms => {
+                    self.needs_full_update = true;
                     self.all_joined_rooms.clear();
                     self.displayed_direct_rooms.clear();
                     self.displayed_regular_rooms.clear();
@@ -550,6 +698,12 @@ impl RoomsList {
                     self.displayed_invited_rooms.clear();
                     self.update_status_rooms_count();
                 }
+                RoomsListUpdate::ResetAll => {
+                    if let Some(sender) = self.current_active_room_killer.take() {
+                        let _ = sender.send(());
+                    }
+                    *self = RoomsList::new(self.state_updaters.clone());
+                }
                 RoomsListUpdate::NotLoaded => {
                     self.status = RoomsCollectionStatus::Loading(
                         "Loading rooms (waiting for homeserver)...".to_owned(),
@@ -876,3 +1030,30 @@ impl RoomsList {
         (new_displayed_regular_rooms, new_displayed_direct_rooms)
     }
 }
+
+/// Returns the op to send for the given room, and marks it as known by the adapter.
+fn room_diff_op<'a, T>(
+    room_id: &'a OwnedRoomId,
+    rooms: &'a HashMap<OwnedRoomId, T>,
+    sent_rooms: &mut HashSet<OwnedRoomId>,
+) -> Option<DiffOp<&'a OwnedRoomId, &'a T>> {
+    match (rooms.get(room_id), sent_rooms.contains(room_id)) {
+        (Some(value), true) => Some(DiffOp::Update {
+            key: room_id,
+            value,
+        }),
+        (Some(value), false) => {
+            sent_rooms.insert(room_id.clone());
+            Some(DiffOp::Insert {
+                key: room_id,
+                index: None,
+                value,
+            })
+        }
+        (None, true) => {
+            sent_rooms.remove(room_id);
+            Some(DiffOp::Remove { key: room_id })
+        }
+        (None, false) => None,
+    }
+}
@@ -0,0 +1,179 @@
+use matrix_sdk::{
+    Client, Room,
+    cross_process_lock::MappedCrossProcessLockState,
+    deserialized_responses::TimelineEvent,
+    ruma::{
+        OwnedRoomId, UInt,
+        api::client::{
+            filter::RoomEventFilter,
+            search::search_events::v3::{Categories, Criteria, OrderBy, Request},
+        },
+        events::{
+            AnySyncMessageLikeEvent, AnySyncTimelineEvent, SyncMessageLikeEvent,
+            room::message::Relation,
+        },
+    },
+};
+use serde::Serialize;
+
+use crate::room::frontend_events::events_dto::{FrontendTimelineItem, map_standalone_event};
+
+/// The number of results returned per page by a local search.
+const LOCAL_SEARCH_PAGE_SIZE: usize = 20;
+
+/// A page of message search results.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendSearchResults {
+    /// The matching events, most recent first.
+    results: Vec<FrontendSearchResult>,
+    /// The words to highlight in the results.
+    highlights: Vec<String>,
+    /// The token to pass to get the next page of results, if any.
+    next_batch: Option<String>,
+    /// An approximation of the total number of results, if known.
+    #[cfg_attr(feature = "ts-rs", ts(type = "number | null"))]
+    count: Option<UInt>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendSearchResult {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
+    room_id: OwnedRoomId,
+    item: FrontendTimelineItem,
+}
+
+/// Searches the messages of the given rooms with the homeserver `/search` API.
+///
+/// The homeserver cannot search the content of encrypted events, see [`search_room_locally`].
+pub(crate) async fn search_on_server(
+    client: &Client,
+    room_ids: Option<Vec<OwnedRoomId>>,
+    search_term: String,
+    next_batch: Option<String>,
+) -> crate::Result<FrontendSearchResults> {
+    let mut filter = RoomEventFilter::default();
+    filter.rooms = room_ids;
+    let mut criteria = Criteria::new(search_term);
+    criteria.filter = filter;
+    criteria.order_by = Some(OrderBy::Recent);
+    let mut categories = Categories::new();
+    categories.room_events = Some(criteria);
+    let mut request = Request::new(categories);
+    request.next_batch = next_batch;
+
+    let response = client
+        .send(request)
+        .await
+        .map_err(matrix_sdk::Error::from)?;
+    let room_events = response.search_categories.room_events;
+
+    let mut results = Vec::with_capacity(room_events.results.len());
+    for raw_event in room_events.results.into_iter().filter_map(|r| r.result) {
+        let Ok(event) = raw_event.deserialize() else {
+            continue;
+        };
+        let room_id = event.room_id().to_owned();
+        let Some(room) = client.get_room(&room_id) else {
+            continue;
+        };
+        let timeline_event =
+            TimelineEvent::from_plaintext(raw_event.cast::<AnySyncTimelineEvent>());
+        if let Some(item) = map_standalone_event(&room, timeline_event).await {
+            results.push(FrontendSearchResult { room_id, item });
+        }
+    }
+
+    Ok(FrontendSearchResults {
+        results,
+        highlights: room_events.highlights,
+        next_batch: room_events.next_batch,
+        count: room_events.count,
+    })
+}
+
+/// Searches the messages of the given room among the events stored in the local event cache.
+///
+/// This is used for encrypted rooms, whose content cannot be searched by the homeserver.
+/// Only the events that were already received and decrypted by this device can be found.
+/// The `next_batch` token is the offset of the next page in the matching events.
+pub(crate) async fn search_room_locally(
+    client: &Client,
+    room: &Room,
+    search_term: String,
+    next_batch: Option<String>,
+) -> crate::Result<FrontendSearchResults> {
+    let offset = match next_batch {
+        Some(token) => token.parse::<usize>().map_err(|_| {
+            crate::Error::InvalidInput(format!("Invalid search pagination token: {token}"))
+        })?,
+        None => 0,
+    };
+    let needle = search_term.to_lowercase();
+    if needle.trim().is_empty() {
+        return Err(crate::Error::InvalidInput(
+            "The search term is empty".to_owned(),
+        ));
+    }
+
+    let events = {
+        let store = match client
+            .event_cache_store()
+            .lock()
+            .await
+            .map_err(matrix_sdk::Error::from)?
+        {
+            MappedCrossProcessLockState::Clean(store)
+            | MappedCrossProcessLockState::Dirty(store) => store,
+        };
+        store
+            .get_room_events(room.room_id(), Some("m.room.message"), None)
+            .await
+            .map_err(matrix_sdk::Error::from)?
+    };
+
+    let mut matches: Vec<TimelineEvent> = events
+        .into_iter()
+        .filter(|event| event_matches(event, &needle))
+        .collect();
+    matches.sort_by_key(|event| std::cmp::Reverse(event.timestamp()));
+    let count = matches.len();
+
+    let mut results = Vec::new();
+    for event in matches
+        .into_iter()
+        .skip(offset)
+        .take(LOCAL_SEARCH_PAGE_SIZE)
+    {
+        if let Some(item) = map_standalone_event(room, event).await {
+            results.push(FrontendSearchResult {
+                room_id: room.room_id().to_owned(),
+                item,
+            });
+        }
+    }
+
+    let next_offset = offset + LOCAL_SEARCH_PAGE_SIZE;
+    Ok(FrontendSearchResults {
+        results,
+        highlights: vec![search_term],
+        next_batch: (next_offset < count).then(|| next_offset.to_string()),
+        count: UInt::new(count as u64),
+    })
+}
+
+/// Whether the body of the given message contains the given lowercase search term.
+/// Edits are skipped, so that edited messages aren't listed twice.
+fn event_matches(event: &TimelineEvent, needle: &str) -> bool {
+    let Ok(AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::RoomMessage(
+        SyncMessageLikeEvent::Original(message),
+    ))) = event.raw().deserialize()
+    else {
+        return false;
+    };
+    !matches!(message.content.relates_to, Some(Relation::Replacement(_)))
+        && message.content.body().to_lowercase().contains(needle)
+}
@@ -3,6 +3,7 @@ use serde::{Serialize, Serializer};
 
 // Newtype for FrontendRoomTags
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS), ts(type = "{ [key in string]?: { order?: number } }"))]
 pub struct FrontendRoomTags(Tags);
 
 impl FrontendRoomTags {
@@ -0,0 +1,216 @@
+use matrix_sdk::{
+    Room,
+    deserialized_responses::TimelineEvent,
+    event_handler::{EventHandlerDropGuard, RawEvent},
+    room::ListThreadsOptions,
+    ruma::{
+        OwnedEventId, OwnedUserId,
+        api::client::threads::get_threads::v1::IncludeThreads,
+        events::room::message::{OriginalSyncRoomMessageEvent, Relation},
+        serde::Raw,
+    },
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    events::timeline::TimelineUpdate,
+    init::singletons::{UIUpdateMessage, broadcast_event},
+    room::frontend_events::{
+        events_dto::{FrontendTimelineItem, map_standalone_event},
+        thread_summary::{FrontendThreadSummary, get_thread_root_summary, preview_of_event},
+    },
+};
+
+/// Which threads of a room to list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum FrontendThreadsListFilter {
+    /// All the threads of the room.
+    #[default]
+    All,
+    /// Only the threads the current user has participated in.
+    Participated,
+}
+
+impl From<FrontendThreadsListFilter> for IncludeThreads {
+    fn from(filter: FrontendThreadsListFilter) -> Self {
+        match filter {
+            FrontendThreadsListFilter::All => IncludeThreads::All,
+            FrontendThreadsListFilter::Participated => IncludeThreads::Participated,
+        }
+    }
+}
+
+/// The threads of a room loaded so far, most recently active first.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendThreadsList {
+    filter: FrontendThreadsListFilter,
+    threads: Vec<FrontendThreadRoot>,
+    /// The token to pass to get the next page of threads, if any.
+    next_batch: Option<String>,
+}
+
+impl FrontendThreadsList {
+    pub(crate) fn new(
+        filter: FrontendThreadsListFilter,
+        threads: Vec<FrontendThreadRoot>,
+        next_batch: Option<String>,
+    ) -> Self {
+        Self {
+            filter,
+            threads,
+            next_batch,
+        }
+    }
+
+    pub(crate) fn filter(&self) -> FrontendThreadsListFilter {
+        self.filter
+    }
+
+    /// Appends the next page of threads, skipping the ones already listed.
+    pub(crate) fn extend(&mut self, threads: Vec<FrontendThreadRoot>, next_batch: Option<String>) {
+        for thread in threads {
+            if !self.threads.iter().any(|t| t.root_id == thread.root_id) {
+                self.threads.push(thread);
+            }
+        }
+        self.next_batch = next_batch;
+    }
+
+    /// Lists the given thread first, unless it is already listed.
+    pub(crate) fn insert_first(&mut self, thread: FrontendThreadRoot) {
+        if !self.threads.iter().any(|t| t.root_id == thread.root_id) {
+            self.threads.insert(0, thread);
+        }
+    }
+
+    /// Updates the summary of the given thread with a new reply, and moves it to the top
+    /// of the list. Returns `false` if the thread isn't listed yet.
+    pub(crate) fn add_reply(
+        &mut self,
+        thread_root_event_id: &OwnedEventId,
+        event_formatted_summary: String,
+        sender_id: OwnedUserId,
+    ) -> bool {
+        let Some(index) = self
+            .threads
+            .iter()
+            .position(|t| &t.root_id == thread_root_event_id)
+        else {
+            return false;
+        };
+        let mut thread = self.threads.remove(index);
+        match thread.summary.as_mut() {
+            Some(summary) => summary.add_reply(event_formatted_summary, sender_id),
+            None => {
+                thread.summary = Some(FrontendThreadSummary::new(
+                    event_formatted_summary,
+                    sender_id,
+                    1,
+                ))
+            }
+        }
+        self.threads.insert(0, thread);
+        true
+    }
+}
+
+/// A thread root event, with the summary of its thread.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendThreadRoot {
+    #[serde(skip)]
+    root_id: OwnedEventId,
+    root: FrontendTimelineItem,
+    summary: Option<FrontendThreadSummary>,
+}
+
+/// Fetches a page of the thread roots of the given room with the homeserver `/threads` API.
+///
+/// Returns the thread roots and the token of the next page, if any.
+pub(crate) async fn paginate_threads_list(
+    room: &Room,
+    filter: FrontendThreadsListFilter,
+    from: Option<String>,
+) -> crate::Result<(Vec<FrontendThreadRoot>, Option<String>)> {
+    let response = room
+        .list_threads(ListThreadsOptions {
+            include_threads: filter.into(),
+            from,
+            limit: None,
+        })
+        .await?;
+
+    let mut threads = Vec::with_capacity(response.chunk.len());
+    for event in response.chunk {
+        if let Some(thread) = to_thread_root(room, event).await {
+            threads.push(thread);
+        }
+    }
+    Ok((threads, response.prev_batch_token))
+}
+
+/// Fetches the root of the given thread, from the event cache or from the homeserver.
+pub(crate) async fn fetch_thread_root(
+    room: &Room,
+    thread_root_event_id: &OwnedEventId,
+) -> crate::Result<Option<FrontendThreadRoot>> {
+    let event = room.load_or_fetch_event(thread_root_event_id, None).await?;
+    Ok(to_thread_root(room, event).await)
+}
+
+async fn to_thread_root(room: &Room, event: TimelineEvent) -> Option<FrontendThreadRoot> {
+    let root_id = event.event_id()?;
+    let summary = get_thread_root_summary(room, &event).await;
+    let root = map_standalone_event(room, event).await?;
+    Some(FrontendThreadRoot {
+        root_id,
+        root,
+        summary,
+    })
+}
+
+/// Subscribes to the thread replies received in the given room.
+///
+/// Each reply is delivered to the main UI thread via [`TimelineUpdate::ThreadReply`],
+/// as long as the returned drop guard is kept alive.
+pub(crate) fn subscribe_to_thread_replies(
+    room: &Room,
+    timeline_update_sender: crossbeam_channel::Sender<TimelineUpdate>,
+) -> EventHandlerDropGuard {
+    let handle = room.add_event_handler(
+        move |ev: OriginalSyncRoomMessageEvent, room: Room, RawEvent(raw): RawEvent| {
+            let timeline_update_sender = timeline_update_sender.clone();
+            async move {
+                let Some(Relation::Thread(thread)) = ev.content.relates_to else {
+                    return;
+                };
+                let event = TimelineEvent::from_plaintext(Raw::from_json(raw));
+                let Some((event_formatted_summary, sender_id)) =
+                    preview_of_event(&room, event).await
+                else {
+                    return;
+                };
+                let update = TimelineUpdate::ThreadReply {
+                    thread_root_event_id: thread.event_id,
+                    event_formatted_summary,
+                    sender_id,
+                };
+                if timeline_update_sender.send(update).is_err() {
+                    warn!(
+                        "Failed to send thread reply update for room {}",
+                        room.room_id()
+                    );
+                    return;
+                }
+                broadcast_event(UIUpdateMessage::RefreshUI);
+            }
+        },
+    );
+    room.client().event_handler_drop_guard(handle)
+}
@@ -6,12 +6,17 @@ use serde::{Serialize, Serializer, ser::SerializeStruct};
 
 /// Wether the user has logged in, or is in another state.
 #[derive(Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum LoginState {
     Initiating,
     Restored,
     AwaitingForHomeserver,
     LoggedIn,
+    /// The login failed, the user has to select a homeserver again to retry.
+    Failed {
+        reason: String,
+    },
 }
 
 impl LoginState {
@@ -21,12 +26,18 @@ impl LoginState {
             LoginState::Restored => "restored".to_owned(),
             LoginState::AwaitingForHomeserver => "awaitingForHomeserver".to_owned(),
             LoginState::LoggedIn => "loggedIn".to_owned(),
+            LoginState::Failed { .. } => "failed".to_owned(),
         }
     }
 }
 
 /// Wether this Matrix client session has been verified or not
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(type = r#"{ verificationState: "unknown" | "verified" | "unverified" }"#)
+)]
 pub struct FrontendVerificationState(VerificationState);
 
 impl Deref for FrontendVerificationState {
@@ -77,6 +88,11 @@ impl Serialize for FrontendVerificationState {
 }
 
 /// Wether the matrix-sdk-ui Sync Service is running or not.
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(type = r#"{ syncServiceState: "error" | "idle" | "offline" | "running" | "terminated" }"#)
+)]
 pub struct FrontendSyncServiceState(sync_service::State);
 
 impl Deref for FrontendSyncServiceState {
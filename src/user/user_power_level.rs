@@ -16,6 +16,13 @@ use serde::{Serialize, Serializer};
 bitflags! {
     /// The powers that a user has in a given room.
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "ts-rs",
+        derive(ts_rs::TS),
+        ts(
+            type = r#"Array<"ban" | "invite" | "kick" | "redact" | "notifyRoom" | "location" | "message" | "reaction" | "roomMessage" | "roomRedaction" | "sticker" | "roomAvatar" | "roomName" | "roomPinnedEvents" | "roomTopic">"#
+        )
+    )]
     pub struct UserPowerLevels: u64 {
         const Ban = 1 << 0;
         const Invite = 1 << 1;
@@ -257,6 +264,11 @@ impl Serialize for UserPowerLevels {
 
 // New type pattern to add the msgtype field to serialization
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(ts_rs::TS),
+    ts(type = "{ userPowerLevel: number | true }")
+)]
 pub struct FrontendUserPowerLevel(UserPowerLevel);
 
 impl Deref for FrontendUserPowerLevel {
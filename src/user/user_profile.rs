@@ -237,7 +237,7 @@ pub fn with_sender(
                     user_id: entry.key().clone(),
                     room_id: room_id.cloned(),
                     local_only: false,
-                    sender: Some(sender),
+                    sender: Some(sender.into()),
                 });
                 entry.insert(UserProfileCacheEntry::Requested);
             }
@@ -292,12 +292,15 @@ pub fn _clear_user_profile_cache() {
 
 /// Information retrieved about a user: their displayable name, ID, and known avatar state.
 #[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 pub struct UserProfile {
+    #[cfg_attr(feature = "ts-rs", ts(as = "String"))]
     pub user_id: OwnedUserId,
     /// The user's default display name, if set.
     /// Note that a user may have per-room display names,
     /// so this should be considered a fallback.
     pub username: Option<String>,
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<String>"))]
     pub avatar: Option<OwnedMxcUri>,
 }
 impl UserProfile {
@@ -1,13 +1,25 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, iter::once, ops::Range};
 use tokio::sync::{broadcast, mpsc};
 use tokio::time::{Duration, sleep};
 use tracing::warn;
 
-use matrix_sdk::ruma::RoomId;
+use matrix_sdk::ruma::{
+    OwnedUserId, RoomId, UInt, UserId,
+    events::{
+        poll::{
+            start::{PollAnswersError, PollKind},
+            unstable_start::{
+                NewUnstablePollStartEventContent, UnstablePollAnswer,
+                UnstablePollStartContentBlock, UnstablePollStartEventContent,
+            },
+        },
+        room::message::{FormattedBody, RoomMessageEventContent},
+    },
+};
 use matrix_sdk_ui::timeline::{EventTimelineItem, TimelineDetails};
 
 use crate::events::timeline::TimelineKind;
-use crate::models::async_requests::{MatrixRequest, submit_async_request};
+use crate::models::async_requests::{MatrixRequest, TextMessageFormat, submit_async_request};
 use crate::models::events::DeviceGuessedType;
 
 /// Returns the sender's display name if available.
@@ -116,6 +128,309 @@ pub fn linkify(text: &str, is_html: bool) -> Cow<'_, str> {
     Cow::Owned(linkified_text)
 }
 
+/// Builds the content of a text message, with an HTML `formatted_body` if the message
+/// has any formatting or links.
+///
+/// The IDs of the `mentioned_users` found in the message are replaced by their display name
+/// in the plain text body, and by a matrix.to pill in the HTML body.
+pub fn text_message_content(
+    message: String,
+    format: TextMessageFormat,
+    mentioned_users: &[(OwnedUserId, String)],
+) -> RoomMessageEventContent {
+    match formatted_text(message, format, mentioned_users) {
+        (body, Some(html)) => RoomMessageEventContent::text_html(body, html),
+        (body, None) => RoomMessageEventContent::text_plain(body),
+    }
+}
+
+/// Builds the content of an emote (`/me`) message, formatted like a text message.
+pub fn emote_message_content(
+    message: String,
+    format: TextMessageFormat,
+) -> RoomMessageEventContent {
+    match formatted_text(message, format, &[]) {
+        (body, Some(html)) => RoomMessageEventContent::emote_html(body, html),
+        (body, None) => RoomMessageEventContent::emote_plain(body),
+    }
+}
+
+/// Returns the plain text body of a message, and its HTML body if it has any formatting or links.
+fn formatted_text(
+    message: String,
+    format: TextMessageFormat,
+    mentioned_users: &[(OwnedUserId, String)],
+) -> (String, Option<String>) {
+    let display_name = |_: &UserId, name: &str| name.to_owned();
+    match format {
+        TextMessageFormat::Plain => {
+            let body = replace_user_ids(
+                &message,
+                once(0..message.len()),
+                mentioned_users,
+                display_name,
+            );
+            (body, None)
+        }
+        TextMessageFormat::Markdown => {
+            let text_ranges = markdown_text_ranges(&message);
+            let body = replace_user_ids(
+                &message,
+                text_ranges.iter().cloned(),
+                mentioned_users,
+                display_name,
+            );
+            let markdown =
+                replace_user_ids(&message, text_ranges, mentioned_users, |user_id, name| {
+                    format!("[{}]({})", escape_markdown(name), user_id.matrix_to_uri())
+                });
+            let html = match FormattedBody::markdown(&markdown) {
+                Some(formatted) => Some(linkify(&formatted.body, true).into_owned()),
+                None => match linkify(&body, false) {
+                    Cow::Owned(html) => Some(html),
+                    Cow::Borrowed(_) => None,
+                },
+            };
+            (body, html)
+        }
+        TextMessageFormat::Html { formatted_body } => {
+            let body = replace_user_ids(
+                &message,
+                once(0..message.len()),
+                mentioned_users,
+                display_name,
+            );
+            let html = replace_user_ids(
+                &formatted_body,
+                html_text_ranges(&formatted_body),
+                mentioned_users,
+                |user_id, name| {
+                    format!(
+                        "<a href=\"{}\">{}</a>",
+                        htmlize::escape_attribute(user_id.matrix_to_uri().to_string()),
+                        htmlize::escape_text(name),
+                    )
+                },
+            );
+            (body, Some(linkify(&html, true).into_owned()))
+        }
+    }
+}
+
+/// Builds the content of a new poll, with a plain text fallback listing the answers
+/// for the clients that don't support polls.
+///
+/// Fails if there are less than 2 or more than 20 answers.
+pub fn poll_start_content(
+    question: String,
+    answers: Vec<String>,
+    kind: PollKind,
+    max_selections: u8,
+) -> Result<UnstablePollStartEventContent, PollAnswersError> {
+    let mut fallback_text = question.clone();
+    for (index, answer) in answers.iter().enumerate() {
+        fallback_text.push_str(&format!("\n{}. {answer}", index + 1));
+    }
+    let answers = answers
+        .into_iter()
+        .enumerate()
+        .map(|(index, answer)| UnstablePollAnswer::new(index.to_string(), answer))
+        .collect::<Vec<_>>();
+    let mut poll_start = UnstablePollStartContentBlock::new(question, answers.try_into()?);
+    poll_start.kind = kind;
+    poll_start.max_selections = UInt::from(max_selections.max(1));
+    Ok(UnstablePollStartEventContent::New(
+        NewUnstablePollStartEventContent::plain_text(fallback_text, poll_start),
+    ))
+}
+
+/// The number of values in the waveform of a voice message.
+const VOICE_WAVEFORM_LENGTH: usize = 100;
+
+/// Computes the waveform of a voice message from the amplitudes sampled while recording.
+///
+/// The amplitudes are averaged into at most [`VOICE_WAVEFORM_LENGTH`] buckets,
+/// then scaled so that the loudest bucket is 1.
+pub fn voice_waveform(amplitudes: &[f32]) -> Vec<f32> {
+    if amplitudes.is_empty() {
+        return Vec::new();
+    }
+    let bucket_size = amplitudes.len().div_ceil(VOICE_WAVEFORM_LENGTH);
+    let buckets: Vec<f32> = amplitudes
+        .chunks(bucket_size)
+        .map(|chunk| chunk.iter().map(|a| a.abs()).sum::<f32>() / chunk.len() as f32)
+        .collect();
+    let peak = buckets.iter().copied().fold(0.0, f32::max);
+    if peak <= 0.0 || !peak.is_finite() {
+        return vec![0.0; buckets.len()];
+    }
+    buckets.into_iter().map(|b| b / peak).collect()
+}
+
+/// Replaces the IDs of the given users in the `text_ranges` of `text` with the output of `replacement`.
+///
+/// Only whole user IDs are replaced: `@bob:example.org` is left untouched in
+/// `@bob:example.org.uk` or in `https://matrix.to/#/@bob:example.org`.
+/// The ranges must be sorted and must not overlap.
+fn replace_user_ids(
+    text: &str,
+    text_ranges: impl IntoIterator<Item = Range<usize>>,
+    users: &[(OwnedUserId, String)],
+    replacement: impl Fn(&UserId, &str) -> String,
+) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for range in text_ranges {
+        let mut index = range.start;
+        while let Some(offset) = text[index..range.end].find('@') {
+            let start = index + offset;
+            // The longest ID wins, in case some of them are prefixes of the others.
+            let mention = users
+                .iter()
+                .filter(|(user_id, _)| {
+                    let end = start + user_id.as_str().len();
+                    text[start..range.end].starts_with(user_id.as_str())
+                        && is_whole_user_id(text, start..end)
+                })
+                .max_by_key(|(user_id, _)| user_id.as_str().len());
+            match mention {
+                Some((user_id, name)) => {
+                    replaced.push_str(&text[last_end..start]);
+                    replaced.push_str(&replacement(user_id, name));
+                    last_end = start + user_id.as_str().len();
+                    index = last_end;
+                }
+                None => index = start + 1,
+            }
+        }
+    }
+    replaced.push_str(&text[last_end..]);
+    replaced
+}
+
+/// Returns true if the user ID found at `range` in `text` isn't part of a longer token,
+/// such as an email address, a URL or another user ID.
+fn is_whole_user_id(text: &str, range: Range<usize>) -> bool {
+    // The characters allowed in user IDs, and in the ports of their servers.
+    let is_user_id_char = |c: char| c.is_ascii_alphanumeric() || "._=-/+:@".contains(c);
+    let is_preceded_by_token = text[..range.start]
+        .chars()
+        .next_back()
+        .is_some_and(is_user_id_char);
+    // Trailing punctuation, like the period ending a sentence, isn't part of the ID.
+    let is_followed_by_token = text[range.end..]
+        .chars()
+        .take_while(|&c| is_user_id_char(c))
+        .any(|c| c.is_ascii_alphanumeric());
+    !is_preceded_by_token && !is_followed_by_token
+}
+
+/// Returns the ranges of `markdown` that are rendered as text, outside of code and links.
+fn markdown_text_ranges(markdown: &str) -> Vec<Range<usize>> {
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+    // The same options as the ones used to render the markdown, see `FormattedBody::markdown`.
+    const OPTIONS: Options = Options::ENABLE_TABLES.union(Options::ENABLE_STRIKETHROUGH);
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    // The number of the links, images and code blocks the current event is in.
+    let mut skipped_depth = 0usize;
+    for (event, range) in Parser::new_ext(markdown, OPTIONS).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link { .. } | Tag::Image { .. } | Tag::CodeBlock(_)) => {
+                skipped_depth += 1
+            }
+            Event::End(TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock) => {
+                skipped_depth = skipped_depth.saturating_sub(1)
+            }
+            // Text can be split in several events, e.g. around some unmatched emphasis markers.
+            Event::Text(_) if skipped_depth == 0 => match ranges.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => ranges.push(range),
+            },
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// Returns the ranges of `html` that are text, outside of tags, comments, code and links.
+fn html_text_ranges(html: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    // The number of the `a`, `code` and `pre` elements the current text is in.
+    let mut skipped_depth = 0usize;
+    let mut index = 0;
+    while index < html.len() {
+        let tag_start = html[index..]
+            .find('<')
+            .map_or(html.len(), |offset| index + offset);
+        if skipped_depth == 0 && tag_start > index {
+            ranges.push(index..tag_start);
+        }
+        if tag_start == html.len() {
+            break;
+        }
+        if html[tag_start..].starts_with("<!--") {
+            index = html[tag_start..]
+                .find("-->")
+                .map_or(html.len(), |offset| tag_start + offset + 3);
+            continue;
+        }
+
+        // Attribute values may contain a `>`.
+        let mut quote = None;
+        let tag_end = html[tag_start..]
+            .char_indices()
+            .find(|&(_, c)| match quote {
+                Some(q) => {
+                    if c == q {
+                        quote = None;
+                    }
+                    false
+                }
+                None if c == '"' || c == '\'' => {
+                    quote = Some(c);
+                    false
+                }
+                None => c == '>',
+            })
+            .map_or(html.len(), |(offset, _)| tag_start + offset + 1);
+        let tag = html[tag_start + 1..tag_end].trim_end_matches('>');
+        let (is_closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let name_len = tag
+            .find(|c: char| c.is_ascii_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_len];
+        if ["a", "code", "pre"]
+            .iter()
+            .any(|skipped| name.eq_ignore_ascii_case(skipped))
+        {
+            if is_closing {
+                skipped_depth = skipped_depth.saturating_sub(1);
+            } else if !tag.ends_with('/') {
+                skipped_depth += 1;
+            }
+        }
+        index = tag_end;
+    }
+    ranges
+}
+
+/// Escapes the markdown syntax in the given `text`.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 /// Returns true if the given `text` string ends with a valid href attribute opener.
 ///
 /// An href attribute looks like this: `href="http://example.com"`,.
@@ -249,3 +564,133 @@ pub(crate) enum VecDiff<T> {
     /// Truncate the list to the given length.
     Truncate { length: usize },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users() -> Vec<(OwnedUserId, String)> {
+        vec![
+            (
+                OwnedUserId::try_from("@bob:example.org").unwrap(),
+                "Bob".to_owned(),
+            ),
+            (
+                OwnedUserId::try_from("@bob:example.org.uk").unwrap(),
+                "Bobby".to_owned(),
+            ),
+        ]
+    }
+
+    fn replace_in_text(text: &str) -> String {
+        replace_user_ids(text, once(0..text.len()), &users(), |_, name| {
+            name.to_owned()
+        })
+    }
+
+    fn replace_in_markdown(markdown: &str) -> String {
+        replace_user_ids(
+            markdown,
+            markdown_text_ranges(markdown),
+            &users(),
+            |_, name| format!("[{name}]"),
+        )
+    }
+
+    fn replace_in_html(html: &str) -> String {
+        replace_user_ids(html, html_text_ranges(html), &users(), |_, name| {
+            format!("<b>{name}</b>")
+        })
+    }
+
+    #[test]
+    fn replaces_whole_user_ids() {
+        assert_eq!(
+            replace_in_text("@bob:example.org, hi @bob:example.org."),
+            "Bob, hi Bob."
+        );
+        assert_eq!(replace_in_text("(@bob:example.org)"), "(Bob)");
+        assert_eq!(replace_in_text("[@bob:example.org]"), "[Bob]");
+    }
+
+    #[test]
+    fn prefers_the_longest_user_id() {
+        assert_eq!(
+            replace_in_text("@bob:example.org.uk and @bob:example.org"),
+            "Bobby and Bob"
+        );
+    }
+
+    #[test]
+    fn ignores_prefixes_of_other_tokens() {
+        for text in [
+            "@bob:example.org.com",
+            "@bob:example.org:8448",
+            "alice@bob:example.org",
+            "https://matrix.to/#/@bob:example.org",
+        ] {
+            assert_eq!(replace_in_text(text), text);
+        }
+    }
+
+    #[test]
+    fn skips_markdown_code_and_links() {
+        assert_eq!(
+            replace_in_markdown("hi @bob:example.org, see `@bob:example.org`"),
+            "hi [Bob], see `@bob:example.org`"
+        );
+        assert_eq!(
+            replace_in_markdown("```\n@bob:example.org\n```\n@bob:example.org"),
+            "```\n@bob:example.org\n```\n[Bob]"
+        );
+        assert_eq!(
+            replace_in_markdown("[@bob:example.org](https://example.org) *@bob:example.org*"),
+            "[@bob:example.org](https://example.org) *[Bob]*"
+        );
+        assert_eq!(
+            replace_in_markdown("<https://matrix.to/#/@bob:example.org>"),
+            "<https://matrix.to/#/@bob:example.org>"
+        );
+    }
+
+    #[test]
+    fn skips_html_tags_code_and_links() {
+        assert_eq!(
+            replace_in_html("<p title=\"@bob:example.org\">@bob:example.org</p>"),
+            "<p title=\"@bob:example.org\"><b>Bob</b></p>"
+        );
+        assert_eq!(
+            replace_in_html(
+                "<code>@bob:example.org</code> <pre><code>@bob:example.org</code></pre> @bob:example.org"
+            ),
+            "<code>@bob:example.org</code> <pre><code>@bob:example.org</code></pre> <b>Bob</b>"
+        );
+        assert_eq!(
+            replace_in_html(
+                "<a href=\"https://example.org\">@bob:example.org</a><br/>@bob:example.org"
+            ),
+            "<a href=\"https://example.org\">@bob:example.org</a><br/><b>Bob</b>"
+        );
+        assert_eq!(
+            replace_in_html("<!-- @bob:example.org -->"),
+            "<!-- @bob:example.org -->"
+        );
+    }
+
+    #[test]
+    fn formats_markdown_mentions_as_links() {
+        let users = users();
+        let (body, html) = formatted_text(
+            "hi @bob:example.org `@bob:example.org`".to_owned(),
+            TextMessageFormat::Markdown,
+            &users[..1],
+        );
+        assert_eq!(body, "hi Bob `@bob:example.org`");
+        assert_eq!(
+            html.as_deref(),
+            Some(
+                "hi <a href=\"https://matrix.to/#/@bob:example.org\">Bob</a> <code>@bob:example.org</code>"
+            )
+        );
+    }
+}